@@ -4,5 +4,6 @@
 pub mod linker;
 pub mod lto;
 pub mod rpath;
+pub mod stack_sizes;
 pub mod symbol_export;
 pub mod write;
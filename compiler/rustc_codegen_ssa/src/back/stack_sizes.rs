@@ -0,0 +1,231 @@
+//! Support for `-Z print-stack-sizes`.
+//!
+//! The call graph and the source information of every function are recorded
+//! while the crate is being codegened, because the `TyCtxt` is gone by the
+//! time the object files exist. The frame sizes themselves are then read
+//! back from the `.stack_sizes` sections LLVM writes into those object files
+//! (see `-Z emit-stack-sizes`).
+
+use crate::CompiledModule;
+
+use object::{BinaryFormat, Object, ObjectSection, ObjectSymbol, RelocationTarget, SymbolKind};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_middle::mir::mono::{CodegenUnit, MonoItem};
+use rustc_middle::mir::TerminatorKind;
+use rustc_middle::ty::{self, Instance, InstanceDef, TyCtxt};
+use rustc_session::code_stats::{FnStackInfo, FrameSize};
+use rustc_session::Session;
+
+use std::fs;
+
+/// Records the call graph and source information of every function in `codegen_units`.
+pub fn record_fn_stack_infos<'tcx>(tcx: TyCtxt<'tcx>, codegen_units: &[CodegenUnit<'tcx>]) {
+    let mut seen = FxHashSet::default();
+    for cgu in codegen_units {
+        for mono_item in cgu.items().keys() {
+            let instance = match *mono_item {
+                MonoItem::Fn(instance) => instance,
+                MonoItem::Static(_) | MonoItem::GlobalAsm(_) => continue,
+            };
+            // Inline functions are instantiated in every codegen unit using them.
+            if !seen.insert(instance) {
+                continue;
+            }
+
+            let symbol_name = tcx.symbol_name(instance).name;
+            let def_id = instance.def_id();
+            let (callees, has_indirect_calls) = direct_callees(tcx, instance);
+            let info = FnStackInfo {
+                name: format!("{:#}", rustc_demangle::demangle(symbol_name)),
+                def_path: tcx.def_path_str(def_id),
+                span: tcx.sess.source_map().span_to_string(tcx.def_span(def_id)),
+                callees,
+                has_indirect_calls,
+            };
+            tcx.sess.code_stats.record_fn_stack_info(symbol_name.to_owned(), info);
+        }
+    }
+}
+
+/// Returns the symbol names of the functions `instance` calls directly, and
+/// whether it also calls functions that are only known at runtime.
+fn direct_callees<'tcx>(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>) -> (Vec<String>, bool) {
+    let param_env = ty::ParamEnv::reveal_all();
+    let body = tcx.instance_mir(instance.def);
+
+    let mut callees = Vec::new();
+    let mut has_indirect_calls = false;
+    let mut add_callee = |callee: Instance<'tcx>| match callee.def {
+        InstanceDef::Intrinsic(_) | InstanceDef::DropGlue(_, None) => {}
+        InstanceDef::Virtual(..) => has_indirect_calls = true,
+        _ => callees.push(tcx.symbol_name(callee).name.to_owned()),
+    };
+
+    for block in body.basic_blocks() {
+        match block.terminator().kind {
+            TerminatorKind::Call { ref func, .. } => {
+                let callee_ty = func.ty(body, tcx);
+                let callee_ty =
+                    instance.subst_mir_and_normalize_erasing_regions(tcx, param_env, callee_ty);
+                match *callee_ty.kind() {
+                    ty::FnDef(def_id, substs) => {
+                        match Instance::resolve(tcx, param_env, def_id, substs) {
+                            Ok(Some(callee)) => add_callee(callee),
+                            _ => has_indirect_calls = true,
+                        }
+                    }
+                    _ => has_indirect_calls = true,
+                }
+            }
+            TerminatorKind::Drop { ref place, .. }
+            | TerminatorKind::DropAndReplace { ref place, .. } => {
+                let ty = place.ty(body, tcx).ty;
+                let ty = instance.subst_mir_and_normalize_erasing_regions(tcx, param_env, ty);
+                add_callee(Instance::resolve_drop_in_place(tcx, ty));
+            }
+            _ => {}
+        }
+    }
+
+    callees.sort_unstable();
+    callees.dedup();
+    (callees, has_indirect_calls)
+}
+
+/// Reads the frame size of every function from the `.stack_sizes` sections of
+/// the object files of `modules`.
+pub fn record_frame_sizes(sess: &Session, modules: &[CompiledModule]) {
+    for module in modules {
+        let path = match module.object {
+            Some(ref path) => path,
+            None => continue,
+        };
+        let data = match fs::read(path) {
+            Ok(data) => data,
+            Err(err) => {
+                sess.err(&format!("failed to read `{}`: {}", path.display(), err));
+                continue;
+            }
+        };
+        let file = match object::File::parse(&data) {
+            Ok(file) => file,
+            Err(err) => {
+                sess.err(&format!("failed to parse `{}`: {}", path.display(), err));
+                continue;
+            }
+        };
+        if file.format() != BinaryFormat::Elf {
+            sess.warn("`-Z print-stack-sizes` is only supported for targets using ELF objects");
+            return;
+        }
+        if !file.sections().any(|s| s.name() == Ok(".stack_sizes")) {
+            sess.warn(&format!(
+                "`{}` has no `.stack_sizes` section, so the frame sizes of its functions \
+                 are unknown",
+                path.display()
+            ));
+        }
+        for (symbol_name, frame_size) in frame_sizes(&file) {
+            sess.code_stats.record_frame_size(symbol_name, frame_size);
+        }
+    }
+}
+
+/// Decodes the `.stack_sizes` sections of a relocatable ELF object.
+///
+/// Each entry is an address-sized relocation against the function followed
+/// by its frame size as ULEB128. Functions LLVM emitted no entry for have
+/// variable-sized objects in their frame, unless there is no such section at
+/// all, in which case nothing is known about any of the functions.
+fn frame_sizes<'data>(file: &object::File<'data>) -> FxHashMap<String, FrameSize> {
+    let has_stack_sizes = file.sections().any(|s| s.name() == Ok(".stack_sizes"));
+    let missing = if has_stack_sizes { FrameSize::Dynamic } else { FrameSize::Unknown };
+    let mut frame_sizes = FxHashMap::default();
+    for symbol in file.symbols() {
+        if symbol.kind() == SymbolKind::Text && symbol.is_definition() {
+            if let Ok(name) = symbol.name() {
+                frame_sizes.insert(name.to_owned(), missing);
+            }
+        }
+    }
+
+    let address_size = if file.is_64() { 8 } else { 4 };
+    for section in file.sections().filter(|s| s.name() == Ok(".stack_sizes")) {
+        let data = match section.data() {
+            Ok(data) => data,
+            Err(_) => continue,
+        };
+        let relocations: FxHashMap<_, _> = section.relocations().collect();
+
+        let mut offset = 0;
+        while offset + address_size <= data.len() {
+            let function = relocations
+                .get(&(offset as u64))
+                .and_then(|relocation| relocation_target(file, relocation, &data[offset..]));
+            offset += address_size;
+            let size = match read_uleb128(data, &mut offset) {
+                Some(size) => size,
+                None => break,
+            };
+            if let Some(function) = function {
+                frame_sizes.insert(function, FrameSize::Static(size));
+            }
+        }
+    }
+    frame_sizes
+}
+
+/// Returns the name of the function a `.stack_sizes` entry refers to.
+fn relocation_target<'data>(
+    file: &object::File<'data>,
+    relocation: &object::Relocation,
+    data: &[u8],
+) -> Option<String> {
+    let symbol = match relocation.target() {
+        RelocationTarget::Symbol(index) => file.symbol_by_index(index).ok()?,
+        _ => return None,
+    };
+    if symbol.kind() != SymbolKind::Section {
+        return symbol.name().ok().map(|name| name.to_owned());
+    }
+
+    // Local functions may be referenced through their section symbol instead.
+    let addend = if relocation.has_implicit_addend() {
+        let size = usize::from(relocation.size() / 8);
+        let bytes = data.get(..size).filter(|_| size <= 8)?;
+        let mut addend = 0;
+        for (i, &byte) in bytes.iter().enumerate() {
+            let shift = if file.is_little_endian() { i } else { size - 1 - i };
+            addend |= u64::from(byte) << (8 * shift);
+        }
+        addend
+    } else {
+        relocation.addend() as u64
+    };
+    let section_index = symbol.section_index()?;
+    file.symbols()
+        .find(|function| {
+            function.kind() == SymbolKind::Text
+                && function.section_index() == Some(section_index)
+                // Ignore the Thumb bit on ARM.
+                && function.address() & !1 == addend & !1
+        })
+        .and_then(|function| function.name().ok().map(|name| name.to_owned()))
+}
+
+fn read_uleb128(data: &[u8], offset: &mut usize) -> Option<u64> {
+    let mut result = 0;
+    let mut shift = 0;
+    loop {
+        let byte = *data.get(*offset)?;
+        *offset += 1;
+        result |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Some(result);
+        }
+        shift += 7;
+        if shift >= 64 {
+            return None;
+        }
+    }
+}
@@ -1,6 +1,7 @@
 use super::link::{self, ensure_removed};
 use super::linker::LinkerInfo;
 use super::lto::{self, SerializedModule};
+use super::stack_sizes;
 use super::symbol_export::symbol_name_for_instance_in_crate;
 
 use crate::{
@@ -1852,6 +1853,13 @@ pub fn join(self, sess: &Session) -> (CodegenResults, FxHashMap<WorkProductId, W
 
         sess.abort_if_errors();
 
+        // The object files might be removed by `produce_final_output_artifacts`.
+        if sess.opts.debugging_opts.print_stack_sizes.is_some() {
+            sess.time("record_frame_sizes", || {
+                stack_sizes::record_frame_sizes(sess, &compiled_modules.modules)
+            });
+        }
+
         let work_products =
             copy_all_cgu_workproducts_to_incr_comp_cache_dir(sess, &compiled_modules);
         produce_final_output_artifacts(sess, &compiled_modules, &self.output_filenames);
@@ -1,3 +1,4 @@
+use crate::back::stack_sizes;
 use crate::back::write::{
     compute_per_cgu_lto_type, start_async_codegen, submit_codegened_module_to_llvm,
     submit_post_lto_module_to_llvm, submit_pre_lto_module_to_llvm, ComputedLtoType, OngoingCodegen,
@@ -18,6 +19,7 @@
 use rustc_hir::def_id::{LocalDefId, LOCAL_CRATE};
 use rustc_hir::lang_items::LangItem;
 use rustc_index::vec::Idx;
+use rustc_middle::dep_graph::DepNode;
 use rustc_middle::middle::codegen_fn_attrs::CodegenFnAttrs;
 use rustc_middle::middle::cstore::EncodedMetadata;
 use rustc_middle::middle::cstore::{self, LinkagePreference};
@@ -27,7 +29,7 @@
 use rustc_middle::ty::layout::{FAT_PTR_ADDR, FAT_PTR_EXTRA};
 use rustc_middle::ty::query::Providers;
 use rustc_middle::ty::{self, Instance, Ty, TyCtxt};
-use rustc_session::cgu_reuse_tracker::CguReuse;
+use rustc_session::cgu_reuse_tracker::{CguRebuildReason, CguReuse};
 use rustc_session::config::{self, EntryFnType};
 use rustc_session::Session;
 use rustc_target::abi::{Align, LayoutOf, VariantIdx};
@@ -481,6 +483,12 @@ pub fn codegen_crate<B: ExtraBackendMethods>(
     // codegen units.
     let codegen_units = tcx.collect_and_partition_mono_items(LOCAL_CRATE).1;
 
+    if tcx.sess.opts.debugging_opts.print_stack_sizes.is_some() {
+        tcx.sess.time("record_fn_stack_infos", || {
+            stack_sizes::record_fn_stack_infos(tcx, codegen_units)
+        });
+    }
+
     // Force all codegen_unit queries so they are already either red or green
     // when compile_codegen_unit accesses them. We are not able to re-execute
     // the codegen_unit query from just the DepNode, so an unknown color would
@@ -850,6 +858,11 @@ fn determine_cgu_reuse<'tcx>(tcx: TyCtxt<'tcx>, cgu: &CodegenUnit<'tcx>) -> CguR
     if tcx.dep_graph.previous_work_product(work_product_id).is_none() {
         // We don't have anything cached for this CGU. This can happen
         // if the CGU did not exist in the previous session.
+        if tcx.sess.opts.debugging_opts.incremental_explain.is_some() {
+            tcx.sess
+                .cgu_reuse_tracker
+                .set_rebuild_reason(&cgu.name().as_str(), CguRebuildReason::NotInPreviousSession);
+        }
         return CguReuse::No;
     }
 
@@ -880,6 +893,25 @@ fn determine_cgu_reuse<'tcx>(tcx: TyCtxt<'tcx>, cgu: &CodegenUnit<'tcx>) -> CguR
             _ => CguReuse::PreLto,
         }
     } else {
+        if tcx.sess.opts.debugging_opts.incremental_explain.is_some() {
+            let reason = explain_cgu_rebuild(tcx, &dep_node);
+            tcx.sess.cgu_reuse_tracker.set_rebuild_reason(&cgu.name().as_str(), reason);
+        }
         CguReuse::No
     }
 }
+
+/// Finds the changed input that kept the `CompileCodegenUnit` dep-node of a
+/// codegen unit from being marked green, for `-Z incremental-explain`.
+fn explain_cgu_rebuild(tcx: TyCtxt<'_>, dep_node: &DepNode) -> CguRebuildReason {
+    let path = tcx.dep_graph.red_dependency_path(dep_node);
+    match path.last() {
+        None => CguRebuildReason::Unknown,
+        Some(input) if tcx.dep_graph.is_red(input) => CguRebuildReason::InputChanged {
+            path: path.iter().map(|dep_node| format!("{:?}", dep_node)).collect(),
+        },
+        Some(dep_node) => {
+            CguRebuildReason::DependencyRemoved { dep_node: format!("{:?}", dep_node) }
+        }
+    }
+}
@@ -16,11 +16,19 @@ pub struct Expression {
     region: Option<CodeRegion>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct BranchRegion {
+    true_operand: ExpressionOperandId,
+    false_operand: ExpressionOperandId,
+    region: CodeRegion,
+}
+
 /// Collects all of the coverage regions associated with (a) injected counters, (b) counter
-/// expressions (additions or subtraction), and (c) unreachable regions (always counted as zero),
-/// for a given Function. Counters and counter expressions have non-overlapping `id`s because they
-/// can both be operands in an expression. This struct also stores the `function_source_hash`,
-/// computed during instrumentation, and forwarded with counters.
+/// expressions (additions or subtraction), (c) unreachable regions (always counted as zero), and
+/// (d) branch regions (a pair of counters or expressions), for a given Function. Counters and
+/// counter expressions have non-overlapping `id`s because they can both be operands in an
+/// expression. This struct also stores the `function_source_hash`, computed during
+/// instrumentation, and forwarded with counters.
 ///
 /// Note, it may be important to understand LLVM's definitions of `unreachable` regions versus "gap
 /// regions" (or "gap areas"). A gap region is a code region within a counted region (either counter
@@ -35,6 +43,7 @@ pub struct FunctionCoverage<'tcx> {
     counters: IndexVec<CounterValueReference, Option<CodeRegion>>,
     expressions: IndexVec<InjectedExpressionIndex, Option<Expression>>,
     unreachable_regions: Vec<CodeRegion>,
+    branch_regions: Vec<BranchRegion>,
 }
 
 impl<'tcx> FunctionCoverage<'tcx> {
@@ -61,6 +70,7 @@ fn create(tcx: TyCtxt<'tcx>, instance: Instance<'tcx>, is_used: bool) -> Self {
             counters: IndexVec::from_elem_n(None, coverageinfo.num_counters as usize),
             expressions: IndexVec::from_elem_n(None, coverageinfo.num_expressions as usize),
             unreachable_regions: Vec::new(),
+            branch_regions: Vec::new(),
         }
     }
 
@@ -132,18 +142,37 @@ pub fn add_unreachable_region(&mut self, region: CodeRegion) {
         self.unreachable_regions.push(region)
     }
 
+    /// Add a branch region, counting how often a condition was `true` and `false`. Like expression
+    /// operands, the operands can reference either counters or expressions, and are translated
+    /// only during code generation.
+    pub fn add_branch_region(
+        &mut self,
+        true_operand: ExpressionOperandId,
+        false_operand: ExpressionOperandId,
+        region: CodeRegion,
+    ) {
+        let branch_region = BranchRegion { true_operand, false_operand, region };
+        if !self.branch_regions.contains(&branch_region) {
+            self.branch_regions.push(branch_region)
+        }
+    }
+
     /// Return the source hash, generated from the HIR node structure, and used to indicate whether
     /// or not the source code structure changed between different compilations.
     pub fn source_hash(&self) -> u64 {
         self.source_hash
     }
 
-    /// Generate an array of CounterExpressions, and an iterator over all `Counter`s and their
-    /// associated `Regions` (from which the LLVM-specific `CoverageMapGenerator` will create
-    /// `CounterMappingRegion`s.
+    /// Generate an array of CounterExpressions, an iterator over all `Counter`s and their
+    /// associated `Regions`, and the `true` and `false` `Counter`s of each branch region (from
+    /// which the LLVM-specific `CoverageMapGenerator` will create `CounterMappingRegion`s.
     pub fn get_expressions_and_counter_regions<'a>(
         &'a self,
-    ) -> (Vec<CounterExpression>, impl Iterator<Item = (Counter, &'a CodeRegion)>) {
+    ) -> (
+        Vec<CounterExpression>,
+        impl Iterator<Item = (Counter, &'a CodeRegion)>,
+        Vec<(Counter, Counter, &'a CodeRegion)>,
+    ) {
         assert!(
             self.source_hash != 0 || !self.is_used,
             "No counters provided the source_hash for used function: {:?}",
@@ -151,12 +180,14 @@ pub fn get_expressions_and_counter_regions<'a>(
         );
 
         let counter_regions = self.counter_regions();
-        let (counter_expressions, expression_regions) = self.expressions_with_regions();
+        let (counter_expressions, expression_regions, new_indexes) =
+            self.expressions_with_regions();
         let unreachable_regions = self.unreachable_regions();
+        let branch_regions = self.branch_regions(&new_indexes);
 
         let counter_regions =
             counter_regions.chain(expression_regions.into_iter().chain(unreachable_regions));
-        (counter_expressions, counter_regions)
+        (counter_expressions, counter_regions, branch_regions)
     }
 
     fn counter_regions<'a>(&'a self) -> impl Iterator<Item = (Counter, &'a CodeRegion)> {
@@ -169,64 +200,15 @@ fn counter_regions<'a>(&'a self) -> impl Iterator<Item = (Counter, &'a CodeRegio
 
     fn expressions_with_regions(
         &'a self,
-    ) -> (Vec<CounterExpression>, impl Iterator<Item = (Counter, &'a CodeRegion)>) {
+    ) -> (
+        Vec<CounterExpression>,
+        impl Iterator<Item = (Counter, &'a CodeRegion)>,
+        IndexVec<InjectedExpressionIndex, Option<MappedExpressionIndex>>,
+    ) {
         let mut counter_expressions = Vec::with_capacity(self.expressions.len());
         let mut expression_regions = Vec::with_capacity(self.expressions.len());
         let mut new_indexes = IndexVec::from_elem_n(None, self.expressions.len());
 
-        // This closure converts any `Expression` operand (`lhs` or `rhs` of the `Op::Add` or
-        // `Op::Subtract` operation) into its native `llvm::coverage::Counter::CounterKind` type
-        // and value. Operand ID value `0` maps to `CounterKind::Zero`; values in the known range
-        // of injected LLVM counters map to `CounterKind::CounterValueReference` (and the value
-        // matches the injected counter index); and any other value is converted into a
-        // `CounterKind::Expression` with the expression's `new_index`.
-        //
-        // Expressions will be returned from this function in a sequential vector (array) of
-        // `CounterExpression`, so the expression IDs must be mapped from their original,
-        // potentially sparse set of indexes, originally in reverse order from `u32::MAX`.
-        //
-        // An `Expression` as an operand will have already been encountered as an `Expression` with
-        // operands, so its new_index will already have been generated (as a 1-up index value).
-        // (If an `Expression` as an operand does not have a corresponding new_index, it was
-        // probably optimized out, after the expression was injected into the MIR, so it will
-        // get a `CounterKind::Zero` instead.)
-        //
-        // In other words, an `Expression`s at any given index can include other expressions as
-        // operands, but expression operands can only come from the subset of expressions having
-        // `expression_index`s lower than the referencing `Expression`. Therefore, it is
-        // reasonable to look up the new index of an expression operand while the `new_indexes`
-        // vector is only complete up to the current `ExpressionIndex`.
-        let id_to_counter = |new_indexes: &IndexVec<
-            InjectedExpressionIndex,
-            Option<MappedExpressionIndex>,
-        >,
-                             id: ExpressionOperandId| {
-            if id == ExpressionOperandId::ZERO {
-                Some(Counter::zero())
-            } else if id.index() < self.counters.len() {
-                debug_assert!(
-                    id.index() > 0,
-                    "ExpressionOperandId indexes for counters are 1-based, but this id={}",
-                    id.index()
-                );
-                // Note: Some codegen-injected Counters may be only referenced by `Expression`s,
-                // and may not have their own `CodeRegion`s,
-                let index = CounterValueReference::from(id.index());
-                // Note, the conversion to LLVM `Counter` adjusts the index to be zero-based.
-                Some(Counter::counter_value_reference(index))
-            } else {
-                let index = self.expression_index(u32::from(id));
-                self.expressions
-                    .get(index)
-                    .expect("expression id is out of range")
-                    .as_ref()
-                    // If an expression was optimized out, assume it would have produced a count
-                    // of zero. This ensures that expressions dependent on optimized-out
-                    // expressions are still valid.
-                    .map_or(Some(Counter::zero()), |_| new_indexes[index].map(Counter::expression))
-            }
-        };
-
         for (original_index, expression) in
             self.expressions.iter_enumerated().filter_map(|(original_index, entry)| {
                 // Option::map() will return None to filter out missing expressions. This may happen
@@ -237,9 +219,10 @@ fn expressions_with_regions(
             let optional_region = &expression.region;
             let Expression { lhs, op, rhs, .. } = *expression;
 
-            if let Some(Some((lhs_counter, mut rhs_counter))) = id_to_counter(&new_indexes, lhs)
-                .map(|lhs_counter| {
-                    id_to_counter(&new_indexes, rhs).map(|rhs_counter| (lhs_counter, rhs_counter))
+            if let Some(Some((lhs_counter, mut rhs_counter))) =
+                self.operand_to_counter(&new_indexes, lhs).map(|lhs_counter| {
+                    self.operand_to_counter(&new_indexes, rhs)
+                        .map(|rhs_counter| (lhs_counter, rhs_counter))
                 })
             {
                 if lhs_counter.is_zero() && op.is_subtract() {
@@ -324,13 +307,81 @@ fn expressions_with_regions(
                 );
             }
         }
-        (counter_expressions, expression_regions.into_iter())
+        (counter_expressions, expression_regions.into_iter(), new_indexes)
+    }
+
+    /// Converts any `Expression` operand (`lhs` or `rhs` of the `Op::Add` or `Op::Subtract`
+    /// operation, or either side of a branch region) into its native
+    /// `llvm::coverage::Counter::CounterKind` type and value. Operand ID value `0` maps to
+    /// `CounterKind::Zero`; values in the known range of injected LLVM counters map to
+    /// `CounterKind::CounterValueReference` (and the value matches the injected counter index); and
+    /// any other value is converted into a `CounterKind::Expression` with the expression's
+    /// `new_index`.
+    ///
+    /// Expressions will be returned from `expressions_with_regions()` in a sequential vector
+    /// (array) of `CounterExpression`, so the expression IDs must be mapped from their original,
+    /// potentially sparse set of indexes, originally in reverse order from `u32::MAX`.
+    ///
+    /// An `Expression` as an operand will have already been encountered as an `Expression` with
+    /// operands, so its new_index will already have been generated (as a 1-up index value).
+    /// (If an `Expression` as an operand does not have a corresponding new_index, it was
+    /// probably optimized out, after the expression was injected into the MIR, so it will
+    /// get a `CounterKind::Zero` instead.)
+    ///
+    /// In other words, an `Expression`s at any given index can include other expressions as
+    /// operands, but expression operands can only come from the subset of expressions having
+    /// `expression_index`s lower than the referencing `Expression`. Therefore, it is
+    /// reasonable to look up the new index of an expression operand while the `new_indexes`
+    /// vector is only complete up to the current `ExpressionIndex`.
+    fn operand_to_counter(
+        &self,
+        new_indexes: &IndexVec<InjectedExpressionIndex, Option<MappedExpressionIndex>>,
+        id: ExpressionOperandId,
+    ) -> Option<Counter> {
+        if id == ExpressionOperandId::ZERO {
+            Some(Counter::zero())
+        } else if id.index() < self.counters.len() {
+            debug_assert!(
+                id.index() > 0,
+                "ExpressionOperandId indexes for counters are 1-based, but this id={}",
+                id.index()
+            );
+            // Note: Some codegen-injected Counters may be only referenced by `Expression`s,
+            // and may not have their own `CodeRegion`s,
+            let index = CounterValueReference::from(id.index());
+            // Note, the conversion to LLVM `Counter` adjusts the index to be zero-based.
+            Some(Counter::counter_value_reference(index))
+        } else {
+            let index = self.expression_index(u32::from(id));
+            self.expressions
+                .get(index)
+                .expect("expression id is out of range")
+                .as_ref()
+                // If an expression was optimized out, assume it would have produced a count
+                // of zero. This ensures that expressions dependent on optimized-out
+                // expressions are still valid.
+                .map_or(Some(Counter::zero()), |_| new_indexes[index].map(Counter::expression))
+        }
     }
 
     fn unreachable_regions<'a>(&'a self) -> impl Iterator<Item = (Counter, &'a CodeRegion)> {
         self.unreachable_regions.iter().map(|region| (Counter::zero(), region))
     }
 
+    fn branch_regions<'a>(
+        &'a self,
+        new_indexes: &IndexVec<InjectedExpressionIndex, Option<MappedExpressionIndex>>,
+    ) -> Vec<(Counter, Counter, &'a CodeRegion)> {
+        self.branch_regions
+            .iter()
+            .filter_map(|BranchRegion { true_operand, false_operand, region }| {
+                let true_counter = self.operand_to_counter(new_indexes, *true_operand)?;
+                let false_counter = self.operand_to_counter(new_indexes, *false_operand)?;
+                Some((true_counter, false_counter, region))
+            })
+            .collect()
+    }
+
     fn expression_index(&self, id_descending_from_max: u32) -> InjectedExpressionIndex {
         debug_assert!(id_descending_from_max >= self.counters.len() as u32);
         InjectedExpressionIndex::from(u32::MAX - id_descending_from_max)
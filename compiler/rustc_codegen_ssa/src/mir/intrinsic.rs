@@ -547,6 +547,16 @@ pub fn codegen_intrinsic_call(
                 }
             }
 
+            sym::const_allocate => {
+                // Memory can only be allocated this way at compile time.
+                bx.const_null(llret_ty)
+            }
+
+            sym::const_deallocate => {
+                // Nothing was allocated at runtime, so there is nothing to free.
+                return;
+            }
+
             sym::ptr_offset_from => {
                 let ty = substs.type_at(0);
                 let pointee_size = bx.layout_of(ty).size;
@@ -54,4 +54,14 @@ fn add_coverage_counter_expression(
     /// Returns true if the region was added to the coverage map; false if `-Z instrument-coverage`
     /// is not enabled (a coverage map is not being generated).
     fn add_coverage_unreachable(&mut self, instance: Instance<'tcx>, region: CodeRegion) -> bool;
+
+    /// Returns true if the branch region was added to the coverage map; false if
+    /// `-Z instrument-coverage` is not enabled (a coverage map is not being generated).
+    fn add_coverage_branch(
+        &mut self,
+        instance: Instance<'tcx>,
+        true_operand: ExpressionOperandId,
+        false_operand: ExpressionOperandId,
+        region: CodeRegion,
+    ) -> bool;
 }
@@ -80,6 +80,13 @@
 //! for `DepNodeIndex as StringId`.
 //!
 //! [mm]: https://github.com/rust-lang/measureme/
+//!
+//!
+//! ## Built-in Output Formats
+//!
+//! With `-Z self-profile-format`, the `SelfProfiler` can instead write the
+//! events as Chrome trace events or as a summary table, without the tools from
+//! the `measureme` project. See the `export` module for how this works.
 
 use crate::cold_path;
 use crate::fx::FxHashMap;
@@ -89,13 +96,20 @@
 use std::convert::Into;
 use std::error::Error;
 use std::fs;
+use std::io;
 use std::path::Path;
 use std::process;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use measureme::{EventId, EventIdBuilder, Profiler, SerializableString, StringId};
+use measureme::event_id::SEPARATOR_BYTE;
+use measureme::{EventId, Profiler, SerializableString, StringComponent, StringId};
 use parking_lot::RwLock;
+use smallvec::SmallVec;
+
+use export::{EventKinds, EventLog, IntervalGuard};
+
+mod export;
 
 bitflags::bitflags! {
     struct EventFilter: u32 {
@@ -134,6 +148,44 @@ struct EventFilter: u32 {
     ("llvm", EventFilter::LLVM),
 ];
 
+/// The format `-Z self-profile` writes the profiling data in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SelfProfileFormat {
+    /// The binary format of `measureme`, read with the tools of the
+    /// `measureme` project.
+    Measureme,
+    /// Chrome trace events, as written by the `crox` tool.
+    ChromeJson,
+    /// A table of the time spent per activity, as printed by the `summarize` tool.
+    Summary,
+}
+
+/// The strings that can be allocated in the profiling data. Besides being
+/// serializable by `measureme`, they can be turned back into text for the
+/// built-in output formats.
+pub trait ProfileString: SerializableString {
+    /// Appends the text of the string to `out`, using `resolve` to append the
+    /// text of the strings it refers to.
+    fn append_to(&self, out: &mut String, resolve: &mut dyn FnMut(StringId, &mut String));
+}
+
+impl ProfileString for str {
+    fn append_to(&self, out: &mut String, _: &mut dyn FnMut(StringId, &mut String)) {
+        out.push_str(self);
+    }
+}
+
+impl ProfileString for [StringComponent<'_>] {
+    fn append_to(&self, out: &mut String, resolve: &mut dyn FnMut(StringId, &mut String)) {
+        for component in self {
+            match *component {
+                StringComponent::Value(s) => out.push_str(s),
+                StringComponent::Ref(id) => resolve(id, out),
+            }
+        }
+    }
+}
+
 /// Something that uniquely identifies a query invocation.
 pub struct QueryInvocationId(pub u32);
 
@@ -260,7 +312,7 @@ pub fn generic_activity_with_arg<A>(
         A: Borrow<str> + Into<String>,
     {
         self.exec(EventFilter::GENERIC_ACTIVITIES, |profiler| {
-            let builder = EventIdBuilder::new(&profiler.profiler);
+            let builder = profiler.event_id_builder();
             let event_label = profiler.get_or_alloc_cached_string(event_label);
             let event_id = if profiler.event_filter_mask.contains(EventFilter::FUNCTION_ARGS) {
                 let event_arg = profiler.get_or_alloc_cached_string(event_arg);
@@ -279,7 +331,7 @@ pub fn generic_activity_with_args(
         event_args: &[String],
     ) -> TimingGuard<'_> {
         self.exec(EventFilter::GENERIC_ACTIVITIES, |profiler| {
-            let builder = EventIdBuilder::new(&profiler.profiler);
+            let builder = profiler.event_id_builder();
             let event_label = profiler.get_or_alloc_cached_string(event_label);
             let event_id = if profiler.event_filter_mask.contains(EventFilter::FUNCTION_ARGS) {
                 let event_args: Vec<_> = event_args
@@ -348,11 +400,16 @@ fn instant_query_event(
             let event_id = StringId::new_virtual(query_invocation_id.0);
             let thread_id = std::thread::current().id().as_u64().get() as u32;
 
-            profiler.profiler.record_instant_event(
-                event_kind(profiler),
-                EventId::from_virtual(event_id),
-                thread_id,
-            );
+            if let Some(ref raw_profiler) = profiler.profiler {
+                raw_profiler.record_instant_event(
+                    event_kind(profiler),
+                    EventId::from_virtual(event_id),
+                    thread_id,
+                );
+            }
+            if let Some(ref event_log) = profiler.event_log {
+                event_log.record_instant(event_kind(profiler), event_id, thread_id);
+            }
 
             TimingGuard::none()
         }));
@@ -380,7 +437,9 @@ pub fn get_self_profiler(&self) -> Option<Arc<SelfProfiler>> {
 }
 
 pub struct SelfProfiler {
-    profiler: Profiler,
+    /// The `measureme` profiler, unless one of the built-in output formats was
+    /// requested.
+    profiler: Option<Profiler>,
     event_filter_mask: EventFilter,
 
     string_cache: RwLock<FxHashMap<String, StringId>>,
@@ -390,6 +449,10 @@ pub struct SelfProfiler {
     incremental_load_result_event_kind: StringId,
     query_blocked_event_kind: StringId,
     query_cache_hit_event_kind: StringId,
+
+    /// The strings and events for the built-in output formats, if one of them
+    /// was requested.
+    event_log: Option<EventLog>,
 }
 
 impl SelfProfiler {
@@ -397,19 +460,37 @@ pub fn new(
         output_directory: &Path,
         crate_name: Option<&str>,
         event_filters: &Option<Vec<String>>,
+        format: SelfProfileFormat,
     ) -> Result<SelfProfiler, Box<dyn Error + Send + Sync>> {
         fs::create_dir_all(output_directory)?;
 
         let crate_name = crate_name.unwrap_or("unknown-crate");
-        let filename = format!("{}-{}.rustc_profile", crate_name, process::id());
-        let path = output_directory.join(&filename);
-        let profiler = Profiler::new(&path)?;
+        let extension = match format {
+            SelfProfileFormat::Measureme => None,
+            SelfProfileFormat::ChromeJson => Some("json"),
+            SelfProfileFormat::Summary => Some("txt"),
+        };
+        let (profiler, event_log) = match extension {
+            None => {
+                let filename = format!("{}-{}.rustc_profile", crate_name, process::id());
+                (Some(Profiler::new(&output_directory.join(&filename))?), None)
+            }
+            Some(extension) => {
+                let filename = format!("{}-{}.{}", crate_name, process::id(), extension);
+                (None, Some(EventLog::new(format, output_directory.join(&filename))))
+            }
+        };
+        let alloc_string = |s: &str| match (&profiler, &event_log) {
+            (Some(profiler), _) => profiler.alloc_string(s),
+            (None, Some(event_log)) => event_log.alloc_string(s),
+            (None, None) => unreachable!(),
+        };
 
-        let query_event_kind = profiler.alloc_string("Query");
-        let generic_activity_event_kind = profiler.alloc_string("GenericActivity");
-        let incremental_load_result_event_kind = profiler.alloc_string("IncrementalLoadResult");
-        let query_blocked_event_kind = profiler.alloc_string("QueryBlocked");
-        let query_cache_hit_event_kind = profiler.alloc_string("QueryCacheHit");
+        let query_event_kind = alloc_string("Query");
+        let generic_activity_event_kind = alloc_string("GenericActivity");
+        let incremental_load_result_event_kind = alloc_string("IncrementalLoadResult");
+        let query_blocked_event_kind = alloc_string("QueryBlocked");
+        let query_cache_hit_event_kind = alloc_string("QueryCacheHit");
 
         let mut event_filter_mask = EventFilter::empty();
 
@@ -453,13 +534,18 @@ pub fn new(
             incremental_load_result_event_kind,
             query_blocked_event_kind,
             query_cache_hit_event_kind,
+            event_log,
         })
     }
 
     /// Allocates a new string in the profiling data. Does not do any caching
     /// or deduplication.
-    pub fn alloc_string<STR: SerializableString + ?Sized>(&self, s: &STR) -> StringId {
-        self.profiler.alloc_string(s)
+    pub fn alloc_string<STR: ProfileString + ?Sized>(&self, s: &STR) -> StringId {
+        match (&self.profiler, &self.event_log) {
+            (Some(profiler), _) => profiler.alloc_string(s),
+            (None, Some(event_log)) => event_log.alloc_string(s),
+            (None, None) => unreachable!(),
+        }
     }
 
     /// Gets a `StringId` for the given string. This method makes sure that
@@ -485,7 +571,7 @@ pub fn get_or_alloc_cached_string<A>(&self, s: A) -> StringId
         match string_cache.entry(s.into()) {
             Entry::Occupied(e) => *e.get(),
             Entry::Vacant(e) => {
-                let string_id = self.profiler.alloc_string(&e.key()[..]);
+                let string_id = self.alloc_string(&e.key()[..]);
                 *e.insert(string_id)
             }
         }
@@ -493,7 +579,12 @@ pub fn get_or_alloc_cached_string<A>(&self, s: A) -> StringId
 
     pub fn map_query_invocation_id_to_string(&self, from: QueryInvocationId, to: StringId) {
         let from = StringId::new_virtual(from.0);
-        self.profiler.map_virtual_to_concrete_string(from, to);
+        if let Some(ref profiler) = self.profiler {
+            profiler.map_virtual_to_concrete_string(from, to);
+        }
+        if let Some(ref event_log) = self.event_log {
+            event_log.map_virtual_string(from, to);
+        }
     }
 
     pub fn bulk_map_query_invocation_id_to_single_string<I>(&self, from: I, to: StringId)
@@ -501,7 +592,13 @@ pub fn bulk_map_query_invocation_id_to_single_string<I>(&self, from: I, to: Stri
         I: Iterator<Item = QueryInvocationId> + ExactSizeIterator,
     {
         let from = from.map(|qid| StringId::new_virtual(qid.0));
-        self.profiler.bulk_map_virtual_to_single_concrete_string(from, to);
+        if let Some(ref profiler) = self.profiler {
+            profiler.bulk_map_virtual_to_single_concrete_string(from, to);
+        } else if let Some(ref event_log) = self.event_log {
+            for from in from {
+                event_log.map_virtual_string(from, to);
+            }
+        }
     }
 
     pub fn query_key_recording_enabled(&self) -> bool {
@@ -509,12 +606,59 @@ pub fn query_key_recording_enabled(&self) -> bool {
     }
 
     pub fn event_id_builder(&self) -> EventIdBuilder<'_> {
-        EventIdBuilder::new(&self.profiler)
+        EventIdBuilder { profiler: self }
+    }
+
+    /// Writes the data of the built-in output formats. This is called once at
+    /// the end of the compilation session; the `measureme` data is written
+    /// when the profiler is dropped instead.
+    pub fn finish(&self) -> io::Result<()> {
+        let kinds = EventKinds {
+            query_blocked: self.query_blocked_event_kind,
+            query_cache_hit: self.query_cache_hit_event_kind,
+            incremental_load_result: self.incremental_load_result_event_kind,
+        };
+        match self.event_log {
+            Some(ref event_log) => event_log.write(kinds),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Builds `EventId`s like `measureme::EventIdBuilder`, but through
+/// `SelfProfiler::alloc_string` so that the built-in output formats see them.
+pub struct EventIdBuilder<'p> {
+    profiler: &'p SelfProfiler,
+}
+
+impl EventIdBuilder<'_> {
+    pub fn from_label(&self, label: StringId) -> EventId {
+        // Just forward the string ID, a single identifier is a valid event ID.
+        EventId::from_label(label)
+    }
+
+    pub fn from_label_and_arg(&self, label: StringId, arg: StringId) -> EventId {
+        EventId::from_label(self.profiler.alloc_string(&[
+            StringComponent::Ref(label),
+            StringComponent::Value(SEPARATOR_BYTE),
+            StringComponent::Ref(arg),
+        ][..]))
+    }
+
+    pub fn from_label_and_args(&self, label: StringId, args: &[StringId]) -> EventId {
+        // Store up to 7 components on the stack: 1 label + 3 arguments + 3 argument separators.
+        let mut parts = SmallVec::<[StringComponent<'_>; 7]>::with_capacity(1 + args.len() * 2);
+        parts.push(StringComponent::Ref(label));
+        for arg in args {
+            parts.push(StringComponent::Value(SEPARATOR_BYTE));
+            parts.push(StringComponent::Ref(*arg));
+        }
+        EventId::from_label(self.profiler.alloc_string(&parts[..]))
     }
 }
 
 #[must_use]
-pub struct TimingGuard<'a>(Option<measureme::TimingGuard<'a>>);
+pub struct TimingGuard<'a>(Option<(Option<measureme::TimingGuard<'a>>, Option<IntervalGuard<'a>>)>);
 
 impl<'a> TimingGuard<'a> {
     #[inline]
@@ -524,19 +668,26 @@ pub fn start(
         event_id: EventId,
     ) -> TimingGuard<'a> {
         let thread_id = std::thread::current().id().as_u64().get() as u32;
-        let raw_profiler = &profiler.profiler;
-        let timing_guard =
-            raw_profiler.start_recording_interval_event(event_kind, event_id, thread_id);
-        TimingGuard(Some(timing_guard))
+        let logged = profiler.event_log.as_ref().map(|event_log| {
+            event_log.start_interval(event_kind, event_id.to_string_id(), thread_id)
+        });
+        let timing_guard = profiler.profiler.as_ref().map(|raw_profiler| {
+            raw_profiler.start_recording_interval_event(event_kind, event_id, thread_id)
+        });
+        TimingGuard(Some((timing_guard, logged)))
     }
 
     #[inline]
     pub fn finish_with_query_invocation_id(self, query_invocation_id: QueryInvocationId) {
-        if let Some(guard) = self.0 {
+        if let Some((guard, logged)) = self.0 {
             cold_path(|| {
                 let event_id = StringId::new_virtual(query_invocation_id.0);
-                let event_id = EventId::from_virtual(event_id);
-                guard.finish_with_override_event_id(event_id);
+                if let Some(logged) = logged {
+                    logged.finish_with_event_id(event_id);
+                }
+                if let Some(guard) = guard {
+                    guard.finish_with_override_event_id(EventId::from_virtual(event_id));
+                }
             });
         }
     }
@@ -0,0 +1,308 @@
+//! The built-in output formats of `-Z self-profile-format`.
+//!
+//! `measureme` writes a compact binary format that needs separately installed
+//! tools (`summarize`, `crox`, ...) to be read. For the other formats, the
+//! `SelfProfiler` keeps every string and event in memory instead, and writes
+//! them out when the compilation session finishes. This costs more memory and
+//! time than `measureme`, but needs no external tooling.
+
+use super::{ProfileString, SelfProfileFormat};
+use crate::fx::FxHashMap;
+
+use measureme::event_id::SEPARATOR_BYTE;
+use measureme::StringId;
+use parking_lot::Mutex;
+use rustc_serialize::json::Json;
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufWriter, Write};
+use std::mem;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Instant;
+
+/// The IDs of the strings allocated in an `EventLog` start above the range of
+/// virtual string IDs, which stand for query invocations.
+const FIRST_STRING_ID: u32 = u32::MAX / 2;
+
+struct RawEvent {
+    kind: StringId,
+    id: StringId,
+    thread_id: u32,
+    /// Nanoseconds since the profiler was created.
+    start: u64,
+    /// Nanoseconds since the profiler was created, or `None` for instant events.
+    end: Option<u64>,
+}
+
+/// The event kinds the summary has dedicated columns for.
+#[derive(Clone, Copy)]
+pub(super) struct EventKinds {
+    pub query_blocked: StringId,
+    pub query_cache_hit: StringId,
+    pub incremental_load_result: StringId,
+}
+
+pub(super) struct EventLog {
+    format: SelfProfileFormat,
+    path: PathBuf,
+    start: Instant,
+    next_string_id: AtomicU32,
+    /// The text of every string allocated in the profiling data.
+    strings: Mutex<FxHashMap<StringId, String>>,
+    /// The strings virtual string IDs (i.e. query invocations) are mapped to.
+    virtual_strings: Mutex<FxHashMap<StringId, StringId>>,
+    events: Mutex<Vec<RawEvent>>,
+}
+
+/// Records an interval event into the `EventLog` when dropped.
+pub(super) struct IntervalGuard<'a> {
+    log: &'a EventLog,
+    kind: StringId,
+    id: StringId,
+    thread_id: u32,
+    start: u64,
+}
+
+impl EventLog {
+    pub(super) fn new(format: SelfProfileFormat, path: PathBuf) -> EventLog {
+        EventLog {
+            format,
+            path,
+            start: Instant::now(),
+            next_string_id: AtomicU32::new(FIRST_STRING_ID),
+            strings: Default::default(),
+            virtual_strings: Default::default(),
+            events: Default::default(),
+        }
+    }
+
+    fn now(&self) -> u64 {
+        self.start.elapsed().as_nanos() as u64
+    }
+
+    pub(super) fn alloc_string<STR: ProfileString + ?Sized>(&self, s: &STR) -> StringId {
+        let id = StringId::new(self.next_string_id.fetch_add(1, Ordering::Relaxed));
+        let mut strings = self.strings.lock();
+        let mut text = String::new();
+        s.append_to(&mut text, &mut |id, out| {
+            if let Some(s) = strings.get(&id) {
+                out.push_str(s);
+            }
+        });
+        strings.insert(id, text);
+        id
+    }
+
+    pub(super) fn map_virtual_string(&self, from: StringId, to: StringId) {
+        self.virtual_strings.lock().insert(from, to);
+    }
+
+    pub(super) fn start_interval(
+        &self,
+        kind: StringId,
+        id: StringId,
+        thread_id: u32,
+    ) -> IntervalGuard<'_> {
+        IntervalGuard { log: self, kind, id, thread_id, start: self.now() }
+    }
+
+    pub(super) fn record_instant(&self, kind: StringId, id: StringId, thread_id: u32) {
+        let start = self.now();
+        self.events.lock().push(RawEvent { kind, id, thread_id, start, end: None });
+    }
+
+    /// Writes the events recorded so far in the requested format.
+    pub(super) fn write(&self, kinds: EventKinds) -> io::Result<()> {
+        let mut events = mem::take(&mut *self.events.lock());
+        events.sort_by_key(|event| (event.start, event.thread_id));
+        let strings = self.strings.lock();
+        let virtual_strings = self.virtual_strings.lock();
+        // Returns the text of an event kind or event ID.
+        let resolve = |id| {
+            let id = virtual_strings.get(&id).copied().unwrap_or(id);
+            strings.get(&id).map_or("<unknown>", |s: &String| &s[..])
+        };
+
+        let mut out = BufWriter::new(fs::File::create(&self.path)?);
+        match self.format {
+            SelfProfileFormat::Measureme => unreachable!("no events are logged for `measureme`"),
+            SelfProfileFormat::ChromeJson => write_chrome_json(&mut out, &events, resolve)?,
+            SelfProfileFormat::Summary => write_summary(&mut out, kinds, &events, resolve)?,
+        }
+        out.flush()
+    }
+}
+
+impl IntervalGuard<'_> {
+    /// Records the event with `id` as its event ID instead of the one it was
+    /// started with.
+    pub(super) fn finish_with_event_id(mut self, id: StringId) {
+        self.id = id;
+    }
+}
+
+impl Drop for IntervalGuard<'_> {
+    fn drop(&mut self) {
+        let end = self.log.now();
+        let event = RawEvent {
+            kind: self.kind,
+            id: self.id,
+            thread_id: self.thread_id,
+            start: self.start,
+            end: Some(end),
+        };
+        self.log.events.lock().push(event);
+    }
+}
+
+/// Writes the events in the Chrome trace event format, which can be loaded in
+/// `chrome://tracing` or <https://ui.perfetto.dev>. This is the same output as
+/// the `crox` tool.
+fn write_chrome_json<'s>(
+    out: &mut impl Write,
+    events: &[RawEvent],
+    resolve: impl Fn(StringId) -> &'s str,
+) -> io::Result<()> {
+    let pid = Json::U64(process::id().into());
+    writeln!(out, "{{\"traceEvents\":[")?;
+    for (i, event) in events.iter().enumerate() {
+        // Query keys and function arguments are appended to the label.
+        let mut parts = resolve(event.id).split(SEPARATOR_BYTE);
+        let name = parts.next().unwrap_or("");
+        let args: BTreeMap<String, Json> = parts
+            .enumerate()
+            .map(|(i, arg)| (format!("arg{}", i), Json::String(arg.to_owned())))
+            .collect();
+
+        let mut object = BTreeMap::new();
+        object.insert("name".to_owned(), Json::String(name.to_owned()));
+        object.insert("cat".to_owned(), Json::String(resolve(event.kind).to_owned()));
+        object.insert("ts".to_owned(), Json::F64(event.start as f64 / 1000.0));
+        match event.end {
+            Some(end) => {
+                object.insert("ph".to_owned(), Json::String("X".to_owned()));
+                object.insert("dur".to_owned(), Json::F64((end - event.start) as f64 / 1000.0));
+            }
+            None => {
+                object.insert("ph".to_owned(), Json::String("i".to_owned()));
+                object.insert("s".to_owned(), Json::String("t".to_owned()));
+            }
+        }
+        object.insert("pid".to_owned(), pid.clone());
+        object.insert("tid".to_owned(), Json::U64(event.thread_id.into()));
+        if !args.is_empty() {
+            object.insert("args".to_owned(), Json::Object(args));
+        }
+
+        let separator = if i + 1 < events.len() { "," } else { "" };
+        writeln!(out, "{}{}", Json::Object(object), separator)?;
+    }
+    writeln!(out, "],\"displayTimeUnit\":\"ms\"}}")
+}
+
+#[derive(Default)]
+struct SummaryRow {
+    self_time: u64,
+    time: u64,
+    count: u64,
+    cache_hits: u64,
+    blocked_time: u64,
+    incremental_load_time: u64,
+}
+
+/// Writes a table with the time spent in each kind of activity, aggregated
+/// by the label of the events, like the `summarize` tool.
+fn write_summary<'s>(
+    out: &mut impl Write,
+    kinds: EventKinds,
+    events: &[RawEvent],
+    resolve: impl Fn(StringId) -> &'s str,
+) -> io::Result<()> {
+    let mut rows: FxHashMap<&str, SummaryRow> = FxHashMap::default();
+    // The events each thread is in, innermost last.
+    let mut stacks: FxHashMap<u32, Vec<(&str, u64)>> = FxHashMap::default();
+
+    // `events` is sorted by start time, so parents come before their children.
+    for event in events {
+        let label = resolve(event.id).split(SEPARATOR_BYTE).next().unwrap_or("");
+        let row = rows.entry(label).or_default();
+        let end = match event.end {
+            Some(end) => end,
+            None => {
+                if event.kind == kinds.query_cache_hit {
+                    row.cache_hits += 1;
+                }
+                continue;
+            }
+        };
+        let duration = end - event.start;
+        if event.kind == kinds.query_blocked {
+            row.blocked_time += duration;
+            continue;
+        }
+        if event.kind == kinds.incremental_load_result {
+            row.incremental_load_time += duration;
+            continue;
+        }
+        row.count += 1;
+        row.time += duration;
+        row.self_time += duration;
+
+        // Time spent in a nested event is not spent in its parent.
+        let stack = stacks.entry(event.thread_id).or_default();
+        while stack.last().map_or(false, |&(_, parent_end)| parent_end <= event.start) {
+            stack.pop();
+        }
+        if let Some(&(parent, _)) = stack.last() {
+            let parent = rows.get_mut(parent).unwrap();
+            parent.self_time = parent.self_time.saturating_sub(duration);
+        }
+        stack.push((label, end));
+    }
+
+    let total_self_time: u64 = rows.values().map(|row| row.self_time).sum();
+    let mut rows: Vec<_> = rows.into_iter().collect();
+    rows.sort_by(|(a_label, a), (b_label, b)| {
+        b.self_time.cmp(&a.self_time).then_with(|| a_label.cmp(b_label))
+    });
+
+    let secs = |ns: u64| format!("{:.3}s", ns as f64 / 1e9);
+    let label_width = rows.iter().map(|(label, _)| label.len()).max().unwrap_or(0).max(4);
+    writeln!(
+        out,
+        "{:<w$} | {:>10} | {:>8} | {:>10} | {:>10} | {:>10} | {:>12} | {:>16}",
+        "Item",
+        "Self time",
+        "% total",
+        "Time",
+        "Item count",
+        "Cache hits",
+        "Blocked time",
+        "Incr. load time",
+        w = label_width
+    )?;
+    for (label, row) in rows {
+        let percent = if total_self_time == 0 {
+            0.0
+        } else {
+            row.self_time as f64 * 100.0 / total_self_time as f64
+        };
+        writeln!(
+            out,
+            "{:<w$} | {:>10} | {:>7.2}% | {:>10} | {:>10} | {:>10} | {:>12} | {:>16}",
+            label,
+            secs(row.self_time),
+            percent,
+            secs(row.time),
+            row.count,
+            row.cache_hits,
+            secs(row.blocked_time),
+            secs(row.incremental_load_time),
+            w = label_width
+        )?;
+    }
+    writeln!(out, "Total cpu time: {}", secs(total_self_time))
+}
@@ -407,6 +407,10 @@ fn run_compiler(
                 mem::drop(queries.expansion()?.take());
             }
 
+            if sess.opts.prints.contains(&PrintRequest::LintLevels) {
+                queries.global_ctxt()?.peek_mut().enter(|tcx| rustc_lint::print_lint_levels(tcx));
+            }
+
             if sess.opts.debugging_opts.no_analysis || sess.opts.debugging_opts.ast_json {
                 return early_exit();
             }
@@ -441,8 +445,8 @@ fn run_compiler(
 
             queries.ongoing_codegen()?;
 
-            if sess.opts.debugging_opts.print_type_sizes {
-                sess.code_stats.print_type_sizes();
+            if let Some(format) = sess.opts.debugging_opts.print_type_sizes {
+                sess.code_stats.print_type_sizes(format);
             }
 
             let linker = queries.linker()?;
@@ -454,6 +458,18 @@ fn run_compiler(
             linker.link()?
         }
 
+        if let Some(format) = sess.opts.debugging_opts.print_stack_sizes {
+            sess.code_stats.print_stack_sizes(format);
+        }
+
+        if sess.opts.debugging_opts.const_eval_profile {
+            sess.code_stats.print_const_eval_profile();
+        }
+
+        if let Some(format) = sess.opts.debugging_opts.incremental_explain {
+            sess.cgu_reuse_tracker.print_explanation(format);
+        }
+
         if sess.opts.debugging_opts.perf_stats {
             sess.print_perf_stats();
         }
@@ -681,9 +697,15 @@ fn print_crate_info(
         ofile: &Option<PathBuf>,
     ) -> Compilation {
         use rustc_session::config::PrintRequest::*;
-        // PrintRequest::NativeStaticLibs is special - printed during linking
+        // PrintRequest::NativeStaticLibs is special - printed during linking,
+        // and so are PrintRequest::LintLevels - printed once the HIR is built -
+        // and PrintRequest::MonoStats - printed once mono items are collected
         // (empty iterator returns true)
-        if sess.opts.prints.iter().all(|&p| p == PrintRequest::NativeStaticLibs) {
+        if sess.opts.prints.iter().all(|&p| {
+            p == PrintRequest::NativeStaticLibs
+                || p == PrintRequest::LintLevels
+                || p == PrintRequest::MonoStats
+        }) {
             return Compilation::Continue;
         }
 
@@ -772,7 +794,9 @@ fn print_crate_info(
                     codegen_backend.print(*req, sess);
                 }
                 // Any output here interferes with Cargo's parsing of other printed output
-                PrintRequest::NativeStaticLibs => {}
+                PrintRequest::NativeStaticLibs
+                | PrintRequest::LintLevels
+                | PrintRequest::MonoStats => {}
             }
         }
         Compilation::Stop
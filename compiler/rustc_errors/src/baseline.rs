@@ -0,0 +1,233 @@
+//! Support for `-Z diagnostic-baseline`.
+//!
+//! A baseline is a file listing a fingerprint of every warning a crate had at
+//! some point. Warnings found in the baseline are not reported again, so that
+//! new warnings stand out in crates with many existing ones. The fingerprint
+//! covers the lint name, the source text of the primary span and the path of
+//! the surrounding item, but not line numbers, so that unrelated edits do not
+//! invalidate the baseline.
+//!
+//! The crates of a workspace can share a baseline: it has a section of entries
+//! per crate, and every crate only reads and replaces its own.
+
+use crate::{Diagnostic, DiagnosticId};
+
+use rustc_data_structures::flock;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::stable_hasher::StableHasher;
+use rustc_data_structures::sync::Lrc;
+use rustc_span::source_map::SourceMap;
+
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::fs;
+use std::hash::Hash;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+
+const HEADER: &str = "# rustc diagnostic baseline";
+
+/// The entries of every crate in a baseline file, keyed by the name of their
+/// section.
+type Sections = BTreeMap<String, Vec<String>>;
+
+pub struct DiagnosticBaseline {
+    path: PathBuf,
+    /// The section of the baseline holding the warnings of this crate. A
+    /// baseline can be shared by all the crates of a workspace, and every crate
+    /// only reads and writes its own section.
+    section: String,
+    /// The number of occurrences of every fingerprint in the section of this
+    /// crate that have not been matched yet, or `None` if the section did not
+    /// exist and is being recorded.
+    unmatched: Option<FxHashMap<u128, usize>>,
+    /// The warnings that will be written to the baseline: every warning when
+    /// recording, and every warning that was suppressed otherwise.
+    entries: Vec<(u128, String)>,
+}
+
+/// What happened to the baseline, used to inform the user at the end of the session.
+pub enum BaselineOutcome {
+    /// The crate was not in the baseline and was added with this many warnings.
+    Recorded(usize),
+    /// The crate was not in the baseline, but was not added because the
+    /// session had errors and may not have gotten to emit all its warnings.
+    NotRecorded,
+    /// Warnings from the baseline were suppressed, and entries that did not
+    /// match any warning were removed.
+    Applied { suppressed: usize, removed: usize },
+}
+
+impl DiagnosticBaseline {
+    /// Loads the warnings of the crate called `section` from the baseline at
+    /// `path`. If the baseline does not exist or does not have this crate yet,
+    /// its warnings are recorded instead.
+    pub fn load(path: &Path, section: &str) -> Result<DiagnosticBaseline, String> {
+        let sections = read_sections(path)?;
+        let unmatched = sections.get(section).map(|lines| {
+            let mut unmatched = FxHashMap::default();
+            for line in lines {
+                *unmatched.entry(parse_fingerprint(line).unwrap()).or_insert(0) += 1;
+            }
+            unmatched
+        });
+        Ok(DiagnosticBaseline {
+            path: path.to_owned(),
+            section: section.to_owned(),
+            unmatched,
+            entries: Vec::new(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns whether `diag` is in the baseline and should not be emitted.
+    pub fn suppresses(&mut self, diag: &Diagnostic, sm: Option<&Lrc<SourceMap>>) -> bool {
+        let (fingerprint, name) = fingerprint(diag, sm);
+        match self.unmatched {
+            None => {
+                self.entries.push((fingerprint, name));
+                false
+            }
+            Some(ref mut unmatched) => match unmatched.get_mut(&fingerprint) {
+                Some(count) if *count > 0 => {
+                    *count -= 1;
+                    self.entries.push((fingerprint, name));
+                    true
+                }
+                _ => false,
+            },
+        }
+    }
+
+    /// Writes the section of this crate back. When recording, this adds the
+    /// section, unless the session had errors. Otherwise, if the session had
+    /// no errors, entries that did not match any warning are removed, so that
+    /// fixed warnings cannot come back unnoticed. The sections of other
+    /// crates are left alone.
+    pub fn finish(self, had_errors: bool) -> io::Result<BaselineOutcome> {
+        let outcome = match self.unmatched {
+            None if had_errors => return Ok(BaselineOutcome::NotRecorded),
+            None => BaselineOutcome::Recorded(self.entries.len()),
+            Some(ref unmatched) => {
+                let removed = if had_errors { 0 } else { unmatched.values().sum() };
+                BaselineOutcome::Applied { suppressed: self.entries.len(), removed }
+            }
+        };
+        if let BaselineOutcome::Applied { removed: 0, .. } = outcome {
+            return Ok(outcome);
+        }
+
+        let mut entries = self.entries;
+        entries.sort();
+        let lines = entries
+            .into_iter()
+            .map(|(fingerprint, name)| format!("{:032x} {}", fingerprint, name))
+            .collect();
+        update_section(&self.path, &self.section, lines)?;
+        Ok(outcome)
+    }
+}
+
+/// Reads the sections of the baseline at `path`, which are empty if it does
+/// not exist.
+fn read_sections(path: &Path) -> Result<Sections, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Sections::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut sections = Sections::new();
+    let mut current = None;
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            let section = line[1..line.len() - 1].trim().to_owned();
+            sections.entry(section.clone()).or_default();
+            current = Some(section);
+            continue;
+        }
+        let section = match current {
+            Some(ref section) => section,
+            None => return Err(format!("entry on line {} is not in a `[crate]` section", i + 1)),
+        };
+        if parse_fingerprint(line).is_none() {
+            let fingerprint = line.split_whitespace().next().unwrap();
+            return Err(format!("invalid fingerprint `{}` on line {}", fingerprint, i + 1));
+        }
+        sections.get_mut(section).unwrap().push(line.to_owned());
+    }
+    Ok(sections)
+}
+
+fn parse_fingerprint(line: &str) -> Option<u128> {
+    u128::from_str_radix(line.split_whitespace().next()?, 16).ok()
+}
+
+/// Replaces the section called `section` of the baseline at `path` with
+/// `lines`. The crates of a workspace may be compiled in parallel, so the
+/// baseline is read again under a lock, to keep the sections other crates
+/// wrote since it was loaded, and replaced atomically so that it is never seen
+/// half-written.
+fn update_section(path: &Path, section: &str, lines: Vec<String>) -> io::Result<()> {
+    let with_suffix = |suffix: &str| {
+        let mut path = OsString::from(path);
+        path.push(suffix);
+        PathBuf::from(path)
+    };
+    let _lock = flock::Lock::new(&with_suffix(".lock"), true, true, true)?;
+
+    let mut sections =
+        read_sections(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    sections.insert(section.to_owned(), lines);
+
+    let temp = with_suffix(&format!(".{}.tmp", process::id()));
+    let write = || -> io::Result<()> {
+        let mut file = io::BufWriter::new(fs::File::create(&temp)?);
+        writeln!(file, "{}", HEADER)?;
+        for (section, lines) in &sections {
+            writeln!(file, "[{}]", section)?;
+            for line in lines {
+                writeln!(file, "{}", line)?;
+            }
+        }
+        file.flush()
+    };
+    let result = write().and_then(|()| fs::rename(&temp, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    result
+}
+
+/// Computes the fingerprint of `diag`, along with the name shown next to it
+/// in the baseline.
+fn fingerprint(diag: &Diagnostic, sm: Option<&Lrc<SourceMap>>) -> (u128, String) {
+    let name = match diag.code {
+        Some(DiagnosticId::Lint { ref name, .. }) | Some(DiagnosticId::Error(ref name)) => {
+            name.clone()
+        }
+        None => diag.message(),
+    };
+    // Only keep the first line, since the baseline has one entry per line.
+    let name = name.lines().next().unwrap_or("").to_owned();
+
+    let text = match (diag.span.primary_span(), sm) {
+        (Some(span), Some(sm)) => sm.span_to_snippet(span).unwrap_or_default(),
+        _ => String::new(),
+    };
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let mut hasher = StableHasher::new();
+    name.hash(&mut hasher);
+    text.hash(&mut hasher);
+    diag.item_path.hash(&mut hasher);
+    (hasher.finish(), name)
+}
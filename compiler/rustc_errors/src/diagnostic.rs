@@ -24,6 +24,14 @@ pub struct Diagnostic {
     /// as a sort key to sort a buffer of diagnostics.  By default, it is the primary span of
     /// `span` if there is one.  Otherwise, it is `DUMMY_SP`.
     pub sort_span: Span,
+
+    /// How the level of the lint this diagnostic was emitted for was set, if it
+    /// was emitted for a lint. This is only used by the JSON emitter.
+    pub lint_source: Option<LintSource>,
+
+    /// The path of the item this diagnostic was emitted in, if known. This is
+    /// used to tell apart diagnostics for `-Z diagnostic-baseline`.
+    pub item_path: Option<String>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Encodable, Decodable)]
@@ -32,6 +40,22 @@ pub enum DiagnosticId {
     Lint { name: String, has_future_breakage: bool },
 }
 
+/// How a lint level was set. This is a copy of `rustc_middle::lint::LintLevelSource`
+/// that can be attached to a `Diagnostic`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Encodable, Decodable)]
+pub enum LintSource {
+    /// The lint is at the default level as declared in rustc or a plugin.
+    Default,
+
+    /// The lint level was set by an attribute, such as `#[deny(lint_name)]`.
+    /// `name` is the lint or lint group named in the attribute.
+    Attribute { name: String, span: Span, reason: Option<String> },
+
+    /// The lint level was set by a command-line flag, such as `-D lint-name`.
+    /// `name` is the lint or lint group named on the command line.
+    CommandLine { flag: String, name: String },
+}
+
 /// A "sub"-diagnostic attached to a parent diagnostic.
 /// For example, a note attached to an error.
 #[derive(Clone, Debug, PartialEq, Hash, Encodable, Decodable)]
@@ -91,6 +115,8 @@ pub fn new_with_code(level: Level, code: Option<DiagnosticId>, message: &str) ->
             children: vec![],
             suggestions: vec![],
             sort_span: DUMMY_SP,
+            lint_source: None,
+            item_path: None,
         }
     }
 
@@ -521,6 +547,11 @@ pub fn clear_code(&mut self) -> &mut Self {
         self
     }
 
+    pub fn lint_source(&mut self, source: LintSource) -> &mut Self {
+        self.lint_source = Some(source);
+        self
+    }
+
     pub fn get_code(&self) -> Option<DiagnosticId> {
         self.code.clone()
     }
@@ -1,4 +1,4 @@
-use crate::{Diagnostic, DiagnosticId, DiagnosticStyledString};
+use crate::{Diagnostic, DiagnosticId, DiagnosticStyledString, LintSource};
 use crate::{Handler, Level, StashKey};
 use rustc_lint_defs::Applicability;
 
@@ -364,6 +364,7 @@ pub fn tool_only_span_suggestion(
     forward!(pub fn set_primary_message<M: Into<String>>(&mut self, msg: M) -> &mut Self);
     forward!(pub fn set_span<S: Into<MultiSpan>>(&mut self, sp: S) -> &mut Self);
     forward!(pub fn code(&mut self, s: DiagnosticId) -> &mut Self);
+    forward!(pub fn lint_source(&mut self, source: LintSource) -> &mut Self);
 
     /// Allow attaching suggestions this diagnostic.
     /// If this is set to `false`, then any suggestions attached with the `span_suggestion_*`
@@ -198,6 +198,10 @@ fn emit_future_breakage_report(&mut self, _diags: Vec<(FutureBreakage, Diagnosti
     /// Emit list of unused externs
     fn emit_unused_externs(&mut self, _lint_level: &str, _unused_externs: &[&str]) {}
 
+    /// Called once all diagnostics of the session have been emitted, to write
+    /// out anything the emitter held back until then.
+    fn finish(&mut self) {}
+
     /// Checks if should show explanations about "rustc --explain"
     fn should_show_explain(&self) -> bool {
         true
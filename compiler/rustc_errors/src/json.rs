@@ -6,6 +6,10 @@
 //!
 //! The format of the JSON output should be considered *unstable*. For now the
 //! structs at the end of this file (Diagnostic*) specify the error format.
+//!
+//! The exception is the versioned schema selected with `--json=schema-v2`,
+//! which is documented in `src/doc/rustc/src/json.md`. Any change to the
+//! output of `JsonSchemaVersion::V2` must be reflected there.
 
 // FIXME: spec the JSON output properly.
 
@@ -13,13 +17,13 @@
 
 use crate::emitter::{Emitter, HumanReadableErrorType};
 use crate::registry::Registry;
-use crate::DiagnosticId;
 use crate::ToolMetadata;
+use crate::{DiagnosticId, LintSource};
 use crate::{CodeSuggestion, SubDiagnostic};
 use rustc_lint_defs::{Applicability, FutureBreakage};
 
 use rustc_data_structures::sync::Lrc;
-use rustc_span::hygiene::ExpnData;
+use rustc_span::hygiene::{ExpnData, ExpnKind, MacroKind};
 use rustc_span::{MultiSpan, Span, SpanLabel};
 use std::io::{self, Write};
 use std::path::Path;
@@ -41,6 +45,29 @@ pub struct JsonEmitter {
     json_rendered: HumanReadableErrorType,
     terminal_width: Option<usize>,
     macro_backtrace: bool,
+    schema_version: JsonSchemaVersion,
+}
+
+/// The versions of the schema the JSON emitter can follow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum JsonSchemaVersion {
+    /// The original, unversioned schema.
+    V1,
+    /// The schema selected with `--json=schema-v2`. Diagnostics carry a
+    /// `$schema_version` field, lints are reported separately from error codes
+    /// together with the source of their level, macro backtraces are flattened
+    /// into a list of frames, and suggestions carry their applicability.
+    V2,
+}
+
+impl JsonSchemaVersion {
+    /// The value of the `$schema_version` field.
+    pub fn number(self) -> u32 {
+        match self {
+            JsonSchemaVersion::V1 => 1,
+            JsonSchemaVersion::V2 => 2,
+        }
+    }
 }
 
 impl JsonEmitter {
@@ -61,6 +88,7 @@ pub fn stderr(
             json_rendered,
             terminal_width,
             macro_backtrace,
+            schema_version: JsonSchemaVersion::V1,
         }
     }
 
@@ -99,12 +127,17 @@ pub fn new(
             json_rendered,
             terminal_width,
             macro_backtrace,
+            schema_version: JsonSchemaVersion::V1,
         }
     }
 
     pub fn ui_testing(self, ui_testing: bool) -> Self {
         Self { ui_testing, ..self }
     }
+
+    pub fn schema_version(self, schema_version: JsonSchemaVersion) -> Self {
+        Self { schema_version, ..self }
+    }
 }
 
 impl Emitter for JsonEmitter {
@@ -186,14 +219,25 @@ fn should_show_explain(&self) -> bool {
 // NOTE: this has a manual implementation of Encodable which needs to be updated in
 // parallel.
 struct Diagnostic {
+    /// The schema this diagnostic is serialized with.
+    schema_version: JsonSchemaVersion,
+    /// Whether this diagnostic is not one of the `children` of another one.
+    /// Only top-level diagnostics carry a `$schema_version`.
+    top_level: bool,
     /// The primary error message.
     message: String,
+    /// The error code. Up to schema version 1 this is also the lint name.
     code: Option<DiagnosticCode>,
+    /// The lint that emitted this diagnostic (schema version 2).
+    lint: Option<DiagnosticLint>,
     /// "error: internal compiler error", "error", "warning", "note", "help".
     level: &'static str,
     spans: Vec<DiagnosticSpan>,
     /// Associated diagnostic messages.
     children: Vec<Diagnostic>,
+    /// How confident we are in the suggestion, if this is a suggestion
+    /// (schema version 2).
+    applicability: Option<Applicability>,
     /// The message as rustc would render it.
     rendered: Option<String>,
     /// Extra tool metadata
@@ -226,20 +270,41 @@ macro_rules! encode_fields {
     };
 }
 
-// Special-case encoder to skip tool_metadata if not set
+// Special-case encoder to skip tool_metadata if not set, and to select the
+// fields of the schema version.
 impl<E: Encoder> Encodable<E> for Diagnostic {
     fn encode(&self, s: &mut E) -> Result<(), E::Error> {
-        s.emit_struct("diagnostic", 7, |s| {
+        s.emit_struct("diagnostic", 10, |s| {
             let mut idx = 0;
 
-            idx = encode_fields!(
-                s,
-                idx,
-                self,
-                Self,
-                [message, code, level, spans, children, rendered],
-                [tool_metadata]
-            );
+            match self.schema_version {
+                JsonSchemaVersion::V1 => {
+                    idx = encode_fields!(
+                        s,
+                        idx,
+                        self,
+                        Self,
+                        [message, code, level, spans, children, rendered],
+                        [schema_version, top_level, lint, applicability, tool_metadata]
+                    );
+                }
+                JsonSchemaVersion::V2 => {
+                    if self.top_level {
+                        s.emit_struct_field("$schema_version", idx, |s| {
+                            self.schema_version.number().encode(s)
+                        })?;
+                        idx += 1;
+                    }
+                    idx = encode_fields!(
+                        s,
+                        idx,
+                        self,
+                        Self,
+                        [message, code, lint, level, spans, children, applicability, rendered],
+                        [schema_version, top_level, tool_metadata]
+                    );
+                }
+            }
             if self.tool_metadata.is_set() {
                 idx = encode_fields!(
                     s,
@@ -247,7 +312,18 @@ fn encode(&self, s: &mut E) -> Result<(), E::Error> {
                     self,
                     Self,
                     [tool_metadata],
-                    [message, code, level, spans, children, rendered]
+                    [
+                        schema_version,
+                        top_level,
+                        message,
+                        code,
+                        lint,
+                        level,
+                        spans,
+                        children,
+                        applicability,
+                        rendered
+                    ]
                 );
             }
 
@@ -257,7 +333,8 @@ fn encode(&self, s: &mut E) -> Result<(), E::Error> {
     }
 }
 
-#[derive(Encodable)]
+// NOTE: this has a manual implementation of Encodable which needs to be updated in
+// parallel.
 struct DiagnosticSpan {
     file_name: String,
     byte_start: u32,
@@ -282,6 +359,44 @@ struct DiagnosticSpan {
     suggestion_applicability: Option<Applicability>,
     /// Macro invocations that created the code at this span, if any.
     expansion: Option<Box<DiagnosticSpanMacroExpansion>>,
+    /// The same macro invocations as a list of frames, innermost first. This
+    /// replaces `expansion` from schema version 2 on.
+    macro_backtrace: Option<Vec<DiagnosticMacroFrame>>,
+}
+
+// Special-case encoder to emit either `expansion` or `macro_backtrace`.
+impl<E: Encoder> Encodable<E> for DiagnosticSpan {
+    fn encode(&self, s: &mut E) -> Result<(), E::Error> {
+        s.emit_struct("DiagnosticSpan", 13, |s| {
+            let idx = encode_fields!(
+                s,
+                0,
+                self,
+                Self,
+                [
+                    file_name,
+                    byte_start,
+                    byte_end,
+                    line_start,
+                    line_end,
+                    column_start,
+                    column_end,
+                    is_primary,
+                    text,
+                    label,
+                    suggested_replacement,
+                    suggestion_applicability
+                ],
+                [expansion, macro_backtrace]
+            );
+            if let Some(ref macro_backtrace) = self.macro_backtrace {
+                s.emit_struct_field("macro_backtrace", idx, |s| macro_backtrace.encode(s))?;
+            } else {
+                s.emit_struct_field("expansion", idx, |s| self.expansion.encode(s))?;
+            }
+            Ok(())
+        })
+    }
 }
 
 #[derive(Encodable)]
@@ -308,6 +423,41 @@ struct DiagnosticSpanMacroExpansion {
     def_site_span: DiagnosticSpan,
 }
 
+#[derive(Encodable)]
+struct DiagnosticMacroFrame {
+    /// Name of macro that was applied (e.g., "foo!" or "#[derive(Eq)]").
+    macro_decl_name: String,
+    /// "bang", "attribute", "derive", "ast-pass", "desugaring" or "inlined".
+    kind: &'static str,
+    /// Span where the macro was applied. Unlike in `expansion`, this
+    /// does not itself carry a backtrace; the next frame describes it.
+    call_site: DiagnosticSpan,
+    /// Span where the macro was defined, if known.
+    def_site: Option<DiagnosticSpan>,
+}
+
+#[derive(Encodable)]
+struct DiagnosticLint {
+    /// The name of the lint, e.g. "unused_variables".
+    name: String,
+    /// How the level of the lint was set, if known.
+    source: Option<DiagnosticLintSource>,
+}
+
+#[derive(Encodable)]
+struct DiagnosticLintSource {
+    /// "default", "attribute" or "command-line".
+    kind: &'static str,
+    /// The lint or lint group named by the attribute or command-line flag.
+    name: Option<String>,
+    /// The command-line flag: "-A", "-W", "-D" or "-F".
+    flag: Option<String>,
+    /// The span of the attribute.
+    span: Option<DiagnosticSpan>,
+    /// The reason given in the attribute, if any.
+    reason: Option<String>,
+}
+
 #[derive(Encodable)]
 struct DiagnosticCode {
     /// The code itself.
@@ -350,11 +500,15 @@ struct UnusedExterns<'a, 'b, 'c> {
 impl Diagnostic {
     fn from_errors_diagnostic(diag: &crate::Diagnostic, je: &JsonEmitter) -> Diagnostic {
         let sugg = diag.suggestions.iter().map(|sugg| Diagnostic {
+            schema_version: je.schema_version,
+            top_level: false,
             message: sugg.msg.clone(),
             code: None,
+            lint: None,
             level: "help",
             spans: DiagnosticSpan::from_suggestion(sugg, je),
             children: vec![],
+            applicability: Some(sugg.applicability),
             rendered: None,
             tool_metadata: sugg.tool_metadata.clone(),
         });
@@ -388,9 +542,24 @@ fn flush(&mut self) -> io::Result<()> {
         let output = Arc::try_unwrap(output.0).unwrap().into_inner().unwrap();
         let output = String::from_utf8(output).unwrap();
 
+        let (code, lint) = match (je.schema_version, diag.code.clone()) {
+            (JsonSchemaVersion::V1, code) => (DiagnosticCode::map_opt_string(code, je), None),
+            (JsonSchemaVersion::V2, Some(DiagnosticId::Lint { name, .. })) => {
+                let source = diag
+                    .lint_source
+                    .as_ref()
+                    .map(|source| DiagnosticLintSource::from_lint_source(source, je));
+                (None, Some(DiagnosticLint { name, source }))
+            }
+            (JsonSchemaVersion::V2, code) => (DiagnosticCode::map_opt_string(code, je), None),
+        };
+
         Diagnostic {
+            schema_version: je.schema_version,
+            top_level: true,
             message: diag.message(),
-            code: DiagnosticCode::map_opt_string(diag.code.clone(), je),
+            code,
+            lint,
             level: diag.level.to_str(),
             spans: DiagnosticSpan::from_multispan(&diag.span, je),
             children: diag
@@ -399,6 +568,7 @@ fn flush(&mut self) -> io::Result<()> {
                 .map(|c| Diagnostic::from_sub_diagnostic(c, je))
                 .chain(sugg)
                 .collect(),
+            applicability: None,
             rendered: Some(output),
             tool_metadata: ToolMetadata::default(),
         }
@@ -406,8 +576,11 @@ fn flush(&mut self) -> io::Result<()> {
 
     fn from_sub_diagnostic(diag: &SubDiagnostic, je: &JsonEmitter) -> Diagnostic {
         Diagnostic {
+            schema_version: je.schema_version,
+            top_level: false,
             message: diag.message(),
             code: None,
+            lint: None,
             level: diag.level.to_str(),
             spans: diag
                 .render_span
@@ -415,6 +588,7 @@ fn from_sub_diagnostic(diag: &SubDiagnostic, je: &JsonEmitter) -> Diagnostic {
                 .map(|sp| DiagnosticSpan::from_multispan(sp, je))
                 .unwrap_or_else(|| DiagnosticSpan::from_multispan(&diag.span, je)),
             children: vec![],
+            applicability: None,
             rendered: None,
             tool_metadata: ToolMetadata::default(),
         }
@@ -443,7 +617,24 @@ fn from_span_etc(
         // some decision, such as dropping some frames, and I don't
         // want to duplicate that logic here.
         let backtrace = span.macro_backtrace();
-        DiagnosticSpan::from_span_full(span, is_primary, label, suggestion, backtrace, je)
+        match je.schema_version {
+            JsonSchemaVersion::V1 => {
+                DiagnosticSpan::from_span_full(span, is_primary, label, suggestion, backtrace, je)
+            }
+            JsonSchemaVersion::V2 => {
+                let frames = backtrace.map(|bt| DiagnosticMacroFrame::from_expn_data(bt, je));
+                let mut span = DiagnosticSpan::from_span_full(
+                    span,
+                    is_primary,
+                    label,
+                    suggestion,
+                    vec![].into_iter(),
+                    je,
+                );
+                span.macro_backtrace = Some(frames.collect());
+                span
+            }
+        }
     }
 
     fn from_span_full(
@@ -480,10 +671,22 @@ fn from_span_full(
             suggested_replacement: suggestion.map(|x| x.0.clone()),
             suggestion_applicability: suggestion.map(|x| x.1),
             expansion: backtrace_step,
+            macro_backtrace: None,
             label,
         }
     }
 
+    /// Creates a span without any macro backtrace, for use within a
+    /// `DiagnosticMacroFrame` or a `DiagnosticLintSource`.
+    fn without_backtrace(span: Span, je: &JsonEmitter) -> DiagnosticSpan {
+        let mut span =
+            DiagnosticSpan::from_span_full(span, false, None, None, vec![].into_iter(), je);
+        if je.schema_version == JsonSchemaVersion::V2 {
+            span.macro_backtrace = Some(vec![]);
+        }
+        span
+    }
+
     fn from_multispan(msp: &MultiSpan, je: &JsonEmitter) -> Vec<DiagnosticSpan> {
         msp.span_labels()
             .into_iter()
@@ -554,6 +757,59 @@ fn from_span(span: Span, je: &JsonEmitter) -> Vec<DiagnosticSpanLine> {
     }
 }
 
+impl DiagnosticMacroFrame {
+    fn from_expn_data(bt: ExpnData, je: &JsonEmitter) -> DiagnosticMacroFrame {
+        let kind = match bt.kind {
+            // `Span::macro_backtrace` stops at the root context.
+            ExpnKind::Root => unreachable!("the root context is not part of a macro backtrace"),
+            ExpnKind::Macro(MacroKind::Bang, _) => "bang",
+            ExpnKind::Macro(MacroKind::Attr, _) => "attribute",
+            ExpnKind::Macro(MacroKind::Derive, _) => "derive",
+            ExpnKind::AstPass(_) => "ast-pass",
+            ExpnKind::Desugaring(_) => "desugaring",
+            ExpnKind::Inlined => "inlined",
+        };
+        DiagnosticMacroFrame {
+            macro_decl_name: bt.kind.descr(),
+            kind,
+            call_site: DiagnosticSpan::without_backtrace(bt.call_site, je),
+            def_site: if bt.def_site.is_dummy() {
+                None
+            } else {
+                Some(DiagnosticSpan::without_backtrace(bt.def_site, je))
+            },
+        }
+    }
+}
+
+impl DiagnosticLintSource {
+    fn from_lint_source(source: &LintSource, je: &JsonEmitter) -> DiagnosticLintSource {
+        match *source {
+            LintSource::Default => DiagnosticLintSource {
+                kind: "default",
+                name: None,
+                flag: None,
+                span: None,
+                reason: None,
+            },
+            LintSource::Attribute { ref name, span, ref reason } => DiagnosticLintSource {
+                kind: "attribute",
+                name: Some(name.clone()),
+                flag: None,
+                span: Some(DiagnosticSpan::without_backtrace(span, je)),
+                reason: reason.clone(),
+            },
+            LintSource::CommandLine { ref flag, ref name } => DiagnosticLintSource {
+                kind: "command-line",
+                name: Some(name.clone()),
+                flag: Some(flag.clone()),
+                span: None,
+                reason: None,
+            },
+        }
+    }
+}
+
 impl DiagnosticCode {
     fn map_opt_string(s: Option<DiagnosticId>, je: &JsonEmitter) -> Option<DiagnosticCode> {
         s.map(|s| {
@@ -4,8 +4,9 @@
 use rustc_span::source_map::{FilePathMapping, SourceMap};
 
 use crate::emitter::{ColorConfig, HumanReadableErrorType};
-use crate::Handler;
-use rustc_serialize::json::decode;
+use crate::registry::Registry;
+use crate::{DiagnosticId, Handler, LintSource};
+use rustc_serialize::json::{decode, Json};
 use rustc_span::{BytePos, Span};
 
 use std::str;
@@ -202,3 +203,85 @@ fn span_after_crlf() {
         },
     )
 }
+
+/// Emits a lint with a suggestion and returns the JSON of the diagnostic.
+fn lint_with_suggestion(schema_version: JsonSchemaVersion) -> Json {
+    let mut json = None;
+    with_default_session_globals(|| {
+        let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        sm.new_source_file(Path::new("test.rs").to_owned().into(), "let x = 1;".to_owned());
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let je = JsonEmitter::new(
+            Box::new(Shared { data: output.clone() }),
+            Some(Registry::new(&[])),
+            sm,
+            false,
+            HumanReadableErrorType::Short(ColorConfig::Never),
+            None,
+            false,
+        )
+        .schema_version(schema_version);
+
+        let span = Span::with_root_ctxt(BytePos(4), BytePos(5));
+        let handler = Handler::with_emitter(true, None, Box::new(je));
+        handler
+            .struct_span_warn(span, "unused variable: `x`")
+            .code(DiagnosticId::Lint {
+                name: "unused_variables".to_owned(),
+                has_future_breakage: false,
+            })
+            .lint_source(LintSource::CommandLine {
+                flag: "-W".to_owned(),
+                name: "unused".to_owned(),
+            })
+            .span_suggestion(
+                span,
+                "if this is intentional, prefix it with an underscore",
+                "_x".to_owned(),
+                Applicability::MachineApplicable,
+            )
+            .emit();
+
+        let bytes = output.lock().unwrap();
+        json = Some(rustc_serialize::json::from_str(str::from_utf8(&bytes).unwrap()).unwrap());
+    });
+    json.unwrap()
+}
+
+#[test]
+fn schema_v1() {
+    let json = lint_with_suggestion(JsonSchemaVersion::V1);
+    assert!(json.find("$schema_version").is_none());
+    assert!(json.find("lint").is_none());
+    assert_eq!(json.find_path(&["code", "code"]).unwrap().as_string(), Some("unused_variables"));
+
+    let suggestion = &json["children"][0];
+    assert!(suggestion.find("applicability").is_none());
+    assert_eq!(
+        suggestion["spans"][0]["suggestion_applicability"].as_string(),
+        Some("MachineApplicable")
+    );
+    assert!(suggestion["spans"][0].find("expansion").unwrap().is_null());
+}
+
+#[test]
+fn schema_v2() {
+    let json = lint_with_suggestion(JsonSchemaVersion::V2);
+    assert_eq!(json["$schema_version"].as_u64(), Some(2));
+    assert!(json["code"].is_null());
+    assert_eq!(json.find_path(&["lint", "name"]).unwrap().as_string(), Some("unused_variables"));
+
+    let source = json.find_path(&["lint", "source"]).unwrap();
+    assert_eq!(source["kind"].as_string(), Some("command-line"));
+    assert_eq!(source["flag"].as_string(), Some("-W"));
+    assert_eq!(source["name"].as_string(), Some("unused"));
+    assert!(source["span"].is_null());
+
+    assert!(json["applicability"].is_null());
+    let suggestion = &json["children"][0];
+    assert!(suggestion.find("$schema_version").is_none());
+    assert_eq!(suggestion["applicability"].as_string(), Some("MachineApplicable"));
+    assert!(suggestion["spans"][0].find("expansion").is_none());
+    assert_eq!(suggestion["spans"][0]["macro_backtrace"].as_array().map(Vec::len), Some(0));
+}
@@ -8,6 +8,7 @@
 #![feature(extended_key_value_attributes)]
 #![feature(iter_zip)]
 #![feature(nll)]
+#![feature(once_cell)]
 
 #[macro_use]
 extern crate rustc_macros;
@@ -17,6 +18,7 @@
 use tracing::debug;
 use Level::*;
 
+use baseline::{BaselineOutcome, DiagnosticBaseline};
 use emitter::{is_case_difference, Emitter, EmitterWriter};
 use registry::Registry;
 use rustc_data_structures::fx::{FxHashSet, FxIndexMap};
@@ -40,12 +42,14 @@
 use termcolor::{Color, ColorSpec};
 
 pub mod annotate_snippet_emitter_writer;
+pub mod baseline;
 mod diagnostic;
 mod diagnostic_builder;
 pub mod emitter;
 pub mod json;
 mod lock;
 pub mod registry;
+pub mod sarif;
 mod snippet;
 mod styled_buffer;
 pub use snippet::Style;
@@ -292,7 +296,7 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 
 impl error::Error for ExplicitBug {}
 
-pub use diagnostic::{Diagnostic, DiagnosticId, DiagnosticStyledString, SubDiagnostic};
+pub use diagnostic::{Diagnostic, DiagnosticId, DiagnosticStyledString, LintSource, SubDiagnostic};
 pub use diagnostic_builder::DiagnosticBuilder;
 
 /// A handler deals with errors and other compiler output.
@@ -342,6 +346,9 @@ struct HandlerInner {
     deduplicated_warn_count: usize,
 
     future_breakage_diagnostics: Vec<Diagnostic>,
+
+    /// The baseline of warnings not to report again (`-Z diagnostic-baseline`).
+    diagnostic_baseline: Option<DiagnosticBaseline>,
 }
 
 /// A key denoting where from a diagnostic was stashed.
@@ -456,6 +463,7 @@ pub fn with_emitter_and_flags(
                 emitted_diagnostics: Default::default(),
                 stashed_diagnostics: Default::default(),
                 future_breakage_diagnostics: Vec::new(),
+                diagnostic_baseline: None,
             }),
         }
     }
@@ -769,9 +777,88 @@ pub fn emit_unused_externs(&self, lint_level: &str, unused_externs: &[&str]) {
         self.inner.borrow_mut().emit_unused_externs(lint_level, unused_externs)
     }
 
+    /// Lets the emitter write out anything it held back, e.g. the SARIF log of
+    /// the whole session. Called once no more diagnostics are expected.
+    pub fn finish_emitter(&self) {
+        self.inner.borrow_mut().emitter.finish()
+    }
+
+    /// Passes diagnostics that were already reported through another handler,
+    /// e.g. one with a `SarifEmitter::buffered` emitter, on to the emitter of
+    /// this one. They are not counted or filtered again.
+    pub fn emit_buffered_diagnostics(&self, diagnostics: Vec<Diagnostic>) {
+        let mut inner = self.inner.borrow_mut();
+        for diagnostic in &diagnostics {
+            inner.emitter.emit_diagnostic(diagnostic);
+        }
+    }
+
     pub fn delay_as_bug(&self, diagnostic: Diagnostic) {
         self.inner.borrow_mut().delay_as_bug(diagnostic)
     }
+
+    /// Stops reporting the warnings in `baseline`, or records every warning
+    /// into it if it is new.
+    pub fn set_diagnostic_baseline(&self, baseline: DiagnosticBaseline) {
+        self.inner.borrow_mut().diagnostic_baseline = Some(baseline);
+    }
+
+    /// Writes back the baseline set with `set_diagnostic_baseline`, if any,
+    /// and tells the user what happened to it.
+    pub fn finish_diagnostic_baseline(&self) {
+        let mut inner = self.inner.borrow_mut();
+        let baseline = match inner.diagnostic_baseline.take() {
+            Some(baseline) => baseline,
+            None => return,
+        };
+        let path = baseline.path().display().to_string();
+        // A session with errors may not have gotten to emit all its warnings.
+        let had_errors = inner.has_errors();
+        let (level, msg) = match baseline.finish(had_errors) {
+            Ok(BaselineOutcome::Recorded(0))
+            | Ok(BaselineOutcome::Applied { suppressed: 0, removed: 0 }) => return,
+            Ok(BaselineOutcome::NotRecorded) => (
+                Note,
+                format!(
+                    "warnings were not recorded in the diagnostic baseline `{}` \
+                     because of the errors above",
+                    path
+                ),
+            ),
+            Ok(BaselineOutcome::Recorded(recorded)) => (
+                Note,
+                format!(
+                    "recorded {} warning{} in the diagnostic baseline `{}`",
+                    recorded,
+                    pluralize!(recorded),
+                    path
+                ),
+            ),
+            Ok(BaselineOutcome::Applied { suppressed, removed: 0 }) => (
+                Note,
+                format!(
+                    "{} warning{} from the diagnostic baseline `{}` not shown",
+                    suppressed,
+                    pluralize!(suppressed),
+                    path
+                ),
+            ),
+            Ok(BaselineOutcome::Applied { suppressed, removed }) => (
+                Note,
+                format!(
+                    "{} warning{} from the diagnostic baseline `{}` not shown, \
+                     removed {} fixed warning{} from it",
+                    suppressed,
+                    pluralize!(suppressed),
+                    path,
+                    removed,
+                    pluralize!(removed)
+                ),
+            ),
+            Err(e) => (Error, format!("failed to write diagnostic baseline `{}`: {}", path, e)),
+        };
+        inner.emit_diagnostic(&Diagnostic::new(level, &msg));
+    }
 }
 
 impl HandlerInner {
@@ -822,9 +909,18 @@ fn emit_diagnostic(&mut self, diagnostic: &Diagnostic) {
             !this.emitted_diagnostics.insert(diagnostic_hash)
         };
 
+        // Warnings in the baseline still count towards the warning count, as
+        // far as delayed bugs are concerned.
+        let in_baseline = |this: &mut Self| match this.diagnostic_baseline {
+            Some(ref mut baseline) if diagnostic.level == Warning => {
+                baseline.suppresses(diagnostic, this.emitter.source_map())
+            }
+            _ => false,
+        };
+
         // Only emit the diagnostic if we've been asked to deduplicate and
         // haven't already emitted an equivalent diagnostic.
-        if !(self.flags.deduplicate_diagnostics && already_emitted(self)) {
+        if !(self.flags.deduplicate_diagnostics && already_emitted(self)) && !in_baseline(self) {
             self.emitter.emit_diagnostic(diagnostic);
             if diagnostic.is_error() {
                 self.deduplicated_err_count += 1;
@@ -0,0 +1,416 @@
+//! A SARIF emitter for errors.
+//!
+//! [SARIF] (the Static Analysis Results Interchange Format) is the format
+//! code-scanning platforms ingest. Unlike the JSON emitter, which writes one
+//! object per diagnostic, a SARIF log is a single document for the whole
+//! compilation session. Diagnostics are therefore collected as they are
+//! emitted and the log is written out by `Emitter::finish` once the session
+//! is over. Nothing is written for a handler that is never finished.
+//!
+//! Handlers other than the one of the session, e.g. the ones reporting
+//! diagnostics before the session exists, use an emitter created with
+//! `SarifEmitter::buffered`, which writes no log of its own. Their diagnostics
+//! are passed on to the session's handler with
+//! `Handler::emit_buffered_diagnostics`, so that one compilation writes one log.
+//!
+//! [SARIF]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use rustc_span::source_map::{FilePathMapping, SourceMap};
+
+use crate::emitter::Emitter;
+use crate::registry::Registry;
+use crate::{CodeSuggestion, DiagnosticId, Level};
+use rustc_lint_defs::Applicability;
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::sync::{Lock, Lrc};
+use rustc_span::{MultiSpan, Span, SpanLabel};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use rustc_serialize::json::{Json, ToJson};
+
+#[cfg(test)]
+mod tests;
+
+const SARIF_VERSION: &str = "2.1.0";
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+pub struct SarifEmitter {
+    dst: Box<dyn Write + Send>,
+    registry: Option<Registry>,
+    sm: Lrc<SourceMap>,
+    pretty: bool,
+    /// The rules results refer to, in order of first use.
+    rules: Vec<Json>,
+    rule_indices: FxHashMap<String, usize>,
+    results: Vec<Json>,
+    /// Where diagnostics are held back for the log of another emitter, if
+    /// this emitter writes no log itself.
+    buffer: Option<Lrc<Lock<Vec<crate::Diagnostic>>>>,
+    /// Whether the log has been written already.
+    finished: bool,
+}
+
+impl SarifEmitter {
+    pub fn stderr(registry: Option<Registry>, source_map: Lrc<SourceMap>, pretty: bool) -> Self {
+        Self::new(Box::new(io::BufWriter::new(io::stderr())), registry, source_map, pretty)
+    }
+
+    pub fn basic(pretty: bool) -> Self {
+        let file_path_mapping = FilePathMapping::empty();
+        Self::stderr(None, Lrc::new(SourceMap::new(file_path_mapping)), pretty)
+    }
+
+    /// Creates an emitter that writes no log itself, but adds its diagnostics
+    /// to `buffer`, to be passed on to the handler of the session.
+    pub fn buffered(buffer: Lrc<Lock<Vec<crate::Diagnostic>>>) -> Self {
+        let mut emitter = Self::basic(false);
+        emitter.buffer = Some(buffer);
+        emitter
+    }
+
+    pub fn new(
+        dst: Box<dyn Write + Send>,
+        registry: Option<Registry>,
+        source_map: Lrc<SourceMap>,
+        pretty: bool,
+    ) -> Self {
+        SarifEmitter {
+            dst,
+            registry,
+            sm: source_map,
+            pretty,
+            rules: Vec::new(),
+            rule_indices: FxHashMap::default(),
+            results: Vec::new(),
+            buffer: None,
+            finished: false,
+        }
+    }
+
+    /// Returns the index of the rule for `code` in `tool.driver.rules`, adding it if necessary.
+    fn rule_index(&mut self, code: &DiagnosticId) -> (String, usize) {
+        let (id, is_lint) = match code {
+            DiagnosticId::Error(code) => (code.clone(), false),
+            DiagnosticId::Lint { name, .. } => (name.clone(), true),
+        };
+        if let Some(&index) = self.rule_indices.get(&id) {
+            return (id, index);
+        }
+
+        let mut rule = BTreeMap::new();
+        rule.insert("id".to_owned(), id.to_json());
+        if is_lint {
+            rule.insert("name".to_owned(), id.to_json());
+        } else {
+            let help_uri = format!("https://doc.rust-lang.org/error-index.html#{}", id);
+            rule.insert("helpUri".to_owned(), help_uri.to_json());
+            let explanation = self
+                .registry
+                .as_ref()
+                .and_then(|registry| registry.try_find_description(&id).ok().flatten());
+            if let Some(explanation) = explanation {
+                rule.insert("fullDescription".to_owned(), message(explanation.trim()));
+            }
+        }
+        let mut properties = BTreeMap::new();
+        let kind = if is_lint { "lint" } else { "error-code" };
+        properties.insert("kind".to_owned(), kind.to_json());
+        rule.insert("properties".to_owned(), Json::Object(properties));
+
+        let index = self.rules.len();
+        self.rules.push(Json::Object(rule));
+        self.rule_indices.insert(id.clone(), index);
+        (id, index)
+    }
+
+    fn result(&mut self, diag: &crate::Diagnostic) -> Json {
+        let mut result = BTreeMap::new();
+        if let Some(code) = &diag.code {
+            let (rule_id, rule_index) = self.rule_index(code);
+            result.insert("ruleId".to_owned(), rule_id.to_json());
+            result.insert("ruleIndex".to_owned(), rule_index.to_json());
+        }
+        result.insert("level".to_owned(), sarif_level(diag.level).to_json());
+
+        // Children pointing into the source become related locations, the
+        // messages of all others are appended to the message of the result.
+        let mut text = diag.message();
+        let mut related_locations = Vec::new();
+        for span_label in diag.span.span_labels().into_iter().filter(|label| !label.is_primary) {
+            related_locations.extend(self.location(span_label.span, span_label.label));
+        }
+        for child in &diag.children {
+            let child_text = format!("{}: {}", child.level.to_str(), child.message());
+            let span = child.render_span.as_ref().unwrap_or(&child.span);
+            let locations = self.sub_diagnostic_locations(span, &child_text);
+            if locations.is_empty() {
+                text.push('\n');
+                text.push_str(&child_text);
+            }
+            related_locations.extend(locations);
+        }
+        result.insert("message".to_owned(), message(&text));
+
+        let locations = self.primary_locations(&diag.span);
+        if !locations.is_empty() {
+            result.insert("locations".to_owned(), Json::Array(locations));
+        }
+        if !related_locations.is_empty() {
+            for (id, location) in related_locations.iter_mut().enumerate() {
+                if let Json::Object(location) = location {
+                    location.insert("id".to_owned(), id.to_json());
+                }
+            }
+            result.insert("relatedLocations".to_owned(), Json::Array(related_locations));
+        }
+
+        let fixes: Vec<_> = diag.suggestions.iter().flat_map(|sugg| self.fixes(sugg)).collect();
+        if !fixes.is_empty() {
+            result.insert("fixes".to_owned(), Json::Array(fixes));
+        }
+        Json::Object(result)
+    }
+
+    fn primary_locations(&self, span: &MultiSpan) -> Vec<Json> {
+        span.span_labels()
+            .into_iter()
+            .filter(|label| label.is_primary)
+            .filter_map(|SpanLabel { span, label, .. }| self.location(span, label))
+            .collect()
+    }
+
+    fn sub_diagnostic_locations(&self, span: &MultiSpan, child_text: &str) -> Vec<Json> {
+        span.span_labels()
+            .into_iter()
+            .filter_map(|SpanLabel { span, label, is_primary }| {
+                // Only the primary span carries the message of the child itself.
+                let label = match (is_primary, label) {
+                    (true, Some(label)) => Some(format!("{} ({})", child_text, label)),
+                    (true, None) => Some(child_text.to_owned()),
+                    (false, label) => label,
+                };
+                self.location(span, label)
+            })
+            .collect()
+    }
+
+    /// Converts `span` to a SARIF `location`, if it points into a real file.
+    fn location(&self, span: Span, label: Option<String>) -> Option<Json> {
+        let mut location = BTreeMap::new();
+        location.insert("physicalLocation".to_owned(), self.physical_location(span)?);
+        if let Some(label) = label {
+            location.insert("message".to_owned(), message(&label));
+        }
+        Some(Json::Object(location))
+    }
+
+    fn physical_location(&self, span: Span) -> Option<Json> {
+        let (artifact_location, region) = self.artifact_location_and_region(span)?;
+        let mut physical_location = BTreeMap::new();
+        physical_location.insert("artifactLocation".to_owned(), artifact_location);
+        physical_location.insert("region".to_owned(), region);
+        Some(Json::Object(physical_location))
+    }
+
+    fn artifact_location_and_region(&self, span: Span) -> Option<(Json, Json)> {
+        if span.is_dummy() {
+            return None;
+        }
+        let start = self.sm.lookup_char_pos(span.lo());
+        let end = self.sm.lookup_char_pos(span.hi());
+        if !start.file.name.is_real() {
+            return None;
+        }
+
+        let mut artifact_location = BTreeMap::new();
+        let file_name = start.file.name.to_string();
+        let is_absolute = std::path::Path::new(&file_name).is_absolute();
+        artifact_location.insert("uri".to_owned(), file_uri(&file_name, is_absolute).to_json());
+        if !is_absolute {
+            artifact_location.insert("uriBaseId".to_owned(), "%SRCROOT%".to_json());
+        }
+
+        // Columns are 1-based and `endColumn` points past the end of the region,
+        // like the `column_end` of the JSON emitter.
+        let mut region = BTreeMap::new();
+        region.insert("startLine".to_owned(), start.line.to_json());
+        region.insert("startColumn".to_owned(), (start.col.0 + 1).to_json());
+        region.insert("endLine".to_owned(), end.line.to_json());
+        region.insert("endColumn".to_owned(), (end.col.0 + 1).to_json());
+        let byte_start = start.file.original_relative_byte_pos(span.lo()).0;
+        let byte_end = start.file.original_relative_byte_pos(span.hi()).0;
+        region.insert("byteOffset".to_owned(), byte_start.to_json());
+        region.insert("byteLength".to_owned(), (byte_end - byte_start).to_json());
+        if let Ok(snippet) = self.sm.span_to_snippet(span) {
+            region.insert("snippet".to_owned(), message(&snippet));
+        }
+
+        Some((Json::Object(artifact_location), Json::Object(region)))
+    }
+
+    /// Converts each alternative substitution of `suggestion` into a SARIF `fix`.
+    fn fixes(&self, suggestion: &CodeSuggestion) -> Vec<Json> {
+        suggestion
+            .substitutions
+            .iter()
+            .filter_map(|substitution| {
+                // SARIF groups the replacements of a fix by the file they apply to.
+                let mut changes: Vec<(Json, Vec<Json>)> = Vec::new();
+                for part in &substitution.parts {
+                    let (artifact_location, region) =
+                        self.artifact_location_and_region(part.span)?;
+                    let mut replacement = BTreeMap::new();
+                    replacement.insert("deletedRegion".to_owned(), region);
+                    replacement.insert("insertedContent".to_owned(), message(&part.snippet));
+                    let replacement = Json::Object(replacement);
+                    match changes.iter_mut().find(|(location, _)| *location == artifact_location) {
+                        Some((_, replacements)) => replacements.push(replacement),
+                        None => changes.push((artifact_location, vec![replacement])),
+                    }
+                }
+
+                let changes = changes
+                    .into_iter()
+                    .map(|(artifact_location, replacements)| {
+                        let mut change = BTreeMap::new();
+                        change.insert("artifactLocation".to_owned(), artifact_location);
+                        change.insert("replacements".to_owned(), Json::Array(replacements));
+                        Json::Object(change)
+                    })
+                    .collect();
+                let mut fix = BTreeMap::new();
+                fix.insert("description".to_owned(), message(&suggestion.msg));
+                fix.insert("artifactChanges".to_owned(), Json::Array(changes));
+                let mut properties = BTreeMap::new();
+                properties.insert(
+                    "applicability".to_owned(),
+                    applicability_str(suggestion.applicability).to_json(),
+                );
+                fix.insert("properties".to_owned(), Json::Object(properties));
+                Some(Json::Object(fix))
+            })
+            .collect()
+    }
+
+    fn log(&mut self) -> Json {
+        let mut driver = BTreeMap::new();
+        driver.insert("name".to_owned(), "rustc".to_json());
+        driver.insert("informationUri".to_owned(), "https://www.rust-lang.org/".to_json());
+        if let Some(version) = option_env!("CFG_VERSION") {
+            driver.insert("version".to_owned(), version.to_json());
+        }
+        driver.insert("rules".to_owned(), Json::Array(std::mem::take(&mut self.rules)));
+        let mut tool = BTreeMap::new();
+        tool.insert("driver".to_owned(), Json::Object(driver));
+
+        let mut run = BTreeMap::new();
+        run.insert("tool".to_owned(), Json::Object(tool));
+        run.insert("results".to_owned(), Json::Array(std::mem::take(&mut self.results)));
+        // Columns are counted in characters rather than the default UTF-16 code units.
+        run.insert("columnKind".to_owned(), "unicodeCodePoints".to_json());
+
+        let mut log = BTreeMap::new();
+        log.insert("$schema".to_owned(), SARIF_SCHEMA.to_json());
+        log.insert("version".to_owned(), SARIF_VERSION.to_json());
+        log.insert("runs".to_owned(), Json::Array(vec![Json::Object(run)]));
+        Json::Object(log)
+    }
+
+    fn write_log(&mut self) -> io::Result<()> {
+        let log = self.log();
+        if self.pretty {
+            writeln!(&mut self.dst, "{}", log.pretty())?;
+        } else {
+            writeln!(&mut self.dst, "{}", log)?;
+        }
+        self.dst.flush()
+    }
+}
+
+impl Emitter for SarifEmitter {
+    fn emit_diagnostic(&mut self, diag: &crate::Diagnostic) {
+        match diag.level {
+            // "aborting due to previous error" and similar notes only make
+            // sense in the human-readable output.
+            Level::FailureNote | Level::Cancelled | Level::Allow => {}
+            _ => match &self.buffer {
+                Some(buffer) => buffer.lock().push(diag.clone()),
+                None => {
+                    let result = self.result(diag);
+                    self.results.push(result);
+                }
+            },
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.buffer.is_some() || self.finished {
+            return;
+        }
+        self.finished = true;
+        // This may run while unwinding from a fatal error or an ICE, so
+        // failing to write the log must not panic.
+        if let Err(e) = self.write_log() {
+            let _ = writeln!(io::stderr(), "error: failed to write the SARIF log: {}", e);
+        }
+    }
+
+    fn source_map(&self) -> Option<&Lrc<SourceMap>> {
+        Some(&self.sm)
+    }
+
+    fn should_show_explain(&self) -> bool {
+        false
+    }
+}
+
+fn message(text: &str) -> Json {
+    let mut message = BTreeMap::new();
+    message.insert("text".to_owned(), text.to_json());
+    Json::Object(message)
+}
+
+fn sarif_level(level: Level) -> &'static str {
+    match level {
+        Level::Bug | Level::Fatal | Level::Error => "error",
+        Level::Warning => "warning",
+        Level::Note | Level::Help => "note",
+        Level::Cancelled | Level::FailureNote | Level::Allow => "none",
+    }
+}
+
+fn applicability_str(applicability: Applicability) -> &'static str {
+    match applicability {
+        Applicability::MachineApplicable => "MachineApplicable",
+        Applicability::MaybeIncorrect => "MaybeIncorrect",
+        Applicability::HasPlaceholders => "HasPlaceholders",
+        Applicability::Unspecified => "Unspecified",
+    }
+}
+
+/// Converts a file name to a URI, percent-encoding everything but unreserved
+/// characters and path separators.
+fn file_uri(file_name: &str, is_absolute: bool) -> String {
+    let mut uri = String::new();
+    if is_absolute {
+        uri.push_str("file://");
+        // Windows paths have no leading slash of their own.
+        if !file_name.starts_with('/') {
+            uri.push('/');
+        }
+    }
+    for byte in file_name.bytes() {
+        match byte {
+            b'\\' => uri.push('/'),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                uri.push(byte as char)
+            }
+            // Keep the drive letter of Windows paths readable.
+            b':' if is_absolute => uri.push(':'),
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
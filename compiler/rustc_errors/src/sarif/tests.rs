@@ -0,0 +1,162 @@
+use super::*;
+
+use crate::Handler;
+use rustc_serialize::json;
+use rustc_span::source_map::{FilePathMapping, SourceMap};
+use rustc_span::{BytePos, Span};
+
+use std::path::Path;
+use std::str;
+use std::sync::{Arc, Mutex};
+
+struct Shared<T> {
+    data: Arc<Mutex<T>>,
+}
+
+impl<T: Write> Write for Shared<T> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.data.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.data.lock().unwrap().flush()
+    }
+}
+
+fn with_default_session_globals(f: impl FnOnce()) {
+    let session_globals = rustc_span::SessionGlobals::new(rustc_span::edition::DEFAULT_EDITION);
+    rustc_span::SESSION_GLOBALS.set(&session_globals, f);
+}
+
+/// Runs `f` against a handler emitting SARIF for `code`, and returns the log
+/// written once the handler is finished.
+fn emit(code: &str, f: impl FnOnce(&Handler)) -> Json {
+    let mut log = None;
+    with_default_session_globals(|| {
+        let sm = Lrc::new(SourceMap::new(FilePathMapping::empty()));
+        sm.new_source_file(Path::new("test.rs").to_owned().into(), code.to_owned());
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let emitter = SarifEmitter::new(Box::new(Shared { data: output.clone() }), None, sm, false);
+        let handler = Handler::with_emitter(true, None, Box::new(emitter));
+        f(&handler);
+        handler.finish_emitter();
+        // Finishing the handler again must not write a second log.
+        handler.finish_emitter();
+
+        let bytes = output.lock().unwrap();
+        log = Some(json::from_str(str::from_utf8(&bytes).unwrap()).unwrap());
+    });
+    log.unwrap()
+}
+
+fn span(lo: u32, hi: u32) -> Span {
+    Span::with_root_ctxt(BytePos(lo), BytePos(hi))
+}
+
+fn results(log: &Json) -> &[Json] {
+    log["runs"][0]["results"].as_array().unwrap()
+}
+
+#[test]
+fn empty_log() {
+    let log = emit("", |_| {});
+    assert_eq!(log.find("version").unwrap().as_string(), Some("2.1.0"));
+    assert!(results(&log).is_empty());
+}
+
+#[test]
+fn locations() {
+    let log = emit("fn main() {\n    let x = 1;\n}\n", |handler| {
+        handler
+            .struct_span_err_with_code(span(20, 21), "foo", DiagnosticId::Error("E0001".into()))
+            .span_label(span(3, 7), "bar")
+            .emit();
+    });
+    let result = &results(&log)[0];
+    assert_eq!(result.find("ruleId").unwrap().as_string(), Some("E0001"));
+    assert_eq!(result.find("level").unwrap().as_string(), Some("error"));
+    assert_eq!(result.find_path(&["message", "text"]).unwrap().as_string(), Some("foo"));
+
+    let region = result["locations"][0].find_path(&["physicalLocation", "region"]).unwrap();
+    assert_eq!(region.find("startLine").unwrap().as_u64(), Some(2));
+    assert_eq!(region.find("startColumn").unwrap().as_u64(), Some(9));
+    assert_eq!(region.find("endColumn").unwrap().as_u64(), Some(10));
+    assert_eq!(region.find_path(&["snippet", "text"]).unwrap().as_string(), Some("x"));
+
+    let related = &result.find("relatedLocations").unwrap()[0];
+    assert_eq!(related.find_path(&["message", "text"]).unwrap().as_string(), Some("bar"));
+    let region = related.find_path(&["physicalLocation", "region"]).unwrap();
+    assert_eq!(region.find("startLine").unwrap().as_u64(), Some(1));
+
+    let rule = &log["runs"][0].find_path(&["tool", "driver", "rules"]).unwrap()[0];
+    assert_eq!(rule.find("id").unwrap().as_string(), Some("E0001"));
+}
+
+#[test]
+fn notes_and_fixes() {
+    let log = emit("fn main() {\n    let x = 1;\n}\n", |handler| {
+        handler
+            .struct_span_warn(span(20, 21), "unused variable: `x`")
+            .note("some note")
+            .span_suggestion(
+                span(20, 21),
+                "if this is intentional, prefix it with an underscore",
+                "_x".into(),
+                Applicability::MachineApplicable,
+            )
+            .emit();
+    });
+    let result = &results(&log)[0];
+    assert_eq!(result.find("level").unwrap().as_string(), Some("warning"));
+    assert_eq!(
+        result.find_path(&["message", "text"]).unwrap().as_string(),
+        Some("unused variable: `x`\nnote: some note")
+    );
+
+    let fix = &result.find("fixes").unwrap()[0];
+    assert_eq!(
+        fix.find_path(&["properties", "applicability"]).unwrap().as_string(),
+        Some("MachineApplicable")
+    );
+    let change = &fix.find("artifactChanges").unwrap()[0];
+    let uri = change.find_path(&["artifactLocation", "uri"]).unwrap();
+    assert_eq!(uri.as_string(), Some("test.rs"));
+    let replacement = &change.find("replacements").unwrap()[0];
+    assert_eq!(
+        replacement.find_path(&["insertedContent", "text"]).unwrap().as_string(),
+        Some("_x")
+    );
+    assert_eq!(
+        replacement.find_path(&["deletedRegion", "startColumn"]).unwrap().as_u64(),
+        Some(9)
+    );
+}
+
+#[test]
+fn uris() {
+    assert_eq!(file_uri("src/my file.rs", false), "src/my%20file.rs");
+    assert_eq!(file_uri("/home/user/lib.rs", true), "file:///home/user/lib.rs");
+    assert_eq!(file_uri("C:\\src\\lib.rs", true), "file:///C:/src/lib.rs");
+}
+
+#[test]
+fn buffered_diagnostics_are_part_of_the_session_log() {
+    let log = emit("", |handler| {
+        let buffer = Lrc::new(Lock::new(Vec::new()));
+        let early =
+            Handler::with_emitter(true, None, Box::new(SarifEmitter::buffered(buffer.clone())));
+        early.struct_warn("early warning").emit();
+        // A buffered emitter writes no log of its own.
+        early.finish_emitter();
+        drop(early);
+
+        handler.emit_buffered_diagnostics(buffer.lock().drain(..).collect());
+        handler.struct_warn("late warning").emit();
+    });
+    let messages: Vec<_> = results(&log)
+        .iter()
+        .map(|result| result.find_path(&["message", "text"]).unwrap().as_string().unwrap())
+        .collect();
+    assert_eq!(messages, ["early warning", "late warning"]);
+}
@@ -13,8 +13,10 @@
 mod assert_dep_graph;
 pub mod assert_module_sources;
 mod persist;
+mod query_graph;
 
 use assert_dep_graph::assert_dep_graph;
+use query_graph::dump_query_graph;
 pub use persist::copy_cgu_workproduct_to_incr_comp_cache_dir;
 pub use persist::delete_workproduct_files;
 pub use persist::finalize_session_directory;
@@ -50,6 +50,15 @@
 //! any more and will delete those. It will also delete any finalized session
 //! directories for a given crate except for the most recent one.
 //!
+//! With `-Z incremental-max-size`, the compiler also limits the size of the
+//! whole incremental compilation directory, which holds the session
+//! directories of every crate compiled into it. When it has finalized its
+//! session directory, it adds up the size of the finalized session directories
+//! of all crates, including the work products saved in them, and deletes the
+//! least recently used ones (the ones with the oldest timestamps) until the
+//! total fits. Its own session directory is never deleted, and neither are the
+//! directories other processes are using, as described below.
+//!
 //! ## Synchronization
 //!
 //! There is some synchronization needed in order for the compiler to be able to
@@ -388,6 +397,10 @@ pub fn finalize_session_directory(sess: &Session, svh: Svh) {
     }
 
     let _ = garbage_collect_session_directories(sess);
+
+    if let Some(max_size) = sess.opts.debugging_opts.incremental_max_size {
+        let _ = garbage_collect_to_max_size(sess, max_size);
+    }
 }
 
 pub fn delete_all_session_dir_contents(sess: &Session) -> io::Result<()> {
@@ -919,6 +932,141 @@ fn all_except_most_recent(
     }
 }
 
+/// Deletes the least recently used finalized session directories in the
+/// incremental compilation directory, of any crate, until their total size is
+/// at most `max_size` bytes.
+///
+/// "Least recently used" means the oldest session directories, by the time
+/// their session started, as recorded in their name. Session directories are
+/// only read by the next session of the same crate, which copies them into a
+/// new one, so a session directory is last used when it is created. Access
+/// times are not looked at, as they are often not updated.
+fn garbage_collect_to_max_size(sess: &Session, max_size: u64) -> io::Result<()> {
+    debug!("garbage_collect_to_max_size({}) - begin", max_size);
+
+    // The session directory path is canonicalized, so canonicalize the paths
+    // of the other session directories as well, to recognize it among them.
+    let incr_dir = sess.opts.incremental.as_ref().unwrap().canonicalize()?;
+    let session_directory = sess.incr_comp_session_dir();
+
+    let mut sessions = vec![];
+    for crate_dir_entry in incr_dir.read_dir()? {
+        let crate_directory = match crate_dir_entry {
+            Ok(entry) if entry.file_type().map_or(false, |file_type| file_type.is_dir()) => {
+                entry.path()
+            }
+            _ => continue,
+        };
+        let dir_entries = match crate_directory.read_dir() {
+            Ok(dir_entries) => dir_entries,
+            Err(_) => continue,
+        };
+
+        for dir_entry in dir_entries {
+            let dir_entry = match dir_entry {
+                Ok(dir_entry) => dir_entry,
+                _ => continue,
+            };
+            let directory_name = dir_entry.file_name();
+            let directory_name = directory_name.to_string_lossy();
+            if !is_session_directory(&directory_name) || !is_finalized(&directory_name) {
+                continue;
+            }
+            let timestamp = match extract_timestamp_from_session_dir(&directory_name) {
+                Ok(timestamp) => timestamp,
+                Err(()) => continue,
+            };
+            let path = dir_entry.path();
+            let size = directory_size(&path);
+            sessions.push((timestamp, path, size));
+        }
+    }
+
+    let mut total_size: u64 = sessions.iter().map(|&(_, _, size)| size).sum();
+    debug!("garbage_collect_to_max_size() - total size: {}", total_size);
+
+    for (path, size) in least_recently_used_first(sessions, &session_directory) {
+        if total_size <= max_size {
+            break;
+        }
+
+        // Like when collecting the finalized session directories of a single
+        // crate, hold an exclusive lock while deleting, and leave directories
+        // alone that another process is copying from.
+        let lock = match flock::Lock::new(
+            &lock_file_path(&path),
+            false, // don't wait
+            false, // don't create the lock-file
+            true,  // get an exclusive lock
+        ) {
+            Ok(lock) => lock,
+            Err(_) => {
+                debug!("garbage_collect_to_max_size() - not collecting, still in use");
+                continue;
+            }
+        };
+
+        debug!("garbage_collect_to_max_size() - deleting `{}`", path.display());
+        if let Err(err) = safe_remove_dir_all(&path) {
+            sess.warn(&format!(
+                "Failed to garbage collect incremental compilation session directory `{}`: {}",
+                path.display(),
+                err
+            ));
+        } else {
+            delete_session_dir_lock_file(sess, &lock_file_path(&path));
+            total_size -= size;
+        }
+
+        mem::drop(lock);
+    }
+
+    if total_size > max_size && sess.opts.debugging_opts.incremental_info {
+        eprintln!(
+            "[incremental] incremental compilation directory still uses {} bytes, \
+             more than the maximum of {} bytes",
+            total_size, max_size
+        );
+    }
+
+    Ok(())
+}
+
+/// Orders the session directories for eviction, the least recently used (that
+/// is, the oldest) first, leaving out the session directory of the current
+/// session.
+fn least_recently_used_first(
+    mut sessions: Vec<(SystemTime, PathBuf, u64)>,
+    session_directory: &Path,
+) -> Vec<(PathBuf, u64)> {
+    sessions.sort_by_key(|&(timestamp, ..)| timestamp);
+    sessions
+        .into_iter()
+        .filter(|(_, path, _)| path != session_directory)
+        .map(|(_, path, size)| (path, size))
+        .collect()
+}
+
+/// The total size of the files in a session directory and its subdirectories:
+/// the dependency graph, the query result cache and the work products. Files
+/// that cannot be read are not counted, and symbolic links are not followed.
+fn directory_size(path: &Path) -> u64 {
+    let dir_entries = match path.read_dir() {
+        Ok(dir_entries) => dir_entries,
+        Err(_) => return 0,
+    };
+    dir_entries
+        .filter_map(|dir_entry| dir_entry.ok())
+        .map(|dir_entry| match dir_entry.file_type() {
+            Ok(file_type) if file_type.is_dir() => directory_size(&dir_entry.path()),
+            Ok(file_type) if file_type.is_file() => {
+                dir_entry.metadata().map_or(0, |metadata| metadata.len())
+            }
+            _ => 0,
+        })
+        .sum()
+}
+
 /// Since paths of artifacts within session directories can get quite long, we
 /// need to support deleting files with very long paths. The regular
 /// WinApi functions only support paths up to 260 characters, however. In order
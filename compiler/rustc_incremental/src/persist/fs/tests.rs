@@ -82,3 +82,25 @@ fn test_find_source_directory_in_iter() {
         None
     );
 }
+
+#[test]
+fn test_least_recently_used_first() {
+    assert_eq!(
+        least_recently_used_first(
+            vec![
+                (UNIX_EPOCH + Duration::new(3, 0), PathBuf::from("b/s-3"), 30),
+                (UNIX_EPOCH + Duration::new(1, 0), PathBuf::from("a/s-1"), 10),
+                (UNIX_EPOCH + Duration::new(4, 0), PathBuf::from("c/s-4"), 40),
+                (UNIX_EPOCH + Duration::new(2, 0), PathBuf::from("c/s-2"), 20),
+            ],
+            Path::new("b/s-3"),
+        ),
+        vec![
+            (PathBuf::from("a/s-1"), 10),
+            (PathBuf::from("c/s-2"), 20),
+            (PathBuf::from("c/s-4"), 40),
+        ]
+    );
+
+    assert_eq!(least_recently_used_first(vec![], Path::new("a/s-1")), vec![]);
+}
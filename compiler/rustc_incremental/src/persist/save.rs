@@ -25,6 +25,10 @@ pub fn save_dep_graph(tcx: TyCtxt<'_>) {
         if sess.opts.incremental.is_none() {
             return;
         }
+        // Query cycles are reported as errors, so dump the query graph before checking for them.
+        if let Some(path) = &sess.opts.debugging_opts.dump_query_graph {
+            sess.time("dump_query_graph", || crate::dump_query_graph(tcx, path));
+        }
         // This is going to be deleted in finalize_session_directory, so let's not create it
         if sess.has_errors_or_delayed_span_bugs() {
             return;
@@ -232,7 +236,9 @@ pub fn build_dep_graph(
         prev_graph,
         prev_work_products,
         encoder,
-        sess.opts.debugging_opts.query_dep_graph,
+        sess.opts.debugging_opts.query_dep_graph
+            || sess.opts.debugging_opts.dump_query_graph.is_some(),
         sess.opts.debugging_opts.incremental_info,
+        sess.opts.debugging_opts.dump_query_graph.is_some(),
     ))
 }
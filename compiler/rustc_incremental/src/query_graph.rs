@@ -0,0 +1,116 @@
+//! Writes the queries run in the current session as JSON, for `-Z dump-query-graph`.
+//!
+//! Every node of the current dependency graph is listed with its query, the description of its
+//! key, its incremental status (`green` and `red` for nodes of the previous session, `new`
+//! otherwise), how its result was produced (`executed`, `loaded` from the on-disk cache,
+//! `recomputed` because it is not cached on disk, or `reused` if it was marked green but never
+//! needed), the time that took in microseconds (including the queries it invoked), and its
+//! number of in-memory cache hits. Edges point from a node to the nodes it read. Dependency
+//! cycles are listed separately, as the queries of the cycle.
+
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::graph::implementation::NodeIndex;
+use rustc_middle::dep_graph::{DepGraphQuery, QueryResultSource};
+use rustc_middle::ty::TyCtxt;
+use rustc_serialize::json::Json;
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+pub fn dump_query_graph(tcx: TyCtxt<'_>, path: &Path) {
+    let mut result = Ok(());
+    tcx.dep_graph.with_query(|query| {
+        result = File::create(path)
+            .and_then(|file| write_query_graph(tcx, query, &mut BufWriter::new(file)));
+    });
+    if let Err(err) = result {
+        tcx.sess.err(&format!("failed to write query graph to `{}`: {}", path.display(), err));
+    }
+}
+
+fn write_query_graph(
+    tcx: TyCtxt<'_>,
+    query: &DepGraphQuery,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let timings = match tcx.dep_graph.query_timings() {
+        Some(timings) => timings,
+        None => return Ok(()),
+    };
+    let dep_node_indices: FxHashMap<_, _> = query
+        .dep_index_to_index
+        .iter_enumerated()
+        .filter_map(|(dep_node_index, index)| Some(((*index)?, dep_node_index)))
+        .collect();
+
+    writeln!(out, "{{\"nodes\":[")?;
+    let nodes = query.graph.all_nodes();
+    for (id, node) in nodes.iter().enumerate() {
+        let dep_node = &node.data;
+        let query_name = format!("{:?}", dep_node.kind);
+        let description = format!("{:?}", dep_node);
+        let key = description
+            .strip_prefix(&query_name)
+            .and_then(|key| key.strip_prefix('('))
+            .and_then(|key| key.strip_suffix(')'))
+            .unwrap_or("");
+        let status = if tcx.dep_graph.is_green(dep_node) {
+            "green"
+        } else if tcx.dep_graph.is_red(dep_node) {
+            "red"
+        } else {
+            "new"
+        };
+        let timing = dep_node_indices
+            .get(&NodeIndex(id))
+            .map(|&dep_node_index| timings.node(dep_node_index))
+            .unwrap_or_default();
+        let source = match timing.source {
+            Some(QueryResultSource::Executed) => "executed",
+            Some(QueryResultSource::Loaded) => "loaded",
+            Some(QueryResultSource::Recomputed) => "recomputed",
+            None => "reused",
+        };
+
+        let mut object = BTreeMap::new();
+        object.insert("id".to_owned(), Json::U64(id as u64));
+        object.insert("query".to_owned(), Json::String(query_name));
+        object.insert("key".to_owned(), Json::String(key.to_owned()));
+        object.insert("status".to_owned(), Json::String(status.to_owned()));
+        object.insert("source".to_owned(), Json::String(source.to_owned()));
+        object.insert(
+            "duration_us".to_owned(),
+            Json::F64(timing.duration.as_nanos() as f64 / 1000.0),
+        );
+        object.insert("cache_hits".to_owned(), Json::U64(timing.cache_hits.into()));
+        let separator = if id + 1 < nodes.len() { "," } else { "" };
+        writeln!(out, "{}{}", Json::Object(object), separator)?;
+    }
+
+    writeln!(out, "],\"edges\":[")?;
+    let edges = query.graph.all_edges();
+    for (i, edge) in edges.iter().enumerate() {
+        let separator = if i + 1 < edges.len() { "," } else { "" };
+        writeln!(out, "[{},{}]{}", edge.source().node_id(), edge.target().node_id(), separator)?;
+    }
+
+    writeln!(out, "],\"cycles\":[")?;
+    let cycles = timings.cycles();
+    for (i, cycle) in cycles.iter().enumerate() {
+        let frames = cycle
+            .iter()
+            .map(|(name, description)| {
+                let mut object = BTreeMap::new();
+                object.insert("query".to_owned(), Json::String((*name).to_owned()));
+                object.insert("description".to_owned(), Json::String(description.clone()));
+                Json::Object(object)
+            })
+            .collect();
+        let separator = if i + 1 < cycles.len() { "," } else { "" };
+        writeln!(out, "{}{}", Json::Array(frames), separator)?;
+    }
+    writeln!(out, "]}}")?;
+    out.flush()
+}
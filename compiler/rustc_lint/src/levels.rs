@@ -16,6 +16,7 @@
 };
 use rustc_middle::ty::query::Providers;
 use rustc_middle::ty::TyCtxt;
+use rustc_serialize::json::{Json, ToJson};
 use rustc_session::lint::{
     builtin::{self, FORBIDDEN_LINT_GROUPS},
     Level, Lint, LintId,
@@ -27,6 +28,7 @@
 use tracing::debug;
 
 use std::cmp;
+use std::collections::BTreeMap;
 
 fn lint_levels(tcx: TyCtxt<'_>, cnum: CrateNum) -> LintLevelMap {
     assert_eq!(cnum, LOCAL_CRATE);
@@ -681,6 +683,93 @@ fn visit_impl_item(&mut self, impl_item: &'tcx hir::ImplItem<'tcx>) {
     }
 }
 
+/// Prints, as JSON, the level of every lint in every scope that changes lint
+/// levels, together with the source of that level. This implements
+/// `--print lint-levels`.
+pub fn print_lint_levels(tcx: TyCtxt<'_>) {
+    let store = unerased_lint_store(tcx);
+    let map = tcx.lint_levels(LOCAL_CRATE);
+    let sess = tcx.sess;
+
+    let mut lints = store.get_lints().to_vec();
+    lints.sort_by_cached_key(|lint| lint.name_lower());
+
+    let mut scopes: Vec<_> = map
+        .id_to_set
+        .iter()
+        .map(|(&id, &idx)| (tcx.def_path_str(id.owner.to_def_id()), id, idx))
+        .collect();
+    scopes.sort_by(|(path1, id1, _), (path2, id2, _)| {
+        path1.cmp(path2).then(id1.local_id.cmp(&id2.local_id))
+    });
+
+    let scopes = scopes
+        .into_iter()
+        .map(|(path, id, idx)| {
+            let lints = lints
+                .iter()
+                .map(|&lint| {
+                    let (specified_level, src) =
+                        map.sets.get_uncapped_lint_level(lint, idx, None, sess);
+                    let level = map.sets.cap_lint_level(lint, specified_level, sess);
+                    let mut entry = BTreeMap::new();
+                    entry.insert("name".to_owned(), lint.name_lower().to_json());
+                    entry.insert("level".to_owned(), level.as_str().to_json());
+                    entry.insert("specified_level".to_owned(), specified_level.as_str().to_json());
+                    entry.insert("capped".to_owned(), (level != specified_level).to_json());
+                    entry.insert("source".to_owned(), lint_level_source_to_json(tcx, store, src));
+                    Json::Object(entry)
+                })
+                .collect();
+
+            let mut scope = BTreeMap::new();
+            scope.insert("path".to_owned(), path.to_json());
+            scope.insert("local_id".to_owned(), id.local_id.as_u32().to_json());
+            let span = tcx.hir().span(id);
+            scope.insert("span".to_owned(), sess.source_map().span_to_string(span).to_json());
+            scope.insert("lints".to_owned(), Json::Array(lints));
+            Json::Object(scope)
+        })
+        .collect();
+
+    let mut output = BTreeMap::new();
+    output.insert("crate".to_owned(), tcx.crate_name(LOCAL_CRATE).to_string().to_json());
+    output.insert("lint_cap".to_owned(), map.sets.lint_cap.as_str().to_json());
+    output.insert("scopes".to_owned(), Json::Array(scopes));
+    println!("{}", Json::Object(output));
+}
+
+fn lint_level_source_to_json(tcx: TyCtxt<'_>, store: &LintStore, src: LintLevelSource) -> Json {
+    let mut source = BTreeMap::new();
+    let kind = match src {
+        LintLevelSource::Default => "default",
+        LintLevelSource::Node(_, span, reason) => {
+            let span = tcx.sess.source_map().span_to_string(span);
+            source.insert("span".to_owned(), span.to_json());
+            source.insert("reason".to_owned(), reason.map(|r| r.to_string()).to_json());
+            "attribute"
+        }
+        LintLevelSource::CommandLine(_, flag_level) => {
+            let flag = match flag_level {
+                Level::Allow => "-A",
+                Level::Warn => "-W",
+                Level::Deny => "-D",
+                Level::Forbid => "-F",
+            };
+            source.insert("flag".to_owned(), flag.to_json());
+            "command-line"
+        }
+    };
+    source.insert("kind".to_owned(), kind.to_json());
+    if src != LintLevelSource::Default {
+        // The lint or lint group named by the attribute or flag.
+        let name = src.name();
+        source.insert("name".to_owned(), name.to_string().to_json());
+        source.insert("is_group".to_owned(), store.is_lint_group(name).to_json());
+    }
+    Json::Object(source)
+}
+
 pub fn provide(providers: &mut Providers) {
     providers.lint_levels = lint_levels;
 }
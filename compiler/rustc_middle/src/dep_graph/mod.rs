@@ -8,7 +8,7 @@
 mod dep_node;
 
 pub use rustc_query_system::dep_graph::{
-    debug::DepNodeFilter, hash_result, DepContext, DepNodeColor, DepNodeIndex,
+    debug::DepNodeFilter, hash_result, DepContext, DepNodeColor, DepNodeIndex, QueryResultSource,
     SerializedDepNodeIndex, WorkProduct, WorkProductId,
 };
 
@@ -3,7 +3,7 @@
 use crate::ich::StableHashingContext;
 use rustc_data_structures::fx::FxHashMap;
 use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
-use rustc_errors::{DiagnosticBuilder, DiagnosticId};
+use rustc_errors::{DiagnosticBuilder, DiagnosticId, LintSource};
 use rustc_hir::HirId;
 use rustc_session::lint::{
     builtin::{self, FORBIDDEN_LINT_GROUPS},
@@ -82,6 +82,19 @@ pub fn get_lint_level(
         idx: u32,
         aux: Option<&FxHashMap<LintId, LevelAndSource>>,
         sess: &Session,
+    ) -> LevelAndSource {
+        let (level, src) = self.get_uncapped_lint_level(lint, idx, aux, sess);
+        (self.cap_lint_level(lint, level, sess), src)
+    }
+
+    /// Like `get_lint_level`, but without applying `--cap-lints` or the caps
+    /// set by the driver.
+    pub fn get_uncapped_lint_level(
+        &self,
+        lint: &'static Lint,
+        idx: u32,
+        aux: Option<&FxHashMap<LintId, LevelAndSource>>,
+        sess: &Session,
     ) -> LevelAndSource {
         let (level, mut src) = self.get_lint_id_level(LintId::of(lint), idx, aux);
 
@@ -108,6 +121,11 @@ pub fn get_lint_level(
             }
         }
 
+        (level, src)
+    }
+
+    /// Lowers `level` to the `--cap-lints` argument and the driver's cap for `lint`.
+    pub fn cap_lint_level(&self, lint: &'static Lint, mut level: Level, sess: &Session) -> Level {
         // Ensure that we never exceed the `--cap-lints` argument.
         level = cmp::min(level, self.lint_cap);
 
@@ -116,7 +134,7 @@ pub fn get_lint_level(
             level = cmp::min(*driver_level, level);
         }
 
-        (level, src)
+        level
     }
 
     pub fn get_lint_id_level(
@@ -215,6 +233,11 @@ pub fn build(mut self, msg: &str) -> DiagnosticBuilder<'a> {
         self.0
     }
 
+    /// Set the path of the item the lint is emitted in, see `Diagnostic::item_path`.
+    pub fn set_item_path(&mut self, path: String) {
+        self.0.item_path = Some(path);
+    }
+
     /// Create a LintDiagnosticBuilder from some existing DiagnosticBuilder.
     pub fn new(err: DiagnosticBuilder<'a>) -> LintDiagnosticBuilder<'a> {
         LintDiagnosticBuilder(err)
@@ -292,6 +315,7 @@ fn struct_lint_level_impl(
                     DiagnosticMessageId::from(lint),
                     &format!("`#[{}({})]` on by default", level.as_str(), name),
                 );
+                err.lint_source(LintSource::Default);
             }
             LintLevelSource::CommandLine(lint_flag_val, orig_level) => {
                 let flag = match orig_level {
@@ -300,6 +324,10 @@ fn struct_lint_level_impl(
                     Level::Forbid => "-F",
                     Level::Allow => "-A",
                 };
+                err.lint_source(LintSource::CommandLine {
+                    flag: flag.to_owned(),
+                    name: lint_flag_val.to_string(),
+                });
                 let hyphen_case_lint_name = name.replace("_", "-");
                 if lint_flag_val.as_str() == name {
                     sess.diag_note_once(
@@ -323,6 +351,11 @@ fn struct_lint_level_impl(
                 }
             }
             LintLevelSource::Node(lint_attr_name, src, reason) => {
+                err.lint_source(LintSource::Attribute {
+                    name: lint_attr_name.to_string(),
+                    span: src,
+                    reason: reason.map(|reason| reason.to_string()),
+                });
                 if let Some(rationale) = reason {
                     err.note(&rationale.as_str());
                 }
@@ -113,6 +113,13 @@ pub enum CoverageKind {
         rhs: ExpressionOperandId,
     },
     Unreachable,
+    /// A branch region, reporting how often a condition evaluated to `true` and to `false`. The
+    /// operands refer to counters or expressions injected elsewhere in the function, so a
+    /// `Branch` does not generate any executable code.
+    Branch {
+        true_operand: ExpressionOperandId,
+        false_operand: ExpressionOperandId,
+    },
 }
 
 impl CoverageKind {
@@ -122,12 +129,17 @@ pub fn as_operand_id(&self) -> ExpressionOperandId {
             Counter { id, .. } => ExpressionOperandId::from(id),
             Expression { id, .. } => ExpressionOperandId::from(id),
             Unreachable => bug!("Unreachable coverage cannot be part of an expression"),
+            Branch { .. } => bug!("Branch coverage cannot be part of an expression"),
         }
     }
 
     pub fn is_expression(&self) -> bool {
         matches!(self, Self::Expression { .. })
     }
+
+    pub fn is_branch(&self) -> bool {
+        matches!(self, Self::Branch { .. })
+    }
 }
 
 impl Debug for CoverageKind {
@@ -144,6 +156,12 @@ fn fmt(&self, fmt: &mut Formatter<'_>) -> fmt::Result {
                 rhs.index(),
             ),
             Unreachable => write!(fmt, "Unreachable"),
+            Branch { true_operand, false_operand } => write!(
+                fmt,
+                "Branch(true: {}, false: {})",
+                true_operand.index(),
+                false_operand.index(),
+            ),
         }
     }
 }
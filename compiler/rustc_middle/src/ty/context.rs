@@ -14,6 +14,7 @@
 use crate::mir::interpret::{self, Allocation, ConstValue, Scalar};
 use crate::mir::{Body, Field, Local, Place, PlaceElem, ProjectionKind, Promoted};
 use crate::traits;
+use crate::ty::print::with_no_trimmed_paths;
 use crate::ty::query::{self, OnDiskCache, TyCtxtAt};
 use crate::ty::subst::{GenericArg, GenericArgKind, InternalSubsts, Subst, SubstsRef, UserSubsts};
 use crate::ty::TyKind::*;
@@ -2642,7 +2643,9 @@ pub fn struct_span_lint_hir(
         decorate: impl for<'a> FnOnce(LintDiagnosticBuilder<'a>),
     ) {
         let (level, src) = self.lint_level_at_node(lint, hir_id);
-        struct_lint_level(self.sess, lint, level, src, Some(span.into()), decorate);
+        struct_lint_level(self.sess, lint, level, src, Some(span.into()), |lint| {
+            decorate(self.record_lint_item_path(lint, hir_id))
+        });
     }
 
     pub fn struct_lint_node(
@@ -2652,7 +2655,23 @@ pub fn struct_lint_node(
         decorate: impl for<'a> FnOnce(LintDiagnosticBuilder<'a>),
     ) {
         let (level, src) = self.lint_level_at_node(lint, id);
-        struct_lint_level(self.sess, lint, level, src, None, decorate);
+        struct_lint_level(self.sess, lint, level, src, None, |lint| {
+            decorate(self.record_lint_item_path(lint, id))
+        });
+    }
+
+    /// Records the path of the item containing `id` on `lint`, so that
+    /// `-Z diagnostic-baseline` can tell apart identical lints in different items.
+    fn record_lint_item_path<'a>(
+        self,
+        mut lint: LintDiagnosticBuilder<'a>,
+        id: HirId,
+    ) -> LintDiagnosticBuilder<'a> {
+        if self.sess.opts.debugging_opts.diagnostic_baseline.is_some() {
+            let path = with_no_trimmed_paths(|| self.def_path_str(id.owner.to_def_id()));
+            lint.set_item_path(path);
+        }
+        lint
     }
 
     pub fn in_scope_traits(self, id: HirId) -> Option<&'tcx StableVec<TraitCandidate>> {
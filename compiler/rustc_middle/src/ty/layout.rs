@@ -13,6 +13,7 @@
 use rustc_index::bit_set::BitSet;
 use rustc_index::vec::{Idx, IndexVec};
 use rustc_session::{config::OptLevel, DataTypeKind, FieldInfo, SizeKind, VariantInfo};
+use rustc_session::{NicheInfo, TagInfo};
 use rustc_span::symbol::{Ident, Symbol};
 use rustc_span::DUMMY_SP;
 use rustc_target::abi::call::{
@@ -1697,7 +1698,7 @@ fn generator_layout(
     fn record_layout_for_printing(&self, layout: TyAndLayout<'tcx>) {
         // If we are running with `-Zprint-type-sizes`, maybe record layouts
         // for dumping later.
-        if self.tcx.sess.opts.debugging_opts.print_type_sizes {
+        if self.tcx.sess.opts.debugging_opts.print_type_sizes.is_some() {
             self.record_layout_for_printing_outlined(layout)
         }
     }
@@ -1711,8 +1712,14 @@ fn record_layout_for_printing_outlined(&self, layout: TyAndLayout<'tcx>) {
         }
 
         // (delay format until we actually need it)
-        let record = |kind, packed, opt_discr_size, variants| {
+        let record = |kind, packed, opt_discr_size, tag, variants| {
             let type_desc = format!("{:?}", layout.ty);
+            let niche = layout.largest_niche.as_ref().map(|niche| NicheInfo {
+                offset: niche.offset.bytes(),
+                size: niche.scalar.value.size(self).bytes(),
+                valid_range_start: *niche.scalar.valid_range.start(),
+                valid_range_end: *niche.scalar.valid_range.end(),
+            });
             self.tcx.sess.code_stats.record_type_size(
                 kind,
                 type_desc,
@@ -1720,6 +1727,8 @@ fn record_layout_for_printing_outlined(&self, layout: TyAndLayout<'tcx>) {
                 layout.size,
                 packed,
                 opt_discr_size,
+                tag,
+                niche,
                 variants,
             );
         };
@@ -1732,7 +1741,7 @@ fn record_layout_for_printing_outlined(&self, layout: TyAndLayout<'tcx>) {
 
             ty::Closure(..) => {
                 debug!("print-type-size t: `{:?}` record closure", layout.ty);
-                record(DataTypeKind::Closure, false, None, vec![]);
+                record(DataTypeKind::Closure, false, None, None, vec![]);
                 return;
             }
 
@@ -1789,16 +1798,17 @@ fn record_layout_for_printing_outlined(&self, layout: TyAndLayout<'tcx>) {
                         adt_kind.into(),
                         adt_packed,
                         None,
+                        None,
                         vec![build_variant_info(Some(variant_def.ident), &fields, layout)],
                     );
                 } else {
                     // (This case arises for *empty* enums; so give it
                     // zero variants.)
-                    record(adt_kind.into(), adt_packed, None, vec![]);
+                    record(adt_kind.into(), adt_packed, None, None, vec![]);
                 }
             }
 
-            Variants::Multiple { ref tag, ref tag_encoding, .. } => {
+            Variants::Multiple { ref tag, ref tag_encoding, tag_field, .. } => {
                 debug!(
                     "print-type-size `{:#?}` adt general variants def {}",
                     layout.ty,
@@ -1817,6 +1827,13 @@ fn record_layout_for_printing_outlined(&self, layout: TyAndLayout<'tcx>) {
                         )
                     })
                     .collect();
+                let tag_info = TagInfo {
+                    niche_encoded: !matches!(tag_encoding, TagEncoding::Direct),
+                    offset: layout.fields.offset(tag_field).bytes(),
+                    size: tag.value.size(self).bytes(),
+                    valid_range_start: *tag.valid_range.start(),
+                    valid_range_end: *tag.valid_range.end(),
+                };
                 record(
                     adt_kind.into(),
                     adt_packed,
@@ -1824,6 +1841,7 @@ fn record_layout_for_printing_outlined(&self, layout: TyAndLayout<'tcx>) {
                         TagEncoding::Direct => Some(tag.value.size(self)),
                         _ => None,
                     },
+                    Some(tag_info),
                     variant_infos,
                 );
             }
@@ -17,6 +17,7 @@
 use rustc_span::source_map::Span;
 use rustc_target::abi::{Abi, LayoutOf};
 use std::convert::TryInto;
+use std::time::Instant;
 
 pub fn note_on_undefined_behavior_error() -> &'static str {
     "The rules on what exactly is undefined behavior aren't clear, \
@@ -98,7 +99,7 @@ pub(super) fn mk_eval_cx<'mir, 'tcx>(
         root_span,
         param_env,
         CompileTimeInterpreter::new(tcx.sess.const_eval_limit()),
-        MemoryExtra { can_access_statics },
+        MemoryExtra { can_access_statics, allocations: 0 },
     )
 }
 
@@ -301,11 +302,41 @@ pub fn eval_to_allocation_raw_provider<'tcx>(
         CompileTimeInterpreter::new(tcx.sess.const_eval_limit()),
         // Statics (and promoteds inside statics) may access other statics, because unlike consts
         // they do not have to behave "as if" they were evaluated at runtime.
-        MemoryExtra { can_access_statics: is_static },
+        MemoryExtra { can_access_statics: is_static, allocations: 0 },
     );
 
+    let profile = tcx.sess.opts.debugging_opts.const_eval_profile;
+    let name = if profile || tcx.sess.prof.enabled() {
+        let prom = cid.promoted.map_or_else(String::new, |p| format!("::promoted[{:?}]", p));
+        with_no_trimmed_paths(|| format!("{}{}", cid.instance, prom))
+    } else {
+        String::new()
+    };
+    let timer = tcx.sess.prof.generic_activity_with_arg("const_eval", &name[..]);
+    let start = Instant::now();
+
     let res = ecx.load_mir(cid.instance.def, cid.promoted);
-    match res.and_then(|body| eval_body_using_ecx(&mut ecx, cid, &body)) {
+    let res = res.and_then(|body| eval_body_using_ecx(&mut ecx, cid, &body));
+
+    drop(timer);
+    if profile {
+        let kind = if cid.promoted.is_some() {
+            "promoted"
+        } else if is_static {
+            "static"
+        } else {
+            "const"
+        };
+        tcx.sess.code_stats.record_const_eval(
+            kind,
+            name,
+            ecx.machine.steps,
+            ecx.memory.extra.allocations,
+            start.elapsed(),
+        );
+    }
+
+    match res {
         Err(error) => {
             let err = ConstEvalErr::new(&ecx, error, None);
             // Some CTFE errors raise just a lint, not a hard error; see
@@ -12,12 +12,12 @@
 use rustc_middle::mir::AssertMessage;
 use rustc_session::Limit;
 use rustc_span::symbol::{sym, Symbol};
-use rustc_target::abi::{Align, Size};
+use rustc_target::abi::{Align, LayoutOf, Size};
 use rustc_target::spec::abi::Abi;
 
 use crate::interpret::{
-    self, compile_time_machine, AllocId, Allocation, Frame, ImmTy, InterpCx, InterpResult, Memory,
-    OpTy, PlaceTy, Pointer, Scalar,
+    self, compile_time_machine, AllocId, Allocation, Frame, ImmTy, Immediate, InterpCx,
+    InterpResult, Memory, OpTy, PlaceTy, Pointer, Scalar,
 };
 
 use super::error::*;
@@ -48,6 +48,35 @@ fn hook_panic_fn(
             Ok(())
         }
     }
+
+    /// "Intercept" a call to the `box_free` lang item, which frees the memory of a `Box` after
+    /// its contents were dropped. `box_alloc` allocates boxes on the const heap, so they are
+    /// freed from there.
+    /// Returns `true` if the call was handled.
+    fn hook_box_free(
+        &mut self,
+        instance: ty::Instance<'tcx>,
+        args: &[OpTy<'tcx>],
+        ret: Option<(&PlaceTy<'tcx>, mir::BasicBlock)>,
+    ) -> InterpResult<'tcx, bool> {
+        if Some(instance.def_id()) != self.tcx.lang_items().box_free_fn() {
+            return Ok(false);
+        }
+
+        // The first argument is the `Unique<T>` of the box, whose only non-zero-sized field is
+        // the (possibly wide) pointer to the contents.
+        let ptr = match *self.read_immediate(&args[0])? {
+            Immediate::Scalar(ptr) | Immediate::ScalarPair(ptr, _) => ptr.check_init()?,
+        };
+        let ptr = self.force_ptr(ptr)?;
+        self.memory.deallocate(ptr, None, interpret::MemoryKind::Machine(MemoryKind::Heap))?;
+
+        match ret {
+            Some((_, ret)) => self.go_to_block(ret),
+            None => bug!("`box_free` always returns"),
+        }
+        Ok(true)
+    }
 }
 
 /// Extra machine state for CTFE, and the Machine instance
@@ -58,6 +87,9 @@ pub struct CompileTimeInterpreter<'mir, 'tcx> {
     /// Setting this to `0` disables the limit and allows the interpreter to run forever.
     pub steps_remaining: usize,
 
+    /// The number of terminators evaluated so far, for `-Z const-eval-profile`.
+    pub(super) steps: usize,
+
     /// The virtual call stack.
     pub(crate) stack: Vec<Frame<'mir, 'tcx, (), ()>>,
 }
@@ -71,11 +103,14 @@ pub struct MemoryExtra {
     /// * Pointers to allocations inside of statics can never leak outside, to a non-static global.
     /// This boolean here controls the second part.
     pub(super) can_access_statics: bool,
+
+    /// The number of allocations created so far, for `-Z const-eval-profile`.
+    pub(super) allocations: usize,
 }
 
 impl<'mir, 'tcx> CompileTimeInterpreter<'mir, 'tcx> {
     pub(super) fn new(const_eval_limit: Limit) -> Self {
-        CompileTimeInterpreter { steps_remaining: const_eval_limit.0, stack: Vec::new() }
+        CompileTimeInterpreter { steps_remaining: const_eval_limit.0, steps: 0, stack: Vec::new() }
     }
 }
 
@@ -222,7 +257,7 @@ fn find_mir_or_eval_fn(
         instance: ty::Instance<'tcx>,
         _abi: Abi,
         args: &[OpTy<'tcx>],
-        _ret: Option<(&PlaceTy<'tcx>, mir::BasicBlock)>,
+        ret: Option<(&PlaceTy<'tcx>, mir::BasicBlock)>,
         _unwind: Option<mir::BasicBlock>, // unwinding is not supported in consts
     ) -> InterpResult<'tcx, Option<&'mir mir::Body<'tcx>>> {
         debug!("find_mir_or_eval_fn: {:?}", instance);
@@ -235,6 +270,9 @@ fn find_mir_or_eval_fn(
             if !ecx.tcx.is_const_fn_raw(def.did) {
                 // Some functions we support even if they are non-const -- but avoid testing
                 // that for const fn!
+                if ecx.hook_box_free(instance, args, ret)? {
+                    return Ok(None);
+                }
                 ecx.hook_panic_fn(instance, args)?;
                 // We certainly do *not* want to actually call the fn
                 // though, so be sure we return here.
@@ -309,6 +347,23 @@ fn call_intrinsic(
                 );
                 ecx.write_scalar(Scalar::Ptr(ptr), dest)?;
             }
+            sym::const_deallocate => {
+                let ptr = ecx.read_scalar(&args[0])?.check_init()?;
+                let size = ecx.read_scalar(&args[1])?.to_machine_usize(ecx)?;
+                let align = ecx.read_scalar(&args[2])?.to_machine_usize(ecx)?;
+
+                let align = match Align::from_bytes(align) {
+                    Ok(a) => a,
+                    Err(err) => throw_ub_format!("align has to be a power of 2, {}", err),
+                };
+
+                let ptr = ecx.force_ptr(ptr)?;
+                ecx.memory.deallocate(
+                    ptr,
+                    Some((Size::from_bytes(size), align)),
+                    interpret::MemoryKind::Machine(MemoryKind::Heap),
+                )?;
+            }
             _ => {
                 return Err(ConstEvalErrKind::NeedsRfc(format!(
                     "calling intrinsic `{}`",
@@ -365,13 +420,23 @@ fn binary_ptr_op(
     }
 
     fn box_alloc(
-        _ecx: &mut InterpCx<'mir, 'tcx, Self>,
-        _dest: &PlaceTy<'tcx>,
+        ecx: &mut InterpCx<'mir, 'tcx, Self>,
+        dest: &PlaceTy<'tcx>,
     ) -> InterpResult<'tcx> {
-        Err(ConstEvalErrKind::NeedsRfc("heap allocations via `box` keyword".to_string()).into())
+        // Boxes live on the const heap, like the memory of `const_allocate`. They are freed by
+        // `hook_box_free`.
+        let layout = ecx.layout_of(dest.layout.ty.builtin_deref(false).unwrap().ty)?;
+        let ptr = ecx.memory.allocate(
+            layout.size,
+            layout.align.abi,
+            interpret::MemoryKind::Machine(MemoryKind::Heap),
+        );
+        ecx.write_scalar(Scalar::Ptr(ptr), dest)
     }
 
     fn before_terminator(ecx: &mut InterpCx<'mir, 'tcx, Self>) -> InterpResult<'tcx> {
+        ecx.machine.steps += 1;
+
         // The step limit has already been hit in a previous call to `before_terminator`.
         if ecx.machine.steps_remaining == 0 {
             return Ok(());
@@ -385,6 +450,11 @@ fn before_terminator(ecx: &mut InterpCx<'mir, 'tcx, Self>) -> InterpResult<'tcx>
         Ok(())
     }
 
+    #[inline(always)]
+    fn after_allocation(memory_extra: &mut MemoryExtra, _id: AllocId) {
+        memory_extra.allocations += 1;
+    }
+
     #[inline(always)]
     fn init_frame_extra(
         ecx: &mut InterpCx<'mir, 'tcx, Self>,
@@ -13,6 +13,10 @@
 //! (In principle, we could skip this type-based part for `const` and promoteds, as they need to be
 //! always immutable. At least for `const` however we use this opportunity to reject any `const`
 //! that contains allocations whose mutability we cannot identify.)
+//!
+//! Memory allocated with `const_allocate` (the "const heap") becomes part of the final value like
+//! any other allocation it references, but only as immutable memory: heap memory that would
+//! remain mutable, or that is only reachable through raw pointers in a `const`, is rejected.
 
 use super::validity::RefTracking;
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
@@ -135,6 +139,12 @@ fn intern_shallow<'rt, 'mir, 'tcx, M: CompileTimeMachine<'mir, 'tcx, const_eval:
         // Validation will ensure that there is no `UnsafeCell` on an immutable allocation.
         alloc.mutability = Mutability::Not;
     };
+    let is_heap = kind == MemoryKind::Machine(const_eval::MemoryKind::Heap);
+    if is_heap && alloc.mutability == Mutability::Mut {
+        report_mutable_heap_allocation(ecx);
+        // For better errors later, mark the allocation as immutable.
+        alloc.mutability = Mutability::Not;
+    }
     // link the alloc id to the actual allocation
     let alloc = tcx.intern_const_alloc(alloc);
     leftover_allocations.extend(alloc.relocations().iter().map(|&(_, ((), reloc))| reloc));
@@ -260,6 +270,19 @@ fn visit_value(&mut self, mplace: &MPlaceTy<'tcx>) -> InterpResult<'tcx> {
                         InternMode::Const
                     }
                 };
+                // A mutable reference to heap memory promises ownership of that memory, e.g. to
+                // a `Box` made from it, even where the reference itself can only be read. The
+                // memory would be immutable, so reject this instead. Where the memory stays
+                // mutable, `intern_shallow` reports it.
+                if ref_mutability == Mutability::Mut
+                    && ref_mode == InternMode::Static(Mutability::Not)
+                    && matches!(
+                        self.ecx.memory.alloc_map.get(ptr.alloc_id),
+                        Some((MemoryKind::Machine(const_eval::MemoryKind::Heap), _))
+                    )
+                {
+                    report_mutable_heap_allocation(self.ecx);
+                }
                 match self.intern_shallow(ptr.alloc_id, ref_mode, Some(referenced_ty)) {
                     // No need to recurse, these are interned already and statics may have
                     // cycles, so we don't want to recurse there
@@ -361,7 +384,8 @@ pub fn intern_const_alloc_recursive<M: CompileTimeMachine<'mir, 'tcx, const_eval
 
     let mut todo: Vec<_> = leftover_allocations.iter().cloned().collect();
     while let Some(alloc_id) = todo.pop() {
-        if let Some((_, mut alloc)) = ecx.memory.alloc_map.remove(&alloc_id) {
+        if let Some((kind, mut alloc)) = ecx.memory.alloc_map.remove(&alloc_id) {
+            let is_heap = kind == MemoryKind::Machine(const_eval::MemoryKind::Heap);
             // We can't call the `intern_shallow` method here, as its logic is tailored to safe
             // references and a `leftover_allocations` set (where we only have a todo-list here).
             // So we hand-roll the interning logic here again.
@@ -369,7 +393,13 @@ pub fn intern_const_alloc_recursive<M: CompileTimeMachine<'mir, 'tcx, const_eval
                 // Statics may contain mutable allocations even behind relocations.
                 // Even for immutable statics it would be ok to have mutable allocations behind
                 // raw pointers, e.g. for `static FOO: *const AtomicUsize = &AtomicUsize::new(42)`.
-                InternKind::Static(_) => {}
+                // Heap memory is not, see `report_mutable_heap_allocation`.
+                InternKind::Static(_) => {
+                    if is_heap {
+                        report_mutable_heap_allocation(ecx);
+                        alloc.mutability = Mutability::Not;
+                    }
+                }
                 // Raw pointers in promoteds may only point to immutable things so we mark
                 // everything as immutable.
                 // It is UB to mutate through a raw pointer obtained via an immutable reference:
@@ -388,9 +418,15 @@ pub fn intern_const_alloc_recursive<M: CompileTimeMachine<'mir, 'tcx, const_eval
                     // is tracked by const-checking.
                     // FIXME: downgrade this to a warning? It rejects some legitimate consts,
                     // such as `const CONST_RAW: *const Vec<i32> = &Vec::new() as *const _;`.
-                    ecx.tcx
-                        .sess
-                        .span_err(ecx.tcx.span, "untyped pointers are not allowed in constant");
+                    let msg = "untyped pointers are not allowed in constant";
+                    let mut err = ecx.tcx.sess.struct_span_err(ecx.tcx.span, msg);
+                    if is_heap {
+                        err.note(
+                            "memory allocated during const evaluation can only be part of the \
+                             final value of a constant through references",
+                        );
+                    }
+                    err.emit();
                     // For better errors later, mark the allocation as immutable.
                     alloc.mutability = Mutability::Not;
                 }
@@ -416,6 +452,23 @@ pub fn intern_const_alloc_recursive<M: CompileTimeMachine<'mir, 'tcx, const_eval
     Ok(())
 }
 
+/// Reports heap memory that would remain mutable in the final value of a static. Mutating it at
+/// runtime would be fine, but freeing it would not: the memory is not on the runtime heap, and
+/// nothing stops a `Vec` or `Box` owning it from reallocating or deallocating it.
+fn report_mutable_heap_allocation<'mir, 'tcx, M>(ecx: &InterpCx<'mir, 'tcx, M>)
+where
+    M: CompileTimeMachine<'mir, 'tcx, const_eval::MemoryKind>,
+{
+    ecx.tcx
+        .sess
+        .struct_span_err(ecx.tcx.span, "mutable heap allocation in the final value of a static")
+        .note(
+            "memory allocated during const evaluation can only be part of the final value \
+             through shared references, and must not contain an `UnsafeCell`",
+        )
+        .emit();
+}
+
 impl<'mir, 'tcx: 'mir, M: super::intern::CompileTimeMachine<'mir, 'tcx, !>>
     InterpCx<'mir, 'tcx, M>
 {
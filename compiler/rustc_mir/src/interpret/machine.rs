@@ -305,6 +305,10 @@ fn init_allocation_extra<'b>(
         kind: Option<MemoryKind<Self::MemoryKind>>,
     ) -> (Cow<'b, Allocation<Self::PointerTag, Self::AllocExtra>>, Self::PointerTag);
 
+    /// Called to notify the machine after a new allocation was created.
+    #[inline(always)]
+    fn after_allocation(_memory_extra: &mut Self::MemoryExtra, _id: AllocId) {}
+
     /// Called to notify the machine before a deallocation occurs.
     fn before_deallocation(
         _memory_extra: &mut Self::MemoryExtra,
@@ -221,6 +221,7 @@ pub fn allocate_with(
         // This is a new allocation, not a new global one, so no `global_base_ptr`.
         let (alloc, tag) = M::init_allocation_extra(&self.extra, id, Cow::Owned(alloc), Some(kind));
         self.alloc_map.insert(id, (kind, alloc.into_owned()));
+        M::after_allocation(&mut self.extra, id);
         Pointer::from(id).with_tag(tag)
     }
 
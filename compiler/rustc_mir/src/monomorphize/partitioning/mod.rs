@@ -97,12 +97,13 @@
 
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::sync;
-use rustc_hir::def_id::{CrateNum, DefIdSet, LOCAL_CRATE};
+use rustc_hir::def_id::{CrateNum, DefId, DefIdSet, LOCAL_CRATE};
 use rustc_middle::mir::mono::MonoItem;
 use rustc_middle::mir::mono::{CodegenUnit, Linkage};
 use rustc_middle::ty::print::with_no_trimmed_paths;
 use rustc_middle::ty::query::Providers;
 use rustc_middle::ty::TyCtxt;
+use rustc_session::config::PrintRequest;
 use rustc_span::symbol::Symbol;
 
 use crate::monomorphize::collector::InliningMap;
@@ -421,9 +422,112 @@ fn collect_and_partition_mono_items<'tcx>(
         }
     }
 
+    if tcx.sess.opts.prints.contains(&PrintRequest::MonoStats) {
+        print_mono_stats(tcx, &items, &inlining_map, codegen_units);
+    }
+
     (tcx.arena.alloc(mono_items), codegen_units)
 }
 
+/// The instantiations of a generic item, for `--print mono-stats`.
+#[derive(Default)]
+struct GenericItemStats {
+    instantiations: usize,
+    /// The number of codegen units the instantiations are placed in. Instantiations that are
+    /// copied into every codegen unit using them are counted once per codegen unit.
+    copies: usize,
+    /// The sum of the size estimates of the instantiations, as used by partitioning.
+    size_estimate: usize,
+    /// The crates defining the items that use the instantiations.
+    requested_by: FxHashSet<CrateNum>,
+}
+
+/// Prints how many instantiations of each generic item were collected, their estimated size,
+/// and the crates whose code requested them, the largest first. This implements
+/// `--print mono-stats`.
+fn print_mono_stats<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    items: &FxHashSet<MonoItem<'tcx>>,
+    inlining_map: &InliningMap<'tcx>,
+    codegen_units: &[CodegenUnit<'tcx>],
+) {
+    let mut copies: FxHashMap<MonoItem<'tcx>, usize> = Default::default();
+    for cgu in codegen_units {
+        for &mono_item in cgu.items().keys() {
+            *copies.entry(mono_item).or_default() += 1;
+        }
+    }
+
+    let mut stats: FxHashMap<DefId, GenericItemStats> = Default::default();
+    for mono_item in items {
+        if let MonoItem::Fn(instance) = mono_item {
+            if mono_item.is_generic_fn() {
+                let entry = stats.entry(instance.def_id()).or_default();
+                entry.instantiations += 1;
+                entry.copies += copies.get(mono_item).copied().unwrap_or(0);
+                entry.size_estimate += mono_item.size_estimate(tcx);
+            }
+        }
+    }
+
+    inlining_map.iter_accesses(|accessor, accessees| {
+        let accessor_crate = match accessor {
+            MonoItem::Fn(instance) => instance.def_id().krate,
+            MonoItem::Static(def_id) => def_id.krate,
+            MonoItem::GlobalAsm(_) => LOCAL_CRATE,
+        };
+        for accessee in accessees {
+            if let MonoItem::Fn(instance) = accessee {
+                if accessee.is_generic_fn() {
+                    if let Some(entry) = stats.get_mut(&instance.def_id()) {
+                        entry.requested_by.insert(accessor_crate);
+                    }
+                }
+            }
+        }
+    });
+
+    let mut stats: Vec<_> = stats
+        .into_iter()
+        .map(|(def_id, stats)| (with_no_trimmed_paths(|| tcx.def_path_str(def_id)), stats))
+        .collect();
+    // Primary sort: largest total size estimate first.
+    // Secondary sort: most instantiations first.
+    // Tertiary sort: path (dictionary order)
+    stats.sort_by(|(path1, stats1), (path2, stats2)| {
+        stats2
+            .size_estimate
+            .cmp(&stats1.size_estimate)
+            .then(stats2.instantiations.cmp(&stats1.instantiations))
+            .then_with(|| path1.cmp(path2))
+    });
+
+    let instantiations: usize = stats.iter().map(|(_, stats)| stats.instantiations).sum();
+    println!(
+        "mono-stats: {} instantiations of {} generic items, out of {} mono items",
+        instantiations,
+        stats.len(),
+        items.len()
+    );
+    println!(
+        "mono-stats {:>14} {:>8} {:>13}  item (requested by)",
+        "instantiations", "copies", "size estimate"
+    );
+    for (path, stats) in stats {
+        let mut requested_by: Vec<_> =
+            stats.requested_by.iter().map(|&cnum| tcx.crate_name(cnum).to_string()).collect();
+        requested_by.sort();
+        println!(
+            "mono-stats {:>14} {:>8} {:>13}  {} ({})",
+            stats.instantiations,
+            stats.copies,
+            stats.size_estimate,
+            path,
+            requested_by.join(", ")
+        );
+    }
+}
+
 fn codegened_and_inlined_items<'tcx>(tcx: TyCtxt<'tcx>, cnum: CrateNum) -> &'tcx DefIdSet {
     let (items, cgus) = tcx.collect_and_partition_mono_items(cnum);
     let mut visited = DefIdSet::default();
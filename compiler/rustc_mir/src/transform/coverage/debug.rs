@@ -308,6 +308,7 @@ pub fn format_counter(&self, counter_kind: &CoverageKind) -> String {
                 format!("Expression({})", self.format_counter_kind(counter_kind))
             }
             CoverageKind::Unreachable { .. } => "Unreachable".to_owned(),
+            CoverageKind::Branch { .. } => format!("{:?}", counter_kind),
         }
     }
 
@@ -9,7 +9,7 @@
 mod tests;
 
 use counters::CoverageCounters;
-use graph::{BasicCoverageBlock, BasicCoverageBlockData, CoverageGraph};
+use graph::{BasicCoverageBlock, BasicCoverageBlockData, BcbBranch, CoverageGraph};
 use spans::{CoverageSpan, CoverageSpans};
 
 use crate::transform::MirPass;
@@ -25,8 +25,8 @@
 use rustc_middle::ich::StableHashingContext;
 use rustc_middle::mir::coverage::*;
 use rustc_middle::mir::{
-    self, BasicBlock, BasicBlockData, Coverage, SourceInfo, Statement, StatementKind, Terminator,
-    TerminatorKind,
+    self, BasicBlock, BasicBlockData, Coverage, Operand, SourceInfo, Statement, StatementKind,
+    Terminator, TerminatorKind,
 };
 use rustc_middle::ty::TyCtxt;
 use rustc_span::def_id::DefId;
@@ -192,8 +192,17 @@ fn inject_counters(&'a mut self) {
             .coverage_counters
             .make_bcb_counters(&mut self.basic_coverage_blocks, &coverage_spans);
 
+        let mut branch_regions = Vec::new();
         let (result, intermediate_expressions) = match intermediate_expressions_or_error {
-            Ok(intermediate_expressions) => {
+            Ok(mut intermediate_expressions) => {
+                ////////////////////////////////////////////////////
+                // If branch coverage was requested, pair up the counters of the outgoing branches
+                // of each `SwitchInt`, as the counts of its condition. This must happen before the
+                // counters are removed from the `CoverageGraph` (by the injection steps below).
+                if tcx.sess.instrument_coverage_branches() {
+                    branch_regions = self.make_branch_regions(&mut intermediate_expressions);
+                }
+
                 // If debugging, add any intermediate expressions (which are not associated with any
                 // BCB) to the `debug_used_expressions` map.
                 if debug_used_expressions.is_enabled() {
@@ -264,6 +273,118 @@ fn inject_counters(&'a mut self) {
         for intermediate_expression in intermediate_expressions {
             inject_intermediate_expression(self.mir_body, intermediate_expression);
         }
+
+        ////////////////////////////////////////////////////
+        // And the branch regions, whose operands are now all injected.
+        for (branch, code_region) in branch_regions {
+            inject_branch_region(self.mir_body, branch, code_region);
+        }
+    }
+
+    /// For each `BasicCoverageBlock` that ends in a `SwitchInt`, make a `CoverageKind::Branch`
+    /// for the condition being switched on. A `bool` condition (from `if`, `while`, `&&` and
+    /// `||`) gets a single branch region, counting the edges to its `true` and `false` targets. A
+    /// multi-way switch (from `match`) gets one branch region per distinct target, counting the
+    /// edge to that target as `true`, and every other way out of the switch as `false`.
+    ///
+    /// The edge counts are the counters that `make_bcb_counters()` already assigned to each
+    /// branch (either the target BCB's counter or an edge counter), so branch coverage adds no
+    /// runtime counters. Any `Expression`s needed to compute a `false` count are added to
+    /// `intermediate_expressions`. Branches that were never given a counter (because the branching
+    /// BCB has no `CoverageSpan`) are skipped.
+    fn make_branch_regions(
+        &mut self,
+        intermediate_expressions: &mut Vec<CoverageKind>,
+    ) -> Vec<(CoverageKind, CodeRegion)> {
+        let source_map = self.tcx.sess.source_map();
+        let body_span = self.body_span;
+        let file_name = Symbol::intern(&self.source_file.name.to_string());
+        let mir_body = &*self.mir_body;
+        let basic_coverage_blocks = &self.basic_coverage_blocks;
+        let coverage_counters = &mut self.coverage_counters;
+
+        let mut branch_regions = Vec::new();
+        for (bcb, bcb_data) in basic_coverage_blocks.iter_enumerated() {
+            let terminator = mir_body[bcb_data.last_bb()].terminator();
+            let (discr, switch_ty, targets) = match &terminator.kind {
+                TerminatorKind::SwitchInt { discr, switch_ty, targets } => {
+                    (discr, switch_ty, targets)
+                }
+                _ => continue,
+            };
+
+            // The condition's span is the span of the temporary it was evaluated into, if any.
+            // Otherwise (for instance, when a `match` switches directly on a user variable), use
+            // the span of the test, which the `SwitchInt` terminator was given.
+            let condition_span = match discr {
+                Operand::Copy(place) | Operand::Move(place) => place
+                    .as_local()
+                    .map(|local| &mir_body.local_decls[local])
+                    .filter(|local_decl| !local_decl.is_user_variable())
+                    .map_or(terminator.source_info.span, |local_decl| local_decl.source_info.span),
+                Operand::Constant(_) => continue,
+            };
+            let condition_span = spans::function_source_span(condition_span, body_span);
+            if condition_span == body_span {
+                continue;
+            }
+            let code_region = make_code_region(
+                source_map,
+                file_name,
+                &self.source_file,
+                condition_span,
+                body_span,
+            );
+
+            let branch_operand = |target_bb: BasicBlock| {
+                let target_bcb = basic_coverage_blocks.bcb_from_bb(target_bb)?;
+                BcbBranch::from_to(bcb, target_bcb, basic_coverage_blocks)
+                    .counter(basic_coverage_blocks)
+                    .map(|counter_kind| counter_kind.as_operand_id())
+            };
+
+            if switch_ty.is_bool() && targets.all_targets().len() == 2 {
+                let (value, value_bb) = targets.iter().next().unwrap();
+                let (true_bb, false_bb) = if value == 0 {
+                    (targets.otherwise(), value_bb)
+                } else {
+                    (value_bb, targets.otherwise())
+                };
+                if let (Some(true_operand), Some(false_operand)) =
+                    (branch_operand(true_bb), branch_operand(false_bb))
+                {
+                    let branch = CoverageKind::Branch { true_operand, false_operand };
+                    branch_regions.push((branch, code_region));
+                }
+                continue;
+            }
+
+            let branching_counter_operand = match bcb_data.counter() {
+                Some(counter_kind) => counter_kind.as_operand_id(),
+                None => continue,
+            };
+            let mut seen_target_bcbs = Vec::new();
+            for &target_bb in targets.all_targets() {
+                let target_bcb = match basic_coverage_blocks.bcb_from_bb(target_bb) {
+                    Some(target_bcb) if !seen_target_bcbs.contains(&target_bcb) => target_bcb,
+                    _ => continue,
+                };
+                seen_target_bcbs.push(target_bcb);
+                if let Some(true_operand) = branch_operand(target_bb) {
+                    let not_taken = coverage_counters.make_expression(
+                        branching_counter_operand,
+                        Op::Subtract,
+                        true_operand,
+                        || None,
+                    );
+                    let false_operand = not_taken.as_operand_id();
+                    intermediate_expressions.push(not_taken);
+                    let branch = CoverageKind::Branch { true_operand, false_operand };
+                    branch_regions.push((branch, code_region.clone()));
+                }
+            }
+        }
+        branch_regions
     }
 
     /// Inject a counter for each `CoverageSpan`. There can be multiple `CoverageSpan`s for a given
@@ -488,6 +609,27 @@ fn inject_intermediate_expression(mir_body: &mut mir::Body<'tcx>, expression: Co
     data.statements.push(statement);
 }
 
+// Branch regions are injected into the coverage map, without generating executable code.
+fn inject_branch_region(
+    mir_body: &mut mir::Body<'tcx>,
+    branch: CoverageKind,
+    code_region: CodeRegion,
+) {
+    debug_assert!(branch.is_branch());
+    debug!("  injecting branch region {:?} at code region: {:?}", branch, code_region);
+    let inject_in_bb = mir::START_BLOCK;
+    let data = &mut mir_body[inject_in_bb];
+    let source_info = data.terminator().source_info;
+    let statement = Statement {
+        source_info,
+        kind: StatementKind::Coverage(box Coverage {
+            kind: branch,
+            code_region: Some(code_region),
+        }),
+    };
+    data.statements.push(statement);
+}
+
 /// Convert the Span into its file name, start line and column, and end line and column
 fn make_code_region(
     source_map: &SourceMap,
@@ -1,6 +1,7 @@
 //! Lowers intrinsic calls
 
 use crate::transform::MirPass;
+use rustc_hir as hir;
 use rustc_middle::mir::*;
 use rustc_middle::ty::subst::SubstsRef;
 use rustc_middle::ty::{self, Ty, TyCtxt};
@@ -126,6 +127,67 @@ fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
     }
 }
 
+/// Replaces the calls to the `const_eval_select` intrinsic in `body` with calls to the function
+/// it selects: `called_in_const` in the MIR used for CTFE (`context` is `Const`), and
+/// `called_at_rt` in the MIR used for codegen. This cannot be part of `LowerIntrinsics`, which
+/// runs before the two kinds of MIR are split. The arguments of the intrinsic come as a tuple,
+/// which is taken apart into the arguments of the selected function. Type checking ensures
+/// that both functions are function items taking the elements of that tuple.
+pub fn lower_const_eval_select<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    body: &mut Body<'tcx>,
+    context: hir::Constness,
+) {
+    let (basic_blocks, local_decls) = body.basic_blocks_and_local_decls_mut();
+    for block in basic_blocks {
+        let terminator = block.terminator.as_mut().unwrap();
+        if let TerminatorKind::Call { func, args, fn_span, .. } = &mut terminator.kind {
+            let func_ty = func.ty(&*local_decls, tcx);
+            match resolve_rust_intrinsic(tcx, func_ty) {
+                Some((sym::const_eval_select, _)) => {}
+                _ => continue,
+            }
+
+            let (tupled_args, called_in_const, called_at_rt) = {
+                let mut args = args.drain(..);
+                let it = (args.next().unwrap(), args.next().unwrap(), args.next().unwrap());
+                assert_eq!(args.next(), None, "Extra argument for const_eval_select intrinsic");
+                it
+            };
+            let tuple_ty = tupled_args.ty(&*local_decls, tcx);
+            let (place, copy) = match tupled_args {
+                Operand::Copy(place) => (place, true),
+                Operand::Move(place) => (place, false),
+                // A constant has no place to project the fields from, so move it into one.
+                Operand::Constant(_) => {
+                    let local = local_decls.push(LocalDecl::new(tuple_ty, *fn_span));
+                    block.statements.push(Statement {
+                        source_info: terminator.source_info,
+                        kind: StatementKind::Assign(box (local.into(), Rvalue::Use(tupled_args))),
+                    });
+                    (local.into(), false)
+                }
+            };
+            *args = tuple_ty
+                .tuple_fields()
+                .enumerate()
+                .map(|(i, ty)| {
+                    let field = tcx.mk_place_field(place, Field::new(i), ty);
+                    if copy { Operand::Copy(field) } else { Operand::Move(field) }
+                })
+                .collect();
+            *func = match context {
+                hir::Constness::Const => called_in_const,
+                hir::Constness::NotConst => called_at_rt,
+            };
+            let selected_ty = func.ty(&*local_decls, tcx);
+            if !matches!(selected_ty.kind(), ty::FnDef(..)) {
+                bug!("const_eval_select selected {:?}, which is not a function item", selected_ty);
+            }
+        }
+    }
+}
+
 fn resolve_rust_intrinsic(
     tcx: TyCtxt<'tcx>,
     func_ty: Ty<'tcx>,
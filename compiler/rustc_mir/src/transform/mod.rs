@@ -365,6 +365,7 @@ fn inner_mir_for_ctfe(tcx: TyCtxt<'_>, def: ty::WithOptConstParam<LocalDefId>) -
         .expect("mir_for_ctfe should not be used for runtime functions");
 
     let mut body = tcx.mir_drops_elaborated_and_const_checked(def).borrow().clone();
+    lower_intrinsics::lower_const_eval_select(tcx, &mut body, hir::Constness::Const);
 
     match context {
         // Do not const prop functions, either they get executed at runtime or exported to metadata,
@@ -585,6 +586,7 @@ fn inner_optimized_mir(tcx: TyCtxt<'_>, did: LocalDefId) -> Body<'_> {
     }
     let mut body =
         tcx.mir_drops_elaborated_and_const_checked(ty::WithOptConstParam::unknown(did)).steal();
+    lower_intrinsics::lower_const_eval_select(tcx, &mut body, hir::Constness::NotConst);
     run_optimization_passes(tcx, &mut body);
 
     debug_assert!(!body.has_free_regions(), "Free regions in optimized MIR");
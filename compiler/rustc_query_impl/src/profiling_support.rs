@@ -219,7 +219,7 @@ fn spec_to_self_profile_string(
             StringComponent::Value(")"),
         ];
 
-        builder.profiler.alloc_string(components)
+        builder.profiler.alloc_string(&components[..])
     }
 }
 
@@ -85,14 +85,13 @@ pub fn construct<Ctxt, Key>(tcx: Ctxt, kind: K, arg: &Key) -> DepNode<K>
         let hash = arg.to_fingerprint(tcx);
         let dep_node = DepNode { kind, hash: hash.into() };
 
-        #[cfg(debug_assertions)]
+        if !kind.can_reconstruct_query_key()
+            && (tcx.dep_graph().is_recording_query_timings()
+                || cfg!(debug_assertions)
+                    && (tcx.sess().opts.debugging_opts.incremental_info
+                        || tcx.sess().opts.debugging_opts.query_dep_graph))
         {
-            if !kind.can_reconstruct_query_key()
-                && (tcx.sess().opts.debugging_opts.incremental_info
-                    || tcx.sess().opts.debugging_opts.query_dep_graph)
-            {
-                tcx.dep_graph().register_dep_node_debug_str(dep_node, || arg.to_debug_str(tcx));
-            }
+            tcx.dep_graph().register_dep_node_debug_str(dep_node, || arg.to_debug_str(tcx));
         }
 
         dep_node
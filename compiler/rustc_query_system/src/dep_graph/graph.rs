@@ -1,3 +1,4 @@
+use rustc_data_structures::cold_path;
 use rustc_data_structures::fingerprint::Fingerprint;
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_data_structures::profiling::QueryInvocationId;
@@ -18,10 +19,12 @@
 use std::marker::PhantomData;
 use std::mem;
 use std::sync::atomic::Ordering::Relaxed;
+use std::time::Instant;
 
 use super::prev::PreviousDepGraph;
 use super::query::DepGraphQuery;
 use super::serialized::{GraphEncoder, SerializedDepNodeIndex};
+use super::timings::{QueryCycleFrame, QueryResultSource, QueryTimings};
 use super::{DepContext, DepKind, DepNode, HasDepContext, WorkProductId};
 use crate::query::QueryContext;
 
@@ -37,6 +40,10 @@ pub struct DepGraph<K: DepKind> {
     /// each task has a `DepNodeIndex` that uniquely identifies it. This unique
     /// ID is used for self-profiling.
     virtual_dep_node_index: Lrc<AtomicU32>,
+
+    /// Whether `DepGraphData::query_timings` is set. It is copied here so that
+    /// the hot query paths test a plain `bool` when the timings are disabled.
+    record_query_timings: bool,
 }
 
 rustc_index::newtype_index! {
@@ -95,6 +102,9 @@ struct DepGraphData<K: DepKind> {
     previous_work_products: FxHashMap<WorkProductId, WorkProduct>,
 
     dep_node_debug: Lock<FxHashMap<DepNode<K>, String>>,
+
+    /// The timings of the queries executed in this session, for `-Z dump-query-graph`.
+    query_timings: Option<QueryTimings>,
 }
 
 pub fn hash_result<HashCtxt, R>(hcx: &mut HashCtxt, result: &R) -> Option<Fingerprint>
@@ -114,6 +124,7 @@ pub fn new(
         encoder: FileEncoder,
         record_graph: bool,
         record_stats: bool,
+        record_query_timings: bool,
     ) -> DepGraph<K> {
         let prev_graph_node_count = prev_graph.node_count();
 
@@ -131,13 +142,19 @@ pub fn new(
                 emitting_diagnostics_cond_var: Condvar::new(),
                 previous: prev_graph,
                 colors: DepNodeColorMap::new(prev_graph_node_count),
+                query_timings: record_query_timings.then(QueryTimings::default),
             })),
             virtual_dep_node_index: Lrc::new(AtomicU32::new(0)),
+            record_query_timings,
         }
     }
 
     pub fn new_disabled() -> DepGraph<K> {
-        DepGraph { data: None, virtual_dep_node_index: Lrc::new(AtomicU32::new(0)) }
+        DepGraph {
+            data: None,
+            virtual_dep_node_index: Lrc::new(AtomicU32::new(0)),
+            record_query_timings: false,
+        }
     }
 
     /// Returns `true` if we are actually building the full dep-graph, and `false` otherwise.
@@ -146,12 +163,60 @@ pub fn is_fully_enabled(&self) -> bool {
         self.data.is_some()
     }
 
-    pub fn with_query(&self, f: impl Fn(&DepGraphQuery<K>)) {
+    pub fn with_query(&self, f: impl FnOnce(&DepGraphQuery<K>)) {
         if let Some(data) = &self.data {
             data.current.encoder.borrow().with_query(f)
         }
     }
 
+    /// Returns `true` if the timings of queries are recorded, for `-Z dump-query-graph`.
+    #[inline(always)]
+    pub fn is_recording_query_timings(&self) -> bool {
+        self.record_query_timings
+    }
+
+    /// Starts timing a query, if query timings are recorded.
+    #[inline]
+    pub fn start_query_timer(&self) -> Option<Instant> {
+        if unlikely!(self.is_recording_query_timings()) { Some(Instant::now()) } else { None }
+    }
+
+    /// Records how the result of the query of `dep_node_index` was produced, and the time it
+    /// took since `start_query_timer`.
+    #[inline]
+    pub fn record_query_result(
+        &self,
+        dep_node_index: DepNodeIndex,
+        source: QueryResultSource,
+        timer: Option<Instant>,
+    ) {
+        if let (Some(timings), Some(start)) = (self.query_timings(), timer) {
+            timings.record_result(dep_node_index, source, start.elapsed());
+        }
+    }
+
+    #[inline(always)]
+    pub fn record_query_cache_hit(&self, dep_node_index: DepNodeIndex) {
+        if unlikely!(self.record_query_timings) {
+            cold_path(|| {
+                if let Some(timings) = self.query_timings() {
+                    timings.record_cache_hit(dep_node_index);
+                }
+            })
+        }
+    }
+
+    pub fn record_query_cycle(&self, cycle: impl FnOnce() -> Vec<QueryCycleFrame>) {
+        if let Some(timings) = self.query_timings() {
+            timings.record_cycle(cycle());
+        }
+    }
+
+    #[inline]
+    pub fn query_timings(&self) -> Option<&QueryTimings> {
+        self.data.as_ref()?.query_timings.as_ref()
+    }
+
     pub fn assert_ignored(&self) {
         if let Some(..) = self.data {
             K::read_deps(|task_deps| {
@@ -738,6 +803,52 @@ pub fn is_green(&self, dep_node: &DepNode<K>) -> bool {
         self.node_color(dep_node).map_or(false, |c| c.is_green())
     }
 
+    /// Explains why `dep_node`, which existed in the previous session, could not be marked
+    /// green: returns the path of red nodes from a dependency of `dep_node` to the first input
+    /// found to have changed, which is the last node of the path.
+    ///
+    /// If no dependency of `dep_node` is red, the path only holds the first dependency that is
+    /// not green either, because it could not be forced (e.g. the item it refers to no longer
+    /// exists). The path is empty if `dep_node` did not exist in the previous session.
+    pub fn red_dependency_path(&self, dep_node: &DepNode<K>) -> Vec<DepNode<K>> {
+        let mut path = Vec::new();
+        let data = match self.data {
+            Some(ref data) => data,
+            None => return path,
+        };
+        let mut prev_index = match data.previous.node_to_index_opt(dep_node) {
+            Some(prev_index) => prev_index,
+            None => return path,
+        };
+
+        loop {
+            let deps = data.previous.edge_targets_from(prev_index);
+            // Eval-always nodes, like the HIR of the whole crate, turn red whenever anything
+            // changes, so they are only reported if nothing more specific is red.
+            let red_dep = deps.iter().copied().find(|&dep_index| {
+                data.colors.get(dep_index) == Some(DepNodeColor::Red)
+                    && !data.previous.index_to_node(dep_index).kind.is_eval_always()
+            });
+            match red_dep {
+                Some(dep_index) => {
+                    path.push(data.previous.index_to_node(dep_index));
+                    prev_index = dep_index;
+                }
+                None => {
+                    if path.is_empty() {
+                        let not_green = deps.iter().copied().find(|&dep_index| {
+                            !data.colors.get(dep_index).map_or(false, |color| color.is_green())
+                        });
+                        path.extend(
+                            not_green.map(|dep_index| data.previous.index_to_node(dep_index)),
+                        );
+                    }
+                    return path;
+                }
+            }
+        }
+    }
+
     // This method loads all on-disk cacheable query results into memory, so
     // they can be written out to the new cache file again. Most query results
     // will already be in memory but in the case where we marked something as
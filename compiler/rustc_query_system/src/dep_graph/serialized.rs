@@ -227,7 +227,7 @@ pub fn new(
         GraphEncoder { status, record_graph }
     }
 
-    pub(crate) fn with_query(&self, f: impl Fn(&DepGraphQuery<K>)) {
+    pub(crate) fn with_query(&self, f: impl FnOnce(&DepGraphQuery<K>)) {
         if let Some(record_graph) = &self.record_graph {
             f(&record_graph.lock())
         }
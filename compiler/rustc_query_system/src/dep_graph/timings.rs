@@ -0,0 +1,70 @@
+//! Per-node timings of the queries executed in the current session, recorded for
+//! `-Z dump-query-graph`.
+
+use super::DepNodeIndex;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::sync::Lock;
+use std::time::Duration;
+
+/// How the result of a query was produced in the current session.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueryResultSource {
+    /// The query provider was executed, because the dep-node was new or could not be marked
+    /// green.
+    Executed,
+    /// The dep-node was marked green, and the result was loaded from the on-disk cache.
+    Loaded,
+    /// The dep-node was marked green, but the result is not cached on disk, so the query
+    /// provider was executed again.
+    Recomputed,
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct QueryNodeTiming {
+    /// `None` if the dep-node was marked green, but its result was never needed.
+    pub source: Option<QueryResultSource>,
+    /// The time spent computing or loading the result, including the time spent in the
+    /// queries it invoked.
+    pub duration: Duration,
+    /// The number of times the result was found in the in-memory cache.
+    pub cache_hits: u32,
+}
+
+/// A query of a dependency cycle, as its name and the description of its key.
+pub type QueryCycleFrame = (&'static str, String);
+
+#[derive(Default)]
+pub struct QueryTimings {
+    nodes: Lock<FxHashMap<DepNodeIndex, QueryNodeTiming>>,
+    cycles: Lock<Vec<Vec<QueryCycleFrame>>>,
+}
+
+impl QueryTimings {
+    pub(super) fn record_result(
+        &self,
+        index: DepNodeIndex,
+        source: QueryResultSource,
+        duration: Duration,
+    ) {
+        let mut nodes = self.nodes.lock();
+        let timing = nodes.entry(index).or_default();
+        timing.source = Some(source);
+        timing.duration += duration;
+    }
+
+    pub(super) fn record_cache_hit(&self, index: DepNodeIndex) {
+        self.nodes.lock().entry(index).or_default().cache_hits += 1;
+    }
+
+    pub(super) fn record_cycle(&self, cycle: Vec<QueryCycleFrame>) {
+        self.cycles.lock().push(cycle);
+    }
+
+    pub fn node(&self, index: DepNodeIndex) -> QueryNodeTiming {
+        self.nodes.lock().get(&index).copied().unwrap_or_default()
+    }
+
+    pub fn cycles(&self) -> Vec<Vec<QueryCycleFrame>> {
+        self.cycles.lock().clone()
+    }
+}
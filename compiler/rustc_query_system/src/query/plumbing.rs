@@ -3,7 +3,7 @@
 //! manage the caches, and so forth.
 
 use crate::dep_graph::{DepContext, DepKind, DepNode};
-use crate::dep_graph::{DepNodeIndex, SerializedDepNodeIndex};
+use crate::dep_graph::{DepNodeIndex, QueryResultSource, SerializedDepNodeIndex};
 use crate::query::caches::QueryCache;
 use crate::query::config::{QueryDescription, QueryVtable, QueryVtableExt};
 use crate::query::job::{
@@ -247,6 +247,7 @@ fn try_start<'b, CTX>(
                 &tcx.current_query_job(),
                 span,
             );
+            record_cycle(tcx, &error);
             let error = report_cycle(tcx.dep_context().sess(), error);
             let value = query.handle_cycle_error(tcx, error);
             cache.cache.store_nocache(value)
@@ -259,6 +260,7 @@ fn try_start<'b, CTX>(
             let result = latch.wait_on(tcx.current_query_job(), span);
 
             if let Err(cycle) = result {
+                record_cycle(tcx, &cycle);
                 let cycle = report_cycle(tcx.dep_context().sess(), cycle);
                 let value = query.handle_cycle_error(tcx, cycle);
                 let value = cache.cache.store_nocache(value);
@@ -275,6 +277,7 @@ fn try_start<'b, CTX>(
                     {
                         cache.cache_hits.fetch_add(1, Ordering::Relaxed);
                     }
+                    tcx.dep_context().dep_graph().record_query_cache_hit(index);
                     (value.clone(), index)
                 })
                 .unwrap_or_else(|_| panic!("value must be in cache after waiting"));
@@ -329,6 +332,14 @@ fn with_diagnostics<F, R>(f: F) -> (R, ThinVec<Diagnostic>)
     (result, diagnostics.into_inner())
 }
 
+/// Records a dependency cycle for `-Z dump-query-graph`.
+#[cold]
+fn record_cycle<CTX: QueryContext>(tcx: CTX, error: &CycleError) {
+    tcx.dep_context().dep_graph().record_query_cycle(|| {
+        error.cycle.iter().map(|info| (info.query.name, info.query.description.clone())).collect()
+    });
+}
+
 impl<'tcx, D, C> Drop for JobOwner<'tcx, D, C>
 where
     D: Copy + Clone + Eq + Hash,
@@ -406,6 +417,7 @@ pub fn try_get_cached<'a, CTX, C, R, OnHit>(
         {
             cache.cache_hits.fetch_add(1, Ordering::Relaxed);
         }
+        tcx.dep_graph().record_query_cache_hit(index);
         tcx.dep_graph().read_index(index);
         on_hit(value)
     })
@@ -446,6 +458,7 @@ fn try_execute_query<CTX, C>(
 
     if query.anon {
         let prof_timer = tcx.dep_context().profiler().query_provider();
+        let timer = tcx.dep_context().dep_graph().start_query_timer();
 
         let ((result, dep_node_index), diagnostics) = with_diagnostics(|diagnostics| {
             tcx.start_query(job.id, diagnostics, || {
@@ -458,6 +471,11 @@ fn try_execute_query<CTX, C>(
         });
 
         prof_timer.finish_with_query_invocation_id(dep_node_index.into());
+        tcx.dep_context().dep_graph().record_query_result(
+            dep_node_index,
+            QueryResultSource::Executed,
+            timer,
+        );
 
         tcx.dep_context().dep_graph().read_index(dep_node_index);
 
@@ -516,6 +534,8 @@ fn load_from_disk_and_cache_in_memory<CTX, K, V: Debug>(
 
     debug_assert!(tcx.dep_context().dep_graph().is_green(dep_node));
 
+    let timer = tcx.dep_context().dep_graph().start_query_timer();
+
     // First we try to load the result from the on-disk cache.
     let result = if query.cache_on_disk(tcx, &key, None) {
         let prof_timer = tcx.dep_context().profiler().incr_cache_loading();
@@ -542,6 +562,11 @@ fn load_from_disk_and_cache_in_memory<CTX, K, V: Debug>(
             incremental_verify_ich(*tcx.dep_context(), &result, dep_node, query);
         }
 
+        tcx.dep_context().dep_graph().record_query_result(
+            dep_node_index,
+            QueryResultSource::Loaded,
+            timer,
+        );
         result
     } else {
         // We could not load a result from the on-disk cache, so
@@ -564,6 +589,11 @@ fn load_from_disk_and_cache_in_memory<CTX, K, V: Debug>(
         // an ICE by this check
         incremental_verify_ich(*tcx.dep_context(), &result, dep_node, query);
 
+        tcx.dep_context().dep_graph().record_query_result(
+            dep_node_index,
+            QueryResultSource::Recomputed,
+            timer,
+        );
         result
     }
 }
@@ -625,6 +655,7 @@ fn force_query_with_job<C, CTX>(
     );
 
     let prof_timer = tcx.dep_context().profiler().query_provider();
+    let timer = tcx.dep_context().dep_graph().start_query_timer();
 
     let ((result, dep_node_index), diagnostics) = with_diagnostics(|diagnostics| {
         tcx.start_query(job.id, diagnostics, || {
@@ -649,6 +680,11 @@ fn force_query_with_job<C, CTX>(
     });
 
     prof_timer.finish_with_query_invocation_id(dep_node_index.into());
+    tcx.dep_context().dep_graph().record_query_result(
+        dep_node_index,
+        QueryResultSource::Executed,
+        timer,
+    );
 
     if unlikely!(!diagnostics.is_empty()) && dep_node.kind != DepKind::NULL {
         tcx.store_diagnostics(dep_node_index, diagnostics);
@@ -741,6 +777,7 @@ fn force_query_impl<CTX, C>(
         {
             cache.cache_hits.fetch_add(1, Ordering::Relaxed);
         }
+        tcx.dep_context().dep_graph().record_query_cache_hit(index);
     });
 
     let lookup = match cached {
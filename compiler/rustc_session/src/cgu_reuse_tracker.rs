@@ -1,8 +1,10 @@
 //! Some facilities for tracking how codegen-units are reused during incremental
 //! compilation. This is used for incremental compilation tests and debug
-//! output.
+//! output, like `-Z incremental-explain`.
 
+use crate::config::IncrementalExplainFormat;
 use rustc_data_structures::fx::FxHashMap;
+use rustc_serialize::json;
 use rustc_span::{Span, Symbol};
 use std::sync::{Arc, Mutex};
 use tracing::debug;
@@ -14,6 +16,44 @@ pub enum CguReuse {
     PostLto,
 }
 
+impl CguReuse {
+    fn as_str(self) -> &'static str {
+        match self {
+            CguReuse::No => "no",
+            CguReuse::PreLto => "pre-lto",
+            CguReuse::PostLto => "post-lto",
+        }
+    }
+}
+
+/// Why a codegen-unit could not be reused, for `-Z incremental-explain`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CguRebuildReason {
+    /// The previous session did not save the codegen-unit, e.g. because it did
+    /// not exist yet.
+    NotInPreviousSession,
+    /// An input of the codegen-unit changed. `path` describes the dep-nodes
+    /// from a dependency of the codegen-unit to the changed input, which is the
+    /// last one.
+    InputChanged { path: Vec<String> },
+    /// A dependency of the codegen-unit could not be evaluated again, usually
+    /// because the item it refers to no longer exists.
+    DependencyRemoved { dep_node: String },
+    /// No changed dependency was found, e.g. because of compilation errors.
+    Unknown,
+}
+
+impl CguRebuildReason {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CguRebuildReason::NotInPreviousSession => "not-in-previous-session",
+            CguRebuildReason::InputChanged { .. } => "input-changed",
+            CguRebuildReason::DependencyRemoved { .. } => "dependency-removed",
+            CguRebuildReason::Unknown => "unknown",
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum ComparisonKind {
     Exact,
@@ -22,6 +62,7 @@ pub enum ComparisonKind {
 
 struct TrackerData {
     actual_reuse: FxHashMap<String, CguReuse>,
+    rebuild_reasons: FxHashMap<String, CguRebuildReason>,
     expected_reuse: FxHashMap<String, (String, SendSpan, CguReuse, ComparisonKind)>,
 }
 
@@ -40,8 +81,11 @@ pub struct CguReuseTracker {
 
 impl CguReuseTracker {
     pub fn new() -> CguReuseTracker {
-        let data =
-            TrackerData { actual_reuse: Default::default(), expected_reuse: Default::default() };
+        let data = TrackerData {
+            actual_reuse: Default::default(),
+            rebuild_reasons: Default::default(),
+            expected_reuse: Default::default(),
+        };
 
         CguReuseTracker { data: Some(Arc::new(Mutex::new(data))) }
     }
@@ -65,6 +109,13 @@ pub fn set_actual_reuse(&self, cgu_name: &str, kind: CguReuse) {
         }
     }
 
+    pub fn set_rebuild_reason(&self, cgu_name: &str, reason: CguRebuildReason) {
+        if let Some(ref data) = self.data {
+            debug!("set_rebuild_reason({:?}, {:?})", cgu_name, reason);
+            data.lock().unwrap().rebuild_reasons.insert(cgu_name.to_string(), reason);
+        }
+    }
+
     pub fn set_expectation(
         &self,
         cgu_name: Symbol,
@@ -117,4 +168,119 @@ pub fn check_expected_reuse(&self, diag: &rustc_errors::Handler) {
             }
         }
     }
+
+    /// Prints the reuse of each codegen-unit and, for those that were rebuilt,
+    /// why, for `-Z incremental-explain`.
+    pub fn print_explanation(&self, format: IncrementalExplainFormat) {
+        let data = match self.data {
+            Some(ref data) => data.lock().unwrap(),
+            None => return,
+        };
+
+        let mut cgus: Vec<_> = data
+            .actual_reuse
+            .iter()
+            .map(|(name, &reuse)| {
+                let reason = (reuse == CguReuse::No).then(|| {
+                    data.rebuild_reasons.get(name).unwrap_or(&CguRebuildReason::Unknown)
+                });
+                (name, reuse, reason)
+            })
+            .collect();
+        cgus.sort_by(|(name1, ..), (name2, ..)| name1.cmp(name2));
+
+        match format {
+            IncrementalExplainFormat::Json => {
+                let codegen_units = cgus
+                    .into_iter()
+                    .map(|(name, reuse, reason)| {
+                        // The dep-nodes of the path, from the input to the codegen-unit.
+                        let path = match reason {
+                            Some(CguRebuildReason::InputChanged { path }) => {
+                                path.iter().rev().map(|dep_node| &dep_node[..]).collect()
+                            }
+                            Some(CguRebuildReason::DependencyRemoved { dep_node }) => {
+                                vec![&dep_node[..]]
+                            }
+                            _ => vec![],
+                        };
+                        CguExplanation {
+                            name,
+                            reuse: reuse.as_str(),
+                            reason: reason.map(CguRebuildReason::as_str),
+                            input: path.first().copied(),
+                            path,
+                        }
+                    })
+                    .collect();
+                println!("{}", json::as_json(&CguExplanationReport { codegen_units }));
+            }
+            IncrementalExplainFormat::Text => {
+                let rebuilt = cgus.iter().filter(|(_, reuse, _)| *reuse == CguReuse::No).count();
+                println!(
+                    "incremental-explain: {} of {} codegen units rebuilt",
+                    rebuilt,
+                    cgus.len()
+                );
+                for (name, reuse, reason) in cgus {
+                    let reason = match reason {
+                        Some(reason) => reason,
+                        None => {
+                            println!(
+                                "incremental-explain cgu `{}`: reused ({})",
+                                name,
+                                reuse.as_str()
+                            );
+                            continue;
+                        }
+                    };
+                    match reason {
+                        CguRebuildReason::NotInPreviousSession => println!(
+                            "incremental-explain cgu `{}`: rebuilt, not in the previous session",
+                            name
+                        ),
+                        CguRebuildReason::InputChanged { path } => {
+                            println!(
+                                "incremental-explain cgu `{}`: rebuilt, `{}` changed",
+                                name,
+                                path.last().unwrap()
+                            );
+                            if path.len() > 1 {
+                                let path: Vec<_> = path
+                                    .iter()
+                                    .rev()
+                                    .map(|dep_node| format!("`{}`", dep_node))
+                                    .collect();
+                                println!("incremental-explain     through {}", path.join(" -> "));
+                            }
+                        }
+                        CguRebuildReason::DependencyRemoved { dep_node } => println!(
+                            "incremental-explain cgu `{}`: rebuilt, `{}` no longer exists",
+                            name, dep_node
+                        ),
+                        CguRebuildReason::Unknown => println!(
+                            "incremental-explain cgu `{}`: rebuilt, no changed input found",
+                            name
+                        ),
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[derive(Encodable)]
+struct CguExplanation<'a> {
+    name: &'a str,
+    reuse: &'static str,
+    reason: Option<&'static str>,
+    /// The changed input, or the dependency that no longer exists.
+    input: Option<&'a str>,
+    /// The dep-nodes from the input to the codegen-unit.
+    path: Vec<&'a str>,
+}
+
+#[derive(Encodable)]
+struct CguExplanationReport<'a> {
+    codegen_units: Vec<CguExplanation<'a>>,
 }
@@ -1,7 +1,11 @@
-use rustc_data_structures::fx::FxHashSet;
+use crate::config::{StackSizesFormat, TypeSizesFormat};
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_data_structures::stack::ensure_sufficient_stack;
 use rustc_data_structures::sync::Lock;
+use rustc_serialize::json;
 use rustc_target::abi::{Align, Size};
 use std::cmp::{self, Ordering};
+use std::time::Duration;
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct VariantInfo {
@@ -34,6 +38,32 @@ pub enum DataTypeKind {
     Closure,
 }
 
+/// Where the variant of an enum is stored.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Encodable)]
+pub struct TagInfo {
+    /// Whether the tag is stored in a niche of one of the variants, rather than
+    /// storing the discriminant directly.
+    pub niche_encoded: bool,
+    pub offset: u64,
+    pub size: u64,
+    /// The inclusive range of the values of the tag, which wraps around if
+    /// `valid_range_start > valid_range_end`.
+    pub valid_range_start: u128,
+    pub valid_range_end: u128,
+}
+
+/// The largest niche of a type: a scalar with invalid values, which enclosing
+/// enums can use to store their tag.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Encodable)]
+pub struct NicheInfo {
+    pub offset: u64,
+    pub size: u64,
+    /// The inclusive range of the valid values of the scalar, which wraps
+    /// around if `valid_range_start > valid_range_end`.
+    pub valid_range_start: u128,
+    pub valid_range_end: u128,
+}
+
 #[derive(PartialEq, Eq, Hash, Debug)]
 pub struct TypeSizeInfo {
     pub kind: DataTypeKind,
@@ -42,12 +72,140 @@ pub struct TypeSizeInfo {
     pub overall_size: u64,
     pub packed: bool,
     pub opt_discr_size: Option<u64>,
+    pub tag: Option<TagInfo>,
+    pub niche: Option<NicheInfo>,
     pub variants: Vec<VariantInfo>,
 }
 
+#[derive(Encodable)]
+struct FieldEntry<'a> {
+    name: &'a str,
+    offset: u64,
+    size: u64,
+    align: u64,
+    /// The padding between the previous field, or the discriminant, and this one.
+    padding_before: u64,
+}
+
+#[derive(Encodable)]
+struct VariantEntry<'a> {
+    name: Option<&'a str>,
+    size: u64,
+    align: u64,
+    /// Whether `size` is only the minimum size, because the variant is unsized.
+    min_size: bool,
+    fields: Vec<FieldEntry<'a>>,
+}
+
+#[derive(Encodable)]
+struct TypeSizeEntry<'a> {
+    name: &'a str,
+    kind: &'static str,
+    size: u64,
+    align: u64,
+    packed: bool,
+    tag: Option<&'a TagInfo>,
+    niche: Option<&'a NicheInfo>,
+    variants: Vec<VariantEntry<'a>>,
+    /// The padding after the end of the largest variant.
+    end_padding: u64,
+}
+
+#[derive(Encodable)]
+struct TypeSizeReport<'a> {
+    types: Vec<TypeSizeEntry<'a>>,
+}
+
+/// What is known about a codegened function before its object code exists.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FnStackInfo {
+    /// The demangled symbol name, without the hash.
+    pub name: String,
+    /// The path of the `DefId` the function was instantiated from.
+    pub def_path: String,
+    pub span: String,
+    /// Mangled symbol names of the functions called directly from this one.
+    pub callees: Vec<String>,
+    /// Whether this function calls through function pointers or trait objects.
+    pub has_indirect_calls: bool,
+}
+
+/// The stack frame of a function, as reported by LLVM.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FrameSize {
+    Static(u64),
+    /// The frame contains variable-sized objects, so LLVM reports no size for it.
+    Dynamic,
+    /// The object file containing the function has no `.stack_sizes` section.
+    Unknown,
+}
+
+/// Why no upper bound on the stack used by a call chain could be computed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum UnboundedReason {
+    Recursion,
+    IndirectCalls,
+    DynamicFrame,
+    UnknownFrame,
+}
+
+impl UnboundedReason {
+    fn as_str(self) -> &'static str {
+        match self {
+            UnboundedReason::Recursion => "recursion",
+            UnboundedReason::IndirectCalls => "indirect calls",
+            UnboundedReason::DynamicFrame => "dynamically sized frame",
+            UnboundedReason::UnknownFrame => "unknown frame size",
+        }
+    }
+}
+
+/// Conservative estimate of the stack used by a function and everything it calls.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct CallChainEstimate {
+    bytes: Result<u64, UnboundedReason>,
+    /// Whether the chain calls functions of other crates, whose frames are not included.
+    calls_external: bool,
+}
+
+#[derive(Encodable)]
+struct StackSizeEntry<'a> {
+    name: &'a str,
+    symbol: &'a str,
+    def_path: &'a str,
+    span: &'a str,
+    frame_size: Option<u64>,
+    dynamic: bool,
+    unknown: bool,
+    worst_case_stack: Option<u64>,
+    unbounded_reason: Option<&'static str>,
+    calls_external: bool,
+}
+
+#[derive(Encodable)]
+struct StackSizeReport<'a> {
+    functions: Vec<StackSizeEntry<'a>>,
+}
+
+/// What was measured for the evaluations of one `const`, `static` or promoted
+/// constant.
+#[derive(Clone, Default, Debug)]
+struct ConstEvalStats {
+    evaluations: usize,
+    steps: usize,
+    allocations: usize,
+    time: Duration,
+}
+
 #[derive(Default)]
 pub struct CodeStats {
     type_sizes: Lock<FxHashSet<TypeSizeInfo>>,
+    /// Functions of the local crate, keyed by their mangled symbol name.
+    fn_stack_infos: Lock<FxHashMap<String, FnStackInfo>>,
+    /// Frame sizes of the functions present in the emitted object files.
+    frame_sizes: Lock<FxHashMap<String, FrameSize>>,
+    /// Const evaluations, keyed by the kind and the name of the evaluated item.
+    const_evals: Lock<FxHashMap<(&'static str, String), ConstEvalStats>>,
 }
 
 impl CodeStats {
@@ -59,6 +217,8 @@ pub fn record_type_size<S: ToString>(
         overall_size: Size,
         packed: bool,
         opt_discr_size: Option<Size>,
+        tag: Option<TagInfo>,
+        niche: Option<NicheInfo>,
         mut variants: Vec<VariantInfo>,
     ) {
         // Sort variants so the largest ones are shown first. A stable sort is
@@ -72,12 +232,14 @@ pub fn record_type_size<S: ToString>(
             overall_size: overall_size.bytes(),
             packed,
             opt_discr_size: opt_discr_size.map(|s| s.bytes()),
+            tag,
+            niche,
             variants,
         };
         self.type_sizes.borrow_mut().insert(info);
     }
 
-    pub fn print_type_sizes(&self) {
+    pub fn print_type_sizes(&self, format: TypeSizesFormat) {
         let type_sizes = self.type_sizes.borrow();
         let mut sorted: Vec<_> = type_sizes.iter().collect();
 
@@ -91,6 +253,12 @@ pub fn print_type_sizes(&self) {
             }
         });
 
+        if format == TypeSizesFormat::Json {
+            let types = sorted.iter().map(|info| type_size_entry(info)).collect();
+            println!("{}", json::as_json(&TypeSizeReport { types }));
+            return;
+        }
+
         for info in &sorted {
             println!(
                 "print-type-size type: `{}`: {} bytes, alignment: {} bytes",
@@ -190,4 +358,276 @@ pub fn print_type_sizes(&self) {
             }
         }
     }
+
+    pub fn record_fn_stack_info(&self, symbol_name: String, info: FnStackInfo) {
+        self.fn_stack_infos.borrow_mut().insert(symbol_name, info);
+    }
+
+    pub fn record_frame_size(&self, symbol_name: String, frame_size: FrameSize) {
+        // Inline functions are codegened into every codegen unit using them, and
+        // each copy might have been optimized differently. Keep the largest frame.
+        let mut frame_sizes = self.frame_sizes.borrow_mut();
+        let frame_size = match (frame_sizes.get(&symbol_name), frame_size) {
+            (Some(FrameSize::Static(old)), FrameSize::Static(new)) => {
+                FrameSize::Static(cmp::max(*old, new))
+            }
+            (Some(FrameSize::Dynamic), _) => FrameSize::Dynamic,
+            (Some(&old), FrameSize::Unknown) => old,
+            (_, frame_size) => frame_size,
+        };
+        frame_sizes.insert(symbol_name, frame_size);
+    }
+
+    pub fn print_stack_sizes(&self, format: StackSizesFormat) {
+        let fn_stack_infos = self.fn_stack_infos.borrow();
+        let frame_sizes = self.frame_sizes.borrow();
+
+        // Functions without a frame were inlined everywhere and no longer exist.
+        let mut sorted: Vec<_> = fn_stack_infos
+            .iter()
+            .filter_map(|(symbol, info)| Some((symbol, info, *frame_sizes.get(symbol)?)))
+            .collect();
+
+        // Primary sort: dynamic frames first, then large-to-small, then unknown frames.
+        // Secondary sort: name (dictionary order)
+        sorted.sort_by(|(_, info1, frame1), (_, info2, frame2)| {
+            let key = |frame: &FrameSize| match *frame {
+                FrameSize::Dynamic => (0, cmp::Reverse(0)),
+                FrameSize::Static(size) => (1, cmp::Reverse(size)),
+                FrameSize::Unknown => (2, cmp::Reverse(0)),
+            };
+            key(frame1).cmp(&key(frame2)).then_with(|| info1.name.cmp(&info2.name))
+        });
+
+        let mut estimates = FxHashMap::default();
+        let entries: Vec<_> = sorted
+            .into_iter()
+            .map(|(symbol, info, frame)| {
+                let estimate =
+                    call_chain_estimate(&fn_stack_infos, &frame_sizes, symbol, &mut estimates);
+                StackSizeEntry {
+                    name: &info.name,
+                    symbol,
+                    def_path: &info.def_path,
+                    span: &info.span,
+                    frame_size: match frame {
+                        FrameSize::Static(size) => Some(size),
+                        FrameSize::Dynamic | FrameSize::Unknown => None,
+                    },
+                    dynamic: frame == FrameSize::Dynamic,
+                    unknown: frame == FrameSize::Unknown,
+                    worst_case_stack: estimate.bytes.ok(),
+                    unbounded_reason: estimate.bytes.err().map(UnboundedReason::as_str),
+                    calls_external: estimate.calls_external,
+                }
+            })
+            .collect();
+
+        match format {
+            StackSizesFormat::Json => {
+                println!("{}", json::as_json(&StackSizeReport { functions: entries }));
+            }
+            StackSizesFormat::Text => {
+                for entry in &entries {
+                    match entry.frame_size {
+                        Some(size) => {
+                            println!("print-stack-size fn `{}`: {} bytes", entry.name, size)
+                        }
+                        None if entry.unknown => {
+                            println!("print-stack-size fn `{}`: unknown", entry.name)
+                        }
+                        None => println!("print-stack-size fn `{}`: dynamic", entry.name),
+                    }
+                    let indent = "    ";
+                    println!("print-stack-size {}path: `{}`", indent, entry.def_path);
+                    println!("print-stack-size {}span: {}", indent, entry.span);
+                    let external =
+                        if entry.calls_external { " + callees in other crates" } else { "" };
+                    match (entry.worst_case_stack, entry.unbounded_reason) {
+                        (Some(bytes), _) => println!(
+                            "print-stack-size {}worst-case call chain: {} bytes{}",
+                            indent, bytes, external
+                        ),
+                        (None, reason) => println!(
+                            "print-stack-size {}worst-case call chain: unbounded ({}){}",
+                            indent,
+                            reason.unwrap_or_default(),
+                            external
+                        ),
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn record_const_eval(
+        &self,
+        kind: &'static str,
+        name: String,
+        steps: usize,
+        allocations: usize,
+        time: Duration,
+    ) {
+        let mut const_evals = self.const_evals.borrow_mut();
+        let stats = const_evals.entry((kind, name)).or_default();
+        stats.evaluations += 1;
+        stats.steps += steps;
+        stats.allocations += allocations;
+        stats.time += time;
+    }
+
+    pub fn print_const_eval_profile(&self) {
+        let const_evals = self.const_evals.borrow();
+        let mut sorted: Vec<_> = const_evals.iter().collect();
+
+        // Primary sort: most steps first.
+        // Secondary sort: slowest first.
+        // Tertiary sort: name (dictionary order)
+        sorted.sort_by(|((_, name1), stats1), ((_, name2), stats2)| {
+            stats2
+                .steps
+                .cmp(&stats1.steps)
+                .then_with(|| stats2.time.cmp(&stats1.time))
+                .then_with(|| name1.cmp(name2))
+        });
+
+        let mut total = ConstEvalStats::default();
+        for ((kind, name), stats) in sorted {
+            let evaluations = if stats.evaluations > 1 {
+                format!(" in {} evaluations", stats.evaluations)
+            } else {
+                String::new()
+            };
+            println!(
+                "print-const-eval-profile {} `{}`: {} steps, {} allocations, {:.3}s{}",
+                kind,
+                name,
+                stats.steps,
+                stats.allocations,
+                stats.time.as_secs_f64(),
+                evaluations
+            );
+            total.evaluations += stats.evaluations;
+            total.steps += stats.steps;
+            total.allocations += stats.allocations;
+        }
+        println!(
+            "print-const-eval-profile total: {} steps, {} allocations in {} evaluations",
+            total.steps, total.allocations, total.evaluations
+        );
+    }
+}
+
+/// Computes the worst-case stack usage of the call chain starting at `symbol`.
+///
+/// Functions that were recorded but have no frame in the object files were
+/// inlined into all of their callers; they contribute no frame of their own,
+/// but their callees are still followed.
+fn call_chain_estimate(
+    fn_stack_infos: &FxHashMap<String, FnStackInfo>,
+    frame_sizes: &FxHashMap<String, FrameSize>,
+    symbol: &str,
+    estimates: &mut FxHashMap<String, Option<CallChainEstimate>>,
+) -> CallChainEstimate {
+    match estimates.get(symbol) {
+        Some(Some(estimate)) => return *estimate,
+        // We are already computing this function's estimate further up the call chain.
+        Some(None) => {
+            return CallChainEstimate {
+                bytes: Err(UnboundedReason::Recursion),
+                calls_external: false,
+            };
+        }
+        None => {}
+    }
+    estimates.insert(symbol.to_owned(), None);
+
+    let info = &fn_stack_infos[symbol];
+    let frame = match frame_sizes.get(symbol) {
+        Some(FrameSize::Static(size)) => Ok(*size),
+        Some(FrameSize::Dynamic) => Err(UnboundedReason::DynamicFrame),
+        Some(FrameSize::Unknown) => Err(UnboundedReason::UnknownFrame),
+        None => Ok(0),
+    };
+
+    let mut estimate = CallChainEstimate { bytes: frame, calls_external: false };
+    if info.has_indirect_calls {
+        estimate.bytes = estimate.bytes.and(Err(UnboundedReason::IndirectCalls));
+    }
+    let mut deepest_callee = 0;
+    for callee in &info.callees {
+        if !fn_stack_infos.contains_key(callee) {
+            estimate.calls_external = true;
+            continue;
+        }
+        let callee_estimate = ensure_sufficient_stack(|| {
+            call_chain_estimate(fn_stack_infos, frame_sizes, callee, estimates)
+        });
+        estimate.calls_external |= callee_estimate.calls_external;
+        match callee_estimate.bytes {
+            Ok(bytes) => deepest_callee = cmp::max(deepest_callee, bytes),
+            Err(reason) => estimate.bytes = estimate.bytes.and(Err(reason)),
+        }
+    }
+    estimate.bytes = estimate.bytes.map(|frame| frame + deepest_callee);
+
+    estimates.insert(symbol.to_owned(), Some(estimate));
+    estimate
+}
+
+/// Describes the layout of a type for `-Z print-type-sizes=json`, with the
+/// padding computed the same way as for the text output.
+fn type_size_entry(info: &TypeSizeInfo) -> TypeSizeEntry<'_> {
+    let discr_size = info.opt_discr_size.unwrap_or(0);
+    let mut max_variant_size = discr_size;
+    let variants = info
+        .variants
+        .iter()
+        .map(|variant| {
+            max_variant_size = cmp::max(max_variant_size, variant.size);
+
+            let mut fields: Vec<_> = variant.fields.iter().collect();
+            fields.sort_by_key(|f| (f.offset, f.size));
+            let mut min_offset = discr_size;
+            let fields = fields
+                .into_iter()
+                .map(|field| {
+                    let padding_before = field.offset.saturating_sub(min_offset);
+                    min_offset = field.offset + field.size;
+                    FieldEntry {
+                        name: &field.name,
+                        offset: field.offset,
+                        size: field.size,
+                        align: field.align,
+                        padding_before,
+                    }
+                })
+                .collect();
+
+            VariantEntry {
+                name: variant.name.as_deref(),
+                size: variant.size,
+                align: variant.align,
+                min_size: variant.kind == SizeKind::Min,
+                fields,
+            }
+        })
+        .collect();
+
+    TypeSizeEntry {
+        name: &info.type_description,
+        kind: match info.kind {
+            DataTypeKind::Struct => "struct",
+            DataTypeKind::Union => "union",
+            DataTypeKind::Enum => "enum",
+            DataTypeKind::Closure => "closure",
+        },
+        size: info.overall_size,
+        align: info.align,
+        packed: info.packed,
+        tag: info.tag.as_ref(),
+        niche: info.niche.as_ref(),
+        variants,
+        end_padding: info.overall_size.saturating_sub(max_variant_size),
+    }
 }
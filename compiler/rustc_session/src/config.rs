@@ -6,7 +6,7 @@
 use crate::lint;
 use crate::search_paths::SearchPath;
 use crate::utils::{CanonicalizedPath, NativeLibKind};
-use crate::{early_error, early_warn, Session};
+use crate::{early_error, early_warn_for_session, Session};
 
 use rustc_data_structures::fx::FxHashSet;
 use rustc_data_structures::impl_stable_hash_via_hash;
@@ -24,7 +24,8 @@
 use rustc_span::SourceFileHashAlgorithm;
 
 use rustc_errors::emitter::HumanReadableErrorType;
-use rustc_errors::{ColorConfig, HandlerFlags};
+use rustc_errors::json::JsonSchemaVersion;
+use rustc_errors::{ColorConfig, Diagnostic, HandlerFlags};
 
 use std::collections::btree_map::{
     Iter as BTreeMapIter, Keys as BTreeMapKeysIter, Values as BTreeMapValuesIter,
@@ -123,6 +124,33 @@ pub enum MirSpanview {
     Block,
 }
 
+/// The output formats of `-Z print-stack-sizes`.
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum StackSizesFormat {
+    /// Default `-Z print-stack-sizes` or `-Z print-stack-sizes=text`
+    Text,
+    /// `-Z print-stack-sizes=json`
+    Json,
+}
+
+/// The output formats of `-Z print-type-sizes`.
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum TypeSizesFormat {
+    /// Default `-Z print-type-sizes` or `-Z print-type-sizes=text`
+    Text,
+    /// `-Z print-type-sizes=json`
+    Json,
+}
+
+/// The output formats of `-Z incremental-explain`.
+#[derive(Clone, Copy, PartialEq, Hash, Debug)]
+pub enum IncrementalExplainFormat {
+    /// Default `-Z incremental-explain` or `-Z incremental-explain=text`
+    Text,
+    /// `-Z incremental-explain=json`
+    Json,
+}
+
 /// The different settings that the `-Z instrument-coverage` flag can have.
 ///
 /// Coverage instrumentation now supports combining `-Z instrument-coverage`
@@ -296,6 +324,8 @@ pub enum ErrorOutputType {
         /// human output.
         json_rendered: HumanReadableErrorType,
     },
+    /// A single SARIF log for the whole session, for code-scanning platforms.
+    Sarif,
 }
 
 impl Default for ErrorOutputType {
@@ -509,6 +539,8 @@ pub enum PrintRequest {
     TlsModels,
     TargetSpec,
     NativeStaticLibs,
+    LintLevels,
+    MonoStats,
 }
 
 #[derive(Copy, Clone)]
@@ -703,6 +735,8 @@ fn default() -> Options {
             edition: DEFAULT_EDITION,
             json_artifact_notifications: false,
             json_unused_externs: false,
+            json_schema_version: JsonSchemaVersion::V1,
+            early_diagnostics: Vec::new(),
             pretty: None,
         }
     }
@@ -1048,7 +1082,8 @@ pub fn rustc_short_optgroups() -> Vec<RustcOptGroup> {
             "Compiler information to print on stdout",
             "[crate-name|file-names|sysroot|target-libdir|cfg|target-list|\
              target-cpus|target-features|relocation-models|\
-             code-models|tls-models|target-spec-json|native-static-libs]",
+             code-models|tls-models|target-spec-json|native-static-libs|\
+             lint-levels|mono-stats]",
         ),
         opt::flagmulti_s("g", "", "Equivalent to -C debuginfo=2"),
         opt::flagmulti_s("O", "", "Equivalent to -C opt-level=2"),
@@ -1111,7 +1146,7 @@ pub fn rustc_optgroups() -> Vec<RustcOptGroup> {
             "",
             "error-format",
             "How errors and other messages are produced",
-            "human|json|short",
+            "human|json|short|sarif",
         ),
         opt::multi_s("", "json", "Configure the JSON output of the compiler", "CONFIG"),
         opt::opt_s(
@@ -1206,6 +1241,7 @@ pub struct JsonConfig {
     pub json_rendered: HumanReadableErrorType,
     pub json_artifact_notifications: bool,
     pub json_unused_externs: bool,
+    pub json_schema_version: JsonSchemaVersion,
 }
 
 /// Parse the `--json` flag.
@@ -1218,6 +1254,7 @@ pub fn parse_json(matches: &getopts::Matches) -> JsonConfig {
     let mut json_color = ColorConfig::Never;
     let mut json_artifact_notifications = false;
     let mut json_unused_externs = false;
+    let mut json_schema_version = JsonSchemaVersion::V1;
     for option in matches.opt_strs("json") {
         // For now conservatively forbid `--color` with `--json` since `--json`
         // won't actually be emitting any colors and anything colorized is
@@ -1235,6 +1272,7 @@ pub fn parse_json(matches: &getopts::Matches) -> JsonConfig {
                 "diagnostic-rendered-ansi" => json_color = ColorConfig::Always,
                 "artifacts" => json_artifact_notifications = true,
                 "unused-externs" => json_unused_externs = true,
+                "schema-v2" => json_schema_version = JsonSchemaVersion::V2,
                 s => early_error(
                     ErrorOutputType::default(),
                     &format!("unknown `--json` option `{}`", s),
@@ -1247,6 +1285,7 @@ pub fn parse_json(matches: &getopts::Matches) -> JsonConfig {
         json_rendered: json_rendered(json_color),
         json_artifact_notifications,
         json_unused_externs,
+        json_schema_version,
     }
 }
 
@@ -1271,12 +1310,13 @@ pub fn parse_error_format(
             Some("json") => ErrorOutputType::Json { pretty: false, json_rendered },
             Some("pretty-json") => ErrorOutputType::Json { pretty: true, json_rendered },
             Some("short") => ErrorOutputType::HumanReadable(HumanReadableErrorType::Short(color)),
+            Some("sarif") => ErrorOutputType::Sarif,
 
             Some(arg) => early_error(
                 ErrorOutputType::HumanReadable(HumanReadableErrorType::Default(color)),
                 &format!(
-                    "argument for `--error-format` must be `human`, `json` or \
-                     `short` (instead was `{}`)",
+                    "argument for `--error-format` must be `human`, `json`, \
+                     `short` or `sarif` (instead was `{}`)",
                     arg
                 ),
             ),
@@ -1355,6 +1395,12 @@ fn check_debug_option_stability(
                 "`--error-format=human-annotate-rs` is unstable",
             );
         }
+        if let ErrorOutputType::Sarif = error_format {
+            early_error(
+                ErrorOutputType::default(),
+                "`--error-format=sarif` is unstable",
+            );
+        }
     }
 }
 
@@ -1395,6 +1441,7 @@ fn should_override_cgus_and_disable_thinlto(
     output_types: &OutputTypes,
     matches: &getopts::Matches,
     error_format: ErrorOutputType,
+    early_diagnostics: &mut Vec<Diagnostic>,
     mut codegen_units: Option<usize>,
 ) -> (bool, Option<usize>) {
     let mut disable_thinlto = false;
@@ -1412,8 +1459,9 @@ fn should_override_cgus_and_disable_thinlto(
             Some(n) if n > 1 => {
                 if matches.opt_present("o") {
                     for ot in &incompatible {
-                        early_warn(
+                        early_warn_for_session(
                             error_format,
+                            early_diagnostics,
                             &format!(
                                 "`--emit={}` with `-o` incompatible with \
                                  `-C codegen-units=N` for N > 1",
@@ -1421,7 +1469,11 @@ fn should_override_cgus_and_disable_thinlto(
                             ),
                         );
                     }
-                    early_warn(error_format, "resetting to default -C codegen-units=1");
+                    early_warn_for_session(
+                        error_format,
+                        early_diagnostics,
+                        "resetting to default -C codegen-units=1",
+                    );
                     codegen_units = Some(1);
                     disable_thinlto = true;
                 }
@@ -1490,6 +1542,28 @@ fn collect_print_requests(
                 );
             }
         }
+        "lint-levels" => {
+            if dopts.unstable_options {
+                PrintRequest::LintLevels
+            } else {
+                early_error(
+                    error_format,
+                    "the `-Z unstable-options` flag must also be passed to \
+                     enable the lint-levels print option",
+                );
+            }
+        }
+        "mono-stats" => {
+            if dopts.unstable_options {
+                PrintRequest::MonoStats
+            } else {
+                early_error(
+                    error_format,
+                    "the `-Z unstable-options` flag must also be passed to \
+                     enable the mono-stats print option",
+                );
+            }
+        }
         req => early_error(error_format, &format!("unknown print request `{}`", req)),
     }));
 
@@ -1828,10 +1902,15 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
 
     let edition = parse_crate_edition(matches);
 
-    let JsonConfig { json_rendered, json_artifact_notifications, json_unused_externs } =
-        parse_json(matches);
+    let JsonConfig {
+        json_rendered,
+        json_artifact_notifications,
+        json_unused_externs,
+        json_schema_version,
+    } = parse_json(matches);
 
     let error_format = parse_error_format(matches, color, json_rendered);
+    let mut early_diagnostics = Vec::new();
 
     let unparsed_crate_types = matches.opt_strs("crate-type");
     let crate_types = parse_crate_types_from_list(unparsed_crate_types)
@@ -1850,6 +1929,14 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
         );
     }
 
+    if !debugging_opts.unstable_options && json_schema_version != JsonSchemaVersion::V1 {
+        early_error(
+            error_format,
+            "the `-Z unstable-options` flag must also be passed to enable \
+            the flag `--json=schema-v2`",
+        );
+    }
+
     let output_types = parse_output_types(&debugging_opts, matches, error_format);
 
     let mut cg = build_codegen_options(matches, error_format);
@@ -1857,6 +1944,7 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
         &output_types,
         matches,
         error_format,
+        &mut early_diagnostics,
         cg.codegen_units,
     );
 
@@ -1864,6 +1952,14 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
 
     let incremental = cg.incremental.as_ref().map(PathBuf::from);
 
+    if debugging_opts.dump_query_graph.is_some() && incremental.is_none() {
+        early_error(error_format, "`-Z dump-query-graph` requires `-C incremental`");
+    }
+
+    if debugging_opts.incremental_explain.is_some() && incremental.is_none() {
+        early_error(error_format, "`-Z incremental-explain` requires `-C incremental`");
+    }
+
     if debugging_opts.profile && incremental.is_some() {
         early_error(
             error_format,
@@ -1908,14 +2004,21 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
                 debugging_opts.symbol_mangling_version = Some(SymbolManglingVersion::V0);
             }
             Some(SymbolManglingVersion::Legacy) => {
-                early_warn(
+                early_warn_for_session(
                     error_format,
+                    &mut early_diagnostics,
                     "-Z instrument-coverage requires symbol mangling version `v0`, \
                     but `-Z symbol-mangling-version=legacy` was specified",
                 );
             }
             Some(SymbolManglingVersion::V0) => {}
         }
+    } else if debugging_opts.instrument_coverage_branches {
+        early_warn_for_session(
+            error_format,
+            &mut early_diagnostics,
+            "-Z instrument-coverage-branches has no effect without -Z instrument-coverage",
+        );
     }
 
     if let Ok(graphviz_font) = std::env::var("RUSTC_GRAPHVIZ_FONT") {
@@ -1957,7 +2060,11 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
     let borrowck_mode = parse_borrowck_mode(&debugging_opts, error_format);
 
     if !cg.remark.is_empty() && debuginfo == DebugInfo::None {
-        early_warn(error_format, "-C remark requires \"-C debuginfo=n\" to show source locations");
+        early_warn_for_session(
+            error_format,
+            &mut early_diagnostics,
+            "-C remark requires \"-C debuginfo=n\" to show source locations",
+        );
     }
 
     let externs = parse_externs(matches, &debugging_opts, error_format);
@@ -2011,6 +2118,8 @@ pub fn build_session_options(matches: &getopts::Matches) -> Options {
         edition,
         json_artifact_notifications,
         json_unused_externs,
+        json_schema_version,
+        early_diagnostics,
         pretty,
     }
 }
@@ -2279,8 +2388,8 @@ pub fn needs_analysis(&self) -> bool {
 crate mod dep_tracking {
     use super::{
         CFGuard, CrateType, DebugInfo, ErrorOutputType, InstrumentCoverage, LinkerPluginLto,
-        LtoCli, OptLevel, OutputTypes, Passes, SourceFileHashAlgorithm, SwitchWithOptPath,
-        SymbolManglingVersion, TrimmedDefPaths,
+        LtoCli, OptLevel, OutputTypes, Passes, SourceFileHashAlgorithm, StackSizesFormat,
+        SwitchWithOptPath, SymbolManglingVersion, TrimmedDefPaths,
     };
     use crate::lint;
     use crate::options::WasiExecModel;
@@ -2348,6 +2457,7 @@ fn hash(&self, hasher: &mut DefaultHasher, error_format: ErrorOutputType) {
     impl_dep_tracking_hash_via_hash!(Option<InstrumentCoverage>);
     impl_dep_tracking_hash_via_hash!(Option<lint::Level>);
     impl_dep_tracking_hash_via_hash!(Option<PathBuf>);
+    impl_dep_tracking_hash_via_hash!(Option<StackSizesFormat>);
     impl_dep_tracking_hash_via_hash!(CrateType);
     impl_dep_tracking_hash_via_hash!(MergeFunctions);
     impl_dep_tracking_hash_via_hash!(PanicStrategy);
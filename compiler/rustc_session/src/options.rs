@@ -8,6 +8,9 @@
 use rustc_target::spec::{CodeModel, LinkerFlavor, MergeFunctions, PanicStrategy, SanitizerSet};
 use rustc_target::spec::{RelocModel, RelroLevel, SplitDebuginfo, TargetTriple, TlsModel};
 
+use rustc_data_structures::profiling::SelfProfileFormat;
+use rustc_errors::json::JsonSchemaVersion;
+use rustc_errors::Diagnostic;
 use rustc_feature::UnstableFeatures;
 use rustc_span::edition::Edition;
 use rustc_span::SourceFileHashAlgorithm;
@@ -150,6 +153,13 @@ pub struct Options {
         // `true` if we're emitting a JSON blob containing the unused externs
         json_unused_externs: bool [UNTRACKED],
 
+        // The version of the schema followed by JSON diagnostics.
+        json_schema_version: JsonSchemaVersion [UNTRACKED],
+
+        // Warnings about the options that are yet to be emitted by the handler
+        // of the session. See `early_warn_for_session`.
+        early_diagnostics: Vec<Diagnostic> [UNTRACKED],
+
         pretty: Option<PpMode> [UNTRACKED],
     }
 );
@@ -265,6 +275,9 @@ mod $mod_desc {
         pub const parse_linker_flavor: &str = ::rustc_target::spec::LinkerFlavor::one_of();
         pub const parse_optimization_fuel: &str = "crate=integer";
         pub const parse_mir_spanview: &str = "`statement` (default), `terminator`, or `block`";
+        pub const parse_stack_sizes_format: &str = "`text` (default) or `json`";
+        pub const parse_incremental_explain_format: &str = "`text` (default) or `json`";
+        pub const parse_type_sizes_format: &str = "`text` (default) or `json`";
         pub const parse_instrument_coverage: &str = "`all` (default), `except-unused-generics`, `except-unused-functions`, or `off`";
         pub const parse_unpretty: &str = "`string` or `string=string`";
         pub const parse_treat_err_as_bug: &str = "either no value or a number bigger than 0";
@@ -287,6 +300,8 @@ mod $mod_desc {
         pub const parse_wasi_exec_model: &str = "either `command` or `reactor`";
         pub const parse_split_debuginfo: &str =
             "one of supported split-debuginfo modes (`off` or `dsymutil`)";
+        pub const parse_self_profile_format: &str =
+            "one of: `measureme`, `chrome-json`, `summary`";
     }
 
     #[allow(dead_code)]
@@ -596,6 +611,41 @@ fn parse_mir_spanview(slot: &mut Option<MirSpanview>, v: Option<&str>) -> bool {
             true
         }
 
+        fn parse_stack_sizes_format(slot: &mut Option<StackSizesFormat>, v: Option<&str>) -> bool {
+            *slot = Some(match v {
+                None | Some("text") => StackSizesFormat::Text,
+                Some("json") => StackSizesFormat::Json,
+                _ => return false,
+            });
+            true
+        }
+
+        fn parse_type_sizes_format(slot: &mut Option<TypeSizesFormat>, v: Option<&str>) -> bool {
+            // `y`, `yes`, `on`, `n`, `no` and `off` are still accepted from when
+            // this was a boolean option.
+            *slot = match v {
+                None | Some("text") | Some("y") | Some("yes") | Some("on") => {
+                    Some(TypeSizesFormat::Text)
+                }
+                Some("json") => Some(TypeSizesFormat::Json),
+                Some("n") | Some("no") | Some("off") => None,
+                _ => return false,
+            };
+            true
+        }
+
+        fn parse_incremental_explain_format(
+            slot: &mut Option<IncrementalExplainFormat>,
+            v: Option<&str>,
+        ) -> bool {
+            *slot = Some(match v {
+                None | Some("text") => IncrementalExplainFormat::Text,
+                Some("json") => IncrementalExplainFormat::Json,
+                _ => return false,
+            });
+            true
+        }
+
         fn parse_instrument_coverage(slot: &mut Option<InstrumentCoverage>, v: Option<&str>) -> bool {
             if v.is_some() {
                 let mut bool_arg = None;
@@ -770,6 +820,16 @@ fn parse_split_debuginfo(slot: &mut Option<SplitDebuginfo>, v: Option<&str>) ->
             }
             true
         }
+
+        fn parse_self_profile_format(slot: &mut SelfProfileFormat, v: Option<&str>) -> bool {
+            *slot = match v {
+                Some("measureme") => SelfProfileFormat::Measureme,
+                Some("chrome-json") => SelfProfileFormat::ChromeJson,
+                Some("summary") => SelfProfileFormat::Summary,
+                _ => return false,
+            };
+            true
+        }
     }
 ) }
 
@@ -917,6 +977,9 @@ fn parse_split_debuginfo(slot: &mut Option<SplitDebuginfo>, v: Option<&str>) ->
         "the backend to use"),
     combine_cgu: bool = (false, parse_bool, [TRACKED],
         "combine CGUs into a single one"),
+    const_eval_profile: bool = (false, parse_bool, [UNTRACKED],
+        "print the interpreter steps, allocations and time spent evaluating each \
+        `const` and `static` of the crate (default: no)"),
     crate_attr: Vec<String> = (Vec::new(), parse_string_push, [TRACKED],
         "inject the given attribute in the crate"),
     debug_macros: bool = (false, parse_bool, [TRACKED],
@@ -929,6 +992,9 @@ fn parse_split_debuginfo(slot: &mut Option<SplitDebuginfo>, v: Option<&str>) ->
     dep_tasks: bool = (false, parse_bool, [UNTRACKED],
         "print tasks that execute and the color their dep node gets (requires debug build) \
         (default: no)"),
+    diagnostic_baseline: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "do not report warnings found in this file, or record all warnings into it if it \
+        does not exist yet"),
     dont_buffer_diagnostics: bool = (false, parse_bool, [UNTRACKED],
         "emit diagnostics rather than buffering (breaks NLL error downgrading, sorting) \
         (default: no)"),
@@ -961,6 +1027,10 @@ fn parse_split_debuginfo(slot: &mut Option<SplitDebuginfo>, v: Option<&str>) ->
         computed `block` spans (one span encompassing a block's terminator and \
         all statements). If `-Z instrument-coverage` is also enabled, create \
         an additional `.html` file showing the computed coverage spans."),
+    dump_query_graph: Option<PathBuf> = (None, parse_opt_pathbuf, [UNTRACKED],
+        "write the queries run in this session, with their dependencies, timings, cache hits \
+        and incremental status, and any query cycles, as JSON to this file \
+        (requires `-C incremental`)"),
     emit_future_incompat_report: bool = (false, parse_bool, [UNTRACKED],
         "emits a future-incompatibility report for lints (RFC 2834)"),
     emit_stack_sizes: bool = (false, parse_bool, [UNTRACKED],
@@ -987,11 +1057,18 @@ fn parse_split_debuginfo(slot: &mut Option<SplitDebuginfo>, v: Option<&str>) ->
         "generate human-readable, predictable names for codegen units (default: no)"),
     identify_regions: bool = (false, parse_bool, [UNTRACKED],
         "display unnamed regions as `'<id>`, using a non-ident unique id (default: no)"),
+    incremental_explain: Option<IncrementalExplainFormat> = (None,
+        parse_incremental_explain_format, [UNTRACKED],
+        "explain why each codegen unit was rebuilt, naming the first changed input it depends \
+        on, as `text` (default) or `json` (requires `-C incremental`)"),
     incremental_ignore_spans: bool = (false, parse_bool, [UNTRACKED],
         "ignore spans during ICH computation -- used for testing (default: no)"),
     incremental_info: bool = (false, parse_bool, [UNTRACKED],
         "print high-level information about incremental reuse (or the lack thereof) \
         (default: no)"),
+    incremental_max_size: Option<u64> = (None, parse_opt_number, [UNTRACKED],
+        "when the incremental compilation directory grows larger than this many bytes, delete \
+        the least recently used session directories of other crates until it fits"),
     incremental_verify_ich: bool = (false, parse_bool, [UNTRACKED],
         "verify incr. comp. hashes of green query instances (default: no)"),
     inline_mir: Option<bool> = (None, parse_opt_bool, [TRACKED],
@@ -1012,6 +1089,10 @@ fn parse_split_debuginfo(slot: &mut Option<SplitDebuginfo>, v: Option<&str>) ->
         optimizations. Optional values are: `=all` (default coverage), \
         `=except-unused-generics`, `=except-unused-functions`, or `=off` \
         (default: instrument-coverage=off)"),
+    instrument_coverage_branches: bool = (false, parse_bool, [TRACKED],
+        "also report branch coverage for the conditions of `if`, `while` and `match` \
+        expressions, and for each operand of `&&` and `||`; requires `-Z instrument-coverage` \
+        and LLVM 12 or higher (default: no)"),
     instrument_mcount: bool = (false, parse_bool, [TRACKED],
         "insert function instrument code for mcount-based tracing (default: no)"),
     keep_hygiene_data: bool = (false, parse_bool, [UNTRACKED],
@@ -1094,8 +1175,12 @@ fn parse_split_debuginfo(slot: &mut Option<SplitDebuginfo>, v: Option<&str>) ->
         "print the LLVM optimization passes being run (default: no)"),
     print_mono_items: Option<String> = (None, parse_opt_string, [UNTRACKED],
         "print the result of the monomorphization collection pass"),
-    print_type_sizes: bool = (false, parse_bool, [UNTRACKED],
-        "print layout information for each type encountered (default: no)"),
+    print_stack_sizes: Option<StackSizesFormat> = (None, parse_stack_sizes_format, [TRACKED],
+        "print the stack frame size of each function and a worst-case estimate of the stack \
+        used by its call chain, as `text` (default) or `json`; implies `-Z emit-stack-sizes`"),
+    print_type_sizes: Option<TypeSizesFormat> = (None, parse_type_sizes_format, [UNTRACKED],
+        "print layout information for each type encountered, as `text` (default) or `json` \
+        (with field offsets, padding and niches)"),
     proc_macro_backtrace: bool = (false, parse_bool, [UNTRACKED],
          "show backtraces for panics during proc-macro execution (default: no)"),
     profile: bool = (false, parse_bool, [TRACKED],
@@ -1134,6 +1219,10 @@ fn parse_split_debuginfo(slot: &mut Option<SplitDebuginfo>, v: Option<&str>) ->
         for example: `-Z self-profile-events=default,query-keys`
         all options: none, all, default, generic-activity, query-provider, query-cache-hit
                      query-blocked, incr-cache-load, query-keys, function-args, args, llvm"),
+    self_profile_format: SelfProfileFormat = (SelfProfileFormat::Measureme,
+        parse_self_profile_format, [UNTRACKED],
+        "the format the self profiler writes its data in: `measureme` (the default), \
+        `chrome-json` (Chrome trace events) or `summary` (a table of the time spent per activity)"),
     share_generics: Option<bool> = (None, parse_opt_bool, [TRACKED],
         "make the current crate share its generic instantiations"),
     show_span: Option<String> = (None, parse_opt_string, [TRACKED],
@@ -1,6 +1,6 @@
 use crate::cgu_reuse_tracker::CguReuseTracker;
 use crate::code_stats::CodeStats;
-pub use crate::code_stats::{DataTypeKind, FieldInfo, SizeKind, VariantInfo};
+pub use crate::code_stats::{DataTypeKind, FieldInfo, NicheInfo, SizeKind, TagInfo, VariantInfo};
 use crate::config::{self, CrateType, OutputType, PrintRequest, SwitchWithOptPath};
 use crate::filesearch;
 use crate::lint::{self, LintId};
@@ -17,9 +17,11 @@
     self, AtomicU64, AtomicUsize, Lock, Lrc, OnceCell, OneThread, Ordering, Ordering::SeqCst,
 };
 use rustc_errors::annotate_snippet_emitter_writer::AnnotateSnippetEmitterWriter;
+use rustc_errors::baseline::DiagnosticBaseline;
 use rustc_errors::emitter::{Emitter, EmitterWriter, HumanReadableErrorType};
 use rustc_errors::json::JsonEmitter;
 use rustc_errors::registry::Registry;
+use rustc_errors::sarif::SarifEmitter;
 use rustc_errors::{Diagnostic, DiagnosticBuilder, DiagnosticId, ErrorReported};
 use rustc_lint_defs::FutureBreakage;
 pub use rustc_span::crate_disambiguator::CrateDisambiguator;
@@ -34,9 +36,10 @@
 use std::env;
 use std::fmt;
 use std::io::Write;
+use std::mem;
 use std::num::NonZeroU32;
 use std::ops::{Div, Mul};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
@@ -303,8 +306,20 @@ fn check_miri_unleashed_features(&self) {
     /// Invoked all the way at the end to finish off diagnostics printing.
     pub fn finish_diagnostics(&self, registry: &Registry) {
         self.check_miri_unleashed_features();
+        self.finish_self_profile();
+        self.diagnostic().finish_diagnostic_baseline();
         self.diagnostic().print_error_count(registry);
         self.emit_future_breakage();
+        self.diagnostic().finish_emitter();
+    }
+
+    /// Writes the data of the built-in `-Z self-profile-format`s.
+    fn finish_self_profile(&self) {
+        self.prof.with_profiler(|profiler| {
+            if let Err(e) = profiler.finish() {
+                self.warn(&format!("failed to write self-profile data: {}", e));
+            }
+        });
     }
 
     fn emit_future_breakage(&self) {
@@ -502,7 +517,7 @@ pub fn delay_span_bug<S: Into<MultiSpan>>(&self, sp: S, msg: &str) {
     /// warnings or errors are emitted. If no messages are emitted ("good path"), then
     /// it's likely a bug.
     pub fn delay_good_path_bug(&self, msg: &str) {
-        if self.opts.debugging_opts.print_type_sizes
+        if self.opts.debugging_opts.print_type_sizes.is_some()
             || self.opts.debugging_opts.query_dep_graph
             || self.opts.debugging_opts.dump_mir.is_some()
             || self.opts.debugging_opts.unpretty.is_some()
@@ -1112,6 +1127,10 @@ pub fn instrument_coverage_except_unused_functions(&self) -> bool {
             == config::InstrumentCoverage::ExceptUnusedFunctions
     }
 
+    pub fn instrument_coverage_branches(&self) -> bool {
+        self.instrument_coverage() && self.opts.debugging_opts.instrument_coverage_branches
+    }
+
     pub fn mark_attr_known(&self, attr: &Attribute) {
         self.known_attrs.lock().mark(attr)
     }
@@ -1227,7 +1246,8 @@ fn default_emitter(
                 sopts.debugging_opts.terminal_width,
                 macro_backtrace,
             )
-            .ui_testing(sopts.debugging_opts.ui_testing),
+            .ui_testing(sopts.debugging_opts.ui_testing)
+            .schema_version(sopts.json_schema_version),
         ),
         (config::ErrorOutputType::Json { pretty, json_rendered }, Some(dst)) => Box::new(
             JsonEmitter::new(
@@ -1239,8 +1259,15 @@ fn default_emitter(
                 sopts.debugging_opts.terminal_width,
                 macro_backtrace,
             )
-            .ui_testing(sopts.debugging_opts.ui_testing),
+            .ui_testing(sopts.debugging_opts.ui_testing)
+            .schema_version(sopts.json_schema_version),
         ),
+        (config::ErrorOutputType::Sarif, None) => {
+            Box::new(SarifEmitter::stderr(Some(registry), source_map, false))
+        }
+        (config::ErrorOutputType::Sarif, Some(dst)) => {
+            Box::new(SarifEmitter::new(dst, Some(registry), source_map, false))
+        }
     }
 }
 
@@ -1249,8 +1276,27 @@ pub enum DiagnosticOutput {
     Raw(Box<dyn Write + Send>),
 }
 
+/// The name of the section of the diagnostic baseline holding the warnings of
+/// this crate. Cargo gives the library, binaries and tests of a package the same
+/// crate name, so the crate type is part of the name as well.
+fn diagnostic_baseline_section(sopts: &config::Options, input: Option<&Path>) -> String {
+    let name = match (&sopts.crate_name, input.and_then(|input| input.file_stem())) {
+        (Some(name), _) => name.clone(),
+        (None, Some(stem)) => stem.to_string_lossy().replace('-', "_"),
+        (None, None) => "rust_out".to_owned(),
+    };
+    if sopts.test {
+        format!("{} test", name)
+    } else if sopts.crate_types.is_empty() {
+        name
+    } else {
+        let types: Vec<_> = sopts.crate_types.iter().map(|ty| ty.to_string()).collect();
+        format!("{} {}", name, types.join(","))
+    }
+}
+
 pub fn build_session(
-    sopts: config::Options,
+    mut sopts: config::Options,
     local_crate_source_file: Option<PathBuf>,
     registry: rustc_errors::registry::Registry,
     diagnostics_output: DiagnosticOutput,
@@ -1301,6 +1347,16 @@ pub fn build_session(
         emitter,
         sopts.debugging_opts.diagnostic_handler_flags(can_emit_warnings),
     );
+    if let Some(ref path) = sopts.debugging_opts.diagnostic_baseline {
+        let section = diagnostic_baseline_section(&sopts, local_crate_source_file.as_deref());
+        match DiagnosticBaseline::load(path, &section) {
+            Ok(baseline) => span_diagnostic.set_diagnostic_baseline(baseline),
+            Err(e) => early_error(
+                sopts.error_format,
+                &format!("failed to read diagnostic baseline `{}`: {}", path.display(), e),
+            ),
+        }
+    }
 
     let self_profiler = if let SwitchWithOptPath::Enabled(ref d) = sopts.debugging_opts.self_profile
     {
@@ -1311,11 +1367,16 @@ pub fn build_session(
             directory,
             sopts.crate_name.as_deref(),
             &sopts.debugging_opts.self_profile_events,
+            sopts.debugging_opts.self_profile_format,
         );
         match profiler {
             Ok(profiler) => Some(Arc::new(profiler)),
             Err(e) => {
-                early_warn(sopts.error_format, &format!("failed to create profiler: {}", e));
+                early_warn_for_session(
+                    sopts.error_format,
+                    &mut sopts.early_diagnostics,
+                    &format!("failed to create profiler: {}", e),
+                );
                 None
             }
         }
@@ -1323,6 +1384,10 @@ pub fn build_session(
         None
     };
 
+    // Warnings about the options are emitted only now, by the handler of the
+    // session, so that they are part of its output when it is a single log.
+    span_diagnostic.emit_buffered_diagnostics(mem::take(&mut sopts.early_diagnostics));
+
     let mut parse_sess = ParseSess::with_span_handler(span_diagnostic, source_map);
     parse_sess.assume_incomplete_release = sopts.debugging_opts.assume_incomplete_release;
     let sysroot = match &sopts.maybe_sysroot {
@@ -1357,7 +1422,9 @@ pub fn build_session(
     });
     let working_dir = file_path_mapping.map_prefix(working_dir);
 
-    let cgu_reuse_tracker = if sopts.debugging_opts.query_dep_graph {
+    let cgu_reuse_tracker = if sopts.debugging_opts.query_dep_graph
+        || sopts.debugging_opts.incremental_explain.is_some()
+    {
         CguReuseTracker::new()
     } else {
         CguReuseTracker::new_disabled()
@@ -1556,9 +1623,11 @@ pub fn early_error(output: config::ErrorOutputType, msg: &str) -> ! {
         config::ErrorOutputType::Json { pretty, json_rendered } => {
             Box::new(JsonEmitter::basic(pretty, json_rendered, None, false))
         }
+        config::ErrorOutputType::Sarif => Box::new(SarifEmitter::basic(false)),
     };
     let handler = rustc_errors::Handler::with_emitter(true, None, emitter);
     handler.struct_fatal(msg).emit();
+    handler.finish_emitter();
     rustc_errors::FatalError.raise();
 }
 
@@ -1571,7 +1640,27 @@ pub fn early_warn(output: config::ErrorOutputType, msg: &str) {
         config::ErrorOutputType::Json { pretty, json_rendered } => {
             Box::new(JsonEmitter::basic(pretty, json_rendered, None, false))
         }
+        // There is no session to write the log, so write one just for this.
+        config::ErrorOutputType::Sarif => Box::new(SarifEmitter::basic(false)),
     };
     let handler = rustc_errors::Handler::with_emitter(true, None, emitter);
     handler.struct_warn(msg).emit();
+    handler.finish_emitter();
+}
+
+/// Like `early_warn`, but for a warning about the options of a session that is
+/// yet to be created. SARIF output is a single log for the whole session, so
+/// for it the warning is added to `early_diagnostics` instead, for the handler
+/// of the session to emit (see `Options::early_diagnostics`).
+pub fn early_warn_for_session(
+    output: config::ErrorOutputType,
+    early_diagnostics: &mut Vec<Diagnostic>,
+    msg: &str,
+) {
+    match output {
+        config::ErrorOutputType::Sarif => {
+            early_diagnostics.push(Diagnostic::new(rustc_errors::Level::Warning, msg))
+        }
+        _ => early_warn(output, msg),
+    }
 }
@@ -376,7 +376,9 @@
         const_allocate,
         const_compare_raw_pointers,
         const_constructor,
+        const_deallocate,
         const_eval_limit,
+        const_eval_select,
         const_evaluatable_checked,
         const_extern_fn,
         const_fn,
@@ -7,6 +7,7 @@
 use rustc_hir::def::{Namespace, Res};
 use rustc_hir::def_id::{DefId, LOCAL_CRATE};
 use rustc_infer::infer::type_variable::{TypeVariableOrigin, TypeVariableOriginKind};
+use rustc_infer::infer::InferOk;
 use rustc_infer::{infer, traits};
 use rustc_middle::ty::adjustment::{
     Adjust, Adjustment, AllowTwoPhase, AutoBorrow, AutoBorrowMutability,
@@ -449,9 +450,98 @@ fn confirm_builtin_call(
             def_id,
         );
 
+        if let Some(def_id) = def_id {
+            if fn_sig.abi == abi::Abi::RustIntrinsic
+                && self.tcx.item_name(def_id) == sym::const_eval_select
+            {
+                self.check_const_eval_select(arg_exprs, fn_sig);
+            }
+        }
+
         fn_sig.output()
     }
 
+    /// Calls to `const_eval_select` are replaced with calls to one of its function arguments
+    /// once its MIR is built, which requires them to be function items that take the elements
+    /// of the tuple argument and return the type of the call.
+    fn check_const_eval_select(&self, arg_exprs: &'tcx [hir::Expr<'tcx>], fn_sig: ty::FnSig<'tcx>) {
+        let tcx = self.tcx;
+        let ret_ty = fn_sig.output();
+        let arg_ty = self.structurally_resolved_type(arg_exprs[0].span, fn_sig.inputs()[0]);
+        match arg_ty.kind() {
+            ty::Tuple(_) => {}
+            ty::Error(_) => return,
+            _ => {
+                tcx.sess
+                    .struct_span_err(arg_exprs[0].span, "this argument must be a tuple")
+                    .note(&format!(
+                        "expected a tuple of the arguments of the selected function, found `{}`",
+                        arg_ty
+                    ))
+                    .emit();
+                return;
+            }
+        }
+
+        for (arg_expr, &fn_ty) in iter::zip(&arg_exprs[1..], &fn_sig.inputs()[1..]) {
+            let fn_ty = self.resolve_vars_with_obligations(fn_ty);
+            match fn_ty.kind() {
+                ty::FnDef(..) => {}
+                ty::Error(_) => continue,
+                _ => {
+                    tcx.sess
+                        .struct_span_err(arg_expr.span, "this argument must be a function item")
+                        .note(&format!("expected a function item, found `{}`", fn_ty))
+                        .help(
+                            "`const_eval_select` calls the selected function directly, so \
+                             closures and function pointers cannot be used",
+                        )
+                        .emit();
+                    continue;
+                }
+            }
+
+            let span = arg_expr.span;
+            let poly_sig = fn_ty.fn_sig(tcx);
+            let sig = self.replace_bound_vars_with_fresh_vars(span, infer::FnCall, poly_sig).0;
+            let sig = self.normalize_associated_types_in(span, sig);
+            let cause = self.misc(span);
+            let result = self.commit_if_ok(|_| {
+                let inputs = tcx.mk_tup(sig.inputs().iter());
+                let inputs = self.at(&cause, self.param_env).eq(arg_ty, inputs)?;
+                let output = self.at(&cause, self.param_env).eq(ret_ty, sig.output())?;
+                let obligations =
+                    inputs.obligations.into_iter().chain(output.obligations).collect();
+                Ok(InferOk { value: (), obligations })
+            });
+            match result {
+                Ok(ok) => self.register_infer_ok_obligations(ok),
+                Err(_) => {
+                    let expected = tcx.mk_fn_ptr(ty::Binder::dummy(tcx.mk_fn_sig(
+                        arg_ty.tuple_fields(),
+                        ret_ty,
+                        false,
+                        hir::Unsafety::Normal,
+                        abi::Abi::Rust,
+                    )));
+                    let expected = self.resolve_vars_if_possible(expected);
+                    tcx.sess
+                        .struct_span_err(
+                            span,
+                            "the signature of this function does not match the call to \
+                             `const_eval_select`",
+                        )
+                        .note(&format!(
+                            "expected `{}`, found `{}`",
+                            expected,
+                            tcx.mk_fn_ptr(poly_sig)
+                        ))
+                        .emit();
+                }
+            }
+        }
+    }
+
     fn confirm_deferred_closure_call(
         &self,
         call_expr: &'tcx hir::Expr<'tcx>,
@@ -1739,10 +1739,33 @@ pub unsafe fn drop_in_place<T: ?Sized>(to_drop: *mut T) {
     #[rustc_const_unstable(feature = "const_raw_ptr_comparison", issue = "53020")]
     pub fn ptr_guaranteed_ne<T>(ptr: *const T, other: *const T) -> bool;
 
-    /// Allocate at compile time. Should not be called at runtime.
+    /// Allocate at compile time. At runtime, this returns a null pointer.
     #[rustc_const_unstable(feature = "const_heap", issue = "79597")]
     pub fn const_allocate(size: usize, align: usize) -> *mut u8;
 
+    /// Deallocate memory that was allocated with `const_allocate` at compile time.
+    /// At runtime, this does nothing.
+    ///
+    /// `size` and `align` must be the ones the memory was allocated with.
+    #[rustc_const_unstable(feature = "const_heap", issue = "79597")]
+    pub fn const_deallocate(ptr: *mut u8, size: usize, align: usize);
+
+    /// Calls `called_in_const` with the elements of the tuple `arg` as arguments when evaluated
+    /// at compile time, and `called_at_rt` otherwise. This lets a `const fn` use a different
+    /// implementation during const evaluation, e.g. to allocate with `const_allocate` instead of
+    /// the global allocator.
+    ///
+    /// `arg` must be a tuple, and both functions must be function items, not closures or
+    /// function pointers, that take its elements as arguments and return `RET`. The type checker
+    /// rejects any other call.
+    ///
+    /// # Safety
+    ///
+    /// The two functions must behave the same as far as their callers can observe. Code calling
+    /// a `const fn` must not be able to tell whether the call was evaluated at compile time.
+    #[rustc_const_unstable(feature = "const_eval_select", issue = "none")]
+    pub fn const_eval_select<ARG, F, G, RET>(arg: ARG, called_in_const: F, called_at_rt: G) -> RET;
+
     /// Copies `count * size_of::<T>()` bytes from `src` to `dst`. The source
     /// and destination must *not* overlap.
     ///
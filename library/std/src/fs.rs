@@ -195,6 +195,51 @@ pub struct DirBuilder {
     recursive: bool,
 }
 
+/// A handle to an open directory, relative to which files and directories can
+/// be opened, created, renamed, removed and inspected.
+///
+/// Unlike a path, a `Dir` keeps referring to the same directory when it, or
+/// one of its ancestors, is renamed or replaced by another process. Walking a
+/// directory tree with [`Dir::open_dir`] therefore can't be redirected
+/// elsewhere by swapping a directory for a symlink halfway through.
+///
+/// # Platform-specific behavior
+///
+/// On Unix, this wraps a directory file descriptor and uses the `openat`,
+/// `mkdirat`, `unlinkat`, `renameat` and `fstatat` functions. On Linux 5.6 and
+/// later, all methods additionally refuse to resolve a path to anything
+/// outside of the directory, whether through `..`, an absolute path or a
+/// symlink, returning an error instead. Elsewhere they resolve paths normally,
+/// so there is no such guarantee.
+///
+/// On other platforms, a `Dir` is emulated by joining paths onto the path it
+/// was opened with, which gives none of the guarantees above.
+///
+/// The entries returned by [`Dir::read_dir`] have paths joined onto the path
+/// this `Dir` was opened with, whatever the directory is called by now.
+///
+/// # Examples
+///
+/// ```no_run
+/// #![feature(dir_handle)]
+/// use std::fs::Dir;
+///
+/// fn main() -> std::io::Result<()> {
+///     let dir = Dir::open("/var/spool/sync")?;
+///     for entry in dir.read_dir()? {
+///         let name = entry?.file_name();
+///         if dir.symlink_metadata(&name)?.is_file() {
+///             dir.remove_file(&name)?;
+///         }
+///     }
+///     Ok(())
+/// }
+/// ```
+#[unstable(feature = "dir_handle", issue = "none")]
+pub struct Dir {
+    inner: fs_imp::Dir,
+}
+
 /// Indicates how large a buffer to pre-allocate before reading the entire file.
 fn initial_buffer_size(file: &File) -> usize {
     // Allocate one extra byte so the buffer doesn't need to grow before the
@@ -447,6 +492,177 @@ pub fn sync_data(&self) -> io::Result<()> {
         self.inner.datasync()
     }
 
+    /// Acquires a shared advisory lock on the file, blocking until it can be
+    /// acquired.
+    ///
+    /// Any number of handles may hold a shared lock at the same time, but
+    /// none of them while another handle holds an exclusive lock. The lock is
+    /// released by [`unlock`], or when every handle to the same open file is
+    /// closed.
+    ///
+    /// The lock is advisory: it only affects other callers of the locking
+    /// methods, not reads and writes. If this handle already holds a lock, it
+    /// is unspecified whether it is converted to a shared lock or a second
+    /// lock is taken.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// This function currently corresponds to the `flock` function on Unix
+    /// with the `LOCK_SH` flag, and the `LockFileEx` function on Windows.
+    /// Note that, on Windows, locks are mandatory: other handles can't write
+    /// to the file while it is locked, and can't read it while it is locked
+    /// exclusively.
+    ///
+    /// On illumos, Solaris and VxWorks, which lack `flock`, `fcntl` record
+    /// locks covering the whole file are used instead. These belong to the
+    /// process, so they don't exclude other handles in the same process, and
+    /// closing any handle to the file releases them.
+    ///
+    /// On other platforms, this function returns an error.
+    /// Note that, this [may change in the future][changes].
+    ///
+    /// [changes]: io#platform-specific-behavior
+    /// [`unlock`]: File::unlock
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    /// use std::fs::File;
+    /// use std::io::prelude::*;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let mut f = File::open("foo.txt")?;
+    ///     f.lock_shared()?;
+    ///     let mut contents = String::new();
+    ///     f.read_to_string(&mut contents)?;
+    ///     f.unlock()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "none")]
+    pub fn lock_shared(&self) -> io::Result<()> {
+        self.inner.lock_shared()
+    }
+
+    /// Acquires an exclusive advisory lock on the file, blocking until it can
+    /// be acquired.
+    ///
+    /// No other handle may hold any lock on the file at the same time. The
+    /// lock is released by [`unlock`], or when every handle to the same open
+    /// file is closed.
+    ///
+    /// See [`lock_shared`] for how locks behave on each platform.
+    ///
+    /// [`lock_shared`]: File::lock_shared
+    /// [`unlock`]: File::unlock
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    /// use std::fs::OpenOptions;
+    /// use std::io::prelude::*;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let mut f = OpenOptions::new().append(true).create(true).open("foo.log")?;
+    ///     f.lock_exclusive()?;
+    ///     f.write_all(b"one whole line\n")?;
+    ///     f.unlock()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "none")]
+    pub fn lock_exclusive(&self) -> io::Result<()> {
+        self.inner.lock_exclusive()
+    }
+
+    /// Tries to acquire a shared advisory lock on the file without blocking.
+    ///
+    /// Returns `Ok(false)` if another handle holds an exclusive lock on the
+    /// file. Otherwise this behaves like [`lock_shared`].
+    ///
+    /// [`lock_shared`]: File::lock_shared
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    /// use std::fs::File;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let f = File::open("foo.txt")?;
+    ///     if !f.try_lock_shared()? {
+    ///         println!("foo.txt is being written to, waiting");
+    ///         f.lock_shared()?;
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "none")]
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        self.inner.try_lock_shared()
+    }
+
+    /// Tries to acquire an exclusive advisory lock on the file without
+    /// blocking.
+    ///
+    /// Returns `Ok(false)` if another handle holds any lock on the file.
+    /// Otherwise this behaves like [`lock_exclusive`].
+    ///
+    /// [`lock_exclusive`]: File::lock_exclusive
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    /// use std::fs::File;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let f = File::create("build.lock")?;
+    ///     if !f.try_lock_exclusive()? {
+    ///         println!("another build is running");
+    ///     }
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "none")]
+    pub fn try_lock_exclusive(&self) -> io::Result<bool> {
+        self.inner.try_lock_exclusive()
+    }
+
+    /// Releases any advisory lock this handle holds on the file.
+    ///
+    /// Unlocking a file that isn't locked is not an error.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// This function currently corresponds to the `flock` function on Unix
+    /// with the `LOCK_UN` flag, and the `UnlockFile` function on Windows.
+    /// Windows stacks the locks taken through the same handle, and
+    /// `UnlockFile` releases only one of them, so it is called until none is
+    /// left. Note that, this [may change in the future][changes].
+    ///
+    /// [changes]: io#platform-specific-behavior
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// #![feature(file_lock)]
+    /// use std::fs::File;
+    ///
+    /// fn main() -> std::io::Result<()> {
+    ///     let f = File::open("foo.txt")?;
+    ///     f.lock_shared()?;
+    ///     f.unlock()?;
+    ///     Ok(())
+    /// }
+    /// ```
+    #[unstable(feature = "file_lock", issue = "none")]
+    pub fn unlock(&self) -> io::Result<()> {
+        self.inner.unlock()
+    }
+
     /// Truncates or extends the underlying file, updating the size of
     /// this file to become `size`.
     ///
@@ -2208,3 +2424,136 @@ fn as_inner_mut(&mut self) -> &mut fs_imp::DirBuilder {
         &mut self.inner
     }
 }
+
+impl Dir {
+    /// Opens the directory at `path`.
+    ///
+    /// # Errors
+    ///
+    /// This function will return an error if `path` does not exist or is not
+    /// a directory, or if the user lacks permission to open it.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Dir> {
+        fs_imp::Dir::open(path.as_ref()).map(|inner| Dir { inner })
+    }
+
+    /// Opens a file relative to this directory in read-only mode.
+    ///
+    /// This is the equivalent of [`File::open`] for a `path` relative to this
+    /// directory.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn open_file<P: AsRef<Path>>(&self, path: P) -> io::Result<File> {
+        self.open_file_with(path, OpenOptions::new().read(true))
+    }
+
+    /// Opens a file relative to this directory with the options specified by
+    /// `options`.
+    ///
+    /// This is the equivalent of [`OpenOptions::open`] for a `path` relative
+    /// to this directory.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn open_file_with<P: AsRef<Path>>(
+        &self,
+        path: P,
+        options: &OpenOptions,
+    ) -> io::Result<File> {
+        self.inner.open_file(path.as_ref(), &options.0).map(|inner| File { inner })
+    }
+
+    /// Opens a directory relative to this directory.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn open_dir<P: AsRef<Path>>(&self, path: P) -> io::Result<Dir> {
+        self.inner.open_dir(path.as_ref()).map(|inner| Dir { inner })
+    }
+
+    /// Creates a new, empty directory relative to this directory.
+    ///
+    /// This is the equivalent of [`create_dir`] for a `path` relative to this
+    /// directory.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn create_dir<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.inner.create_dir(path.as_ref())
+    }
+
+    /// Removes a file relative to this directory.
+    ///
+    /// This is the equivalent of [`remove_file`] for a `path` relative to
+    /// this directory.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn remove_file<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.inner.remove_file(path.as_ref())
+    }
+
+    /// Removes an empty directory relative to this directory.
+    ///
+    /// This is the equivalent of [`remove_dir`] for a `path` relative to this
+    /// directory.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn remove_dir<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        self.inner.remove_dir(path.as_ref())
+    }
+
+    /// Renames `from`, relative to this directory, to `to`, relative to
+    /// `to_dir`, which may be this directory.
+    ///
+    /// This is the equivalent of [`rename`] for paths relative to the two
+    /// directories.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(
+        &self,
+        from: P,
+        to_dir: &Dir,
+        to: Q,
+    ) -> io::Result<()> {
+        self.inner.rename(from.as_ref(), &to_dir.inner, to.as_ref())
+    }
+
+    /// Queries metadata about a file relative to this directory, following
+    /// symbolic links.
+    ///
+    /// This is the equivalent of [`metadata`] for a `path` relative to this
+    /// directory.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn metadata<P: AsRef<Path>>(&self, path: P) -> io::Result<Metadata> {
+        self.inner.stat(path.as_ref()).map(Metadata)
+    }
+
+    /// Queries metadata about a file relative to this directory, without
+    /// following symbolic links.
+    ///
+    /// This is the equivalent of [`symlink_metadata`] for a `path` relative to
+    /// this directory.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn symlink_metadata<P: AsRef<Path>>(&self, path: P) -> io::Result<Metadata> {
+        self.inner.lstat(path.as_ref()).map(Metadata)
+    }
+
+    /// Returns an iterator over the entries within this directory.
+    ///
+    /// The paths of the returned entries are joined onto the path this `Dir`
+    /// was opened with, so they go stale if the directory is moved; use
+    /// [`DirEntry::file_name`] with the methods of this `Dir` instead.
+    #[unstable(feature = "dir_handle", issue = "none")]
+    pub fn read_dir(&self) -> io::Result<ReadDir> {
+        self.inner.read_dir().map(ReadDir)
+    }
+}
+
+#[unstable(feature = "dir_handle", issue = "none")]
+impl fmt::Debug for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl AsInner<fs_imp::Dir> for Dir {
+    fn as_inner(&self) -> &fs_imp::Dir {
+        &self.inner
+    }
+}
+
+impl IntoInner<fs_imp::Dir> for Dir {
+    fn into_inner(self) -> fs_imp::Dir {
+        self.inner
+    }
+}
@@ -1392,3 +1392,114 @@ fn symlink_hard_link() {
     // "hard_link" should still appear as a symlink.
     assert!(check!(fs::symlink_metadata(tmpdir.join("hard_link"))).file_type().is_symlink());
 }
+
+#[test]
+fn dir_relative_ops() {
+    let tmpdir = tmpdir();
+    check!(fs::create_dir(tmpdir.join("d")));
+    let dir = check!(fs::Dir::open(tmpdir.join("d")));
+
+    check!(dir.create_dir("sub"));
+    assert!(check!(dir.metadata("sub")).is_dir());
+    let mut f = check!(dir.open_file_with("file", OpenOptions::new().write(true).create(true)));
+    check!(f.write_all(b"hello"));
+    drop(f);
+
+    // Renaming the directory itself doesn't affect a descriptor-backed handle,
+    // while the path-joining emulation keeps using the stale path.
+    let fd_backed =
+        cfg!(all(unix, not(any(target_os = "vxworks", target_os = "redox", target_os = "l4re"))));
+    check!(fs::rename(tmpdir.join("d"), tmpdir.join("d.moved")));
+    if !fd_backed {
+        assert!(dir.open_dir("sub").is_err());
+        check!(fs::rename(tmpdir.join("d.moved"), tmpdir.join("d")));
+    }
+
+    let sub = check!(dir.open_dir("sub"));
+    check!(dir.rename("file", &sub, "file.renamed"));
+    assert!(dir.symlink_metadata("file").is_err());
+    let mut s = String::new();
+    check!(check!(sub.open_file("file.renamed")).read_to_string(&mut s));
+    assert_eq!(s, "hello");
+    assert_eq!(check!(sub.metadata("file.renamed")).len(), 5);
+
+    let names: Vec<_> = check!(sub.read_dir()).map(|e| check!(e).file_name()).collect();
+    assert_eq!(names, ["file.renamed"]);
+
+    check!(sub.remove_file("file.renamed"));
+    check!(dir.remove_dir("sub"));
+    let path = if fd_backed { tmpdir.join("d.moved") } else { tmpdir.join("d") };
+    assert!(check!(fs::read_dir(path)).next().is_none());
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn dir_relative_ops_stay_beneath() {
+    use crate::os::unix::fs::symlink;
+
+    let tmpdir = tmpdir();
+    check!(fs::create_dir_all(tmpdir.join("d/sub")));
+    check!(fs::create_dir(tmpdir.join("outside")));
+    check!(symlink("../outside", tmpdir.join("d/escape")));
+    let dir = check!(fs::Dir::open(tmpdir.join("d")));
+
+    // Kernels before 5.6 can't confine paths to the directory.
+    if dir.open_dir("..").is_ok() {
+        return;
+    }
+
+    assert!(dir.create_dir("escape/new").is_err());
+    assert!(dir.create_dir("../outside/new").is_err());
+    assert!(dir.metadata("escape").is_err());
+    assert!(dir.symlink_metadata("escape/.").is_err());
+    assert!(dir.rename("sub", &dir, "escape/sub").is_err());
+    assert!(!tmpdir.join("outside/new").exists());
+    assert!(!tmpdir.join("outside/sub").exists());
+
+    // Paths that stay inside still work, and the symlink itself can be removed.
+    check!(dir.create_dir("sub/new"));
+    assert!(check!(dir.metadata("sub/../sub/new")).is_dir());
+    check!(dir.remove_dir("sub/new/"));
+    assert!(check!(dir.symlink_metadata("escape")).file_type().is_symlink());
+    check!(dir.remove_file("escape"));
+}
+
+// `fcntl` record locks, used where `flock` is missing, don't conflict between
+// descriptors in the same process.
+#[test]
+#[cfg(any(unix, windows))]
+#[cfg_attr(any(target_os = "solaris", target_os = "illumos", target_os = "vxworks"), ignore)]
+fn file_lock_contention() {
+    let tmpdir = tmpdir();
+    let path = tmpdir.join("lock");
+    let a = check!(File::create(&path));
+    let b = check!(File::open(&path));
+
+    // Shared locks coexist, but exclude an exclusive lock.
+    check!(a.lock_shared());
+    assert!(check!(b.try_lock_shared()));
+    check!(b.unlock());
+    assert!(!check!(b.try_lock_exclusive()));
+
+    // An exclusive lock excludes any other lock.
+    check!(a.unlock());
+    check!(a.lock_exclusive());
+    assert!(!check!(b.try_lock_shared()));
+    assert!(!check!(b.try_lock_exclusive()));
+
+    // A blocked lock is granted once the holder unlocks.
+    let (tx, rx) = crate::sync::mpsc::channel();
+    let t = thread::spawn(move || {
+        check!(b.lock_exclusive());
+        tx.send(()).unwrap();
+        check!(b.unlock());
+    });
+    thread::sleep(crate::time::Duration::from_millis(10));
+    assert!(rx.try_recv().is_err());
+    check!(a.unlock());
+    rx.recv().unwrap();
+    t.join().unwrap();
+
+    // Unlocking an unlocked file is not an error.
+    check!(a.unlock());
+}
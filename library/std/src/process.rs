@@ -117,6 +117,7 @@
 #[unstable(feature = "command_access", issue = "44434")]
 pub use crate::sys_common::process::CommandEnvs;
 use crate::sys_common::{AsInner, AsInnerMut, FromInner, IntoInner};
+use crate::time::Duration;
 
 /// Representation of a running or exited child process.
 ///
@@ -204,12 +205,20 @@ pub struct Child {
     pub stderr: Option<ChildStderr>,
 }
 
+impl crate::sealed::Sealed for Child {}
+
 impl AsInner<imp::Process> for Child {
     fn as_inner(&self) -> &imp::Process {
         &self.handle
     }
 }
 
+impl AsInnerMut<imp::Process> for Child {
+    fn as_inner_mut(&mut self) -> &mut imp::Process {
+        &mut self.handle
+    }
+}
+
 impl FromInner<(imp::Process, imp::StdioPipes)> for Child {
     fn from_inner((handle, io): (imp::Process, imp::StdioPipes)) -> Child {
         Child {
@@ -1638,6 +1647,53 @@ pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
         Ok(self.handle.try_wait()?.map(ExitStatus))
     }
 
+    /// Waits for the child to exit for at most `timeout`, collecting its exit
+    /// status if it did.
+    ///
+    /// If the child exits before the timeout elapses then `Ok(Some(status))`
+    /// is returned, and on Unix the process ID is reaped. If the timeout
+    /// elapses first then `Ok(None)` is returned and the child keeps running.
+    /// If an error occurs, then that error is returned.
+    ///
+    /// The calling thread blocks until the child exits or the timeout elapses.
+    ///
+    /// Note that unlike `wait`, this function will not attempt to drop stdin.
+    ///
+    /// # Platform-specific behavior
+    ///
+    /// This waits on the child directly on Windows, Fuchsia, Linux 5.3 and
+    /// later (with a pidfd), and macOS, iOS, FreeBSD, DragonFly BSD, NetBSD
+    /// and OpenBSD (with a kqueue). Elsewhere, including older Linux kernels,
+    /// Android, Solaris, illumos and VxWorks, it falls back to checking
+    /// whether the child exited at increasing intervals of up to 50
+    /// milliseconds, so it may return that much later than the child exits.
+    /// This may change in the future.
+    ///
+    /// # Examples
+    ///
+    /// Basic usage:
+    ///
+    /// ```no_run
+    /// #![feature(child_wait_timeout)]
+    /// use std::process::Command;
+    /// use std::time::Duration;
+    ///
+    /// let mut child = Command::new("sleep").arg("10").spawn().unwrap();
+    ///
+    /// match child.wait_timeout(Duration::from_secs(1)) {
+    ///     Ok(Some(status)) => println!("exited with: {}", status),
+    ///     Ok(None) => {
+    ///         println!("timed out, killing the child");
+    ///         child.kill().unwrap();
+    ///     }
+    ///     Err(e) => println!("error attempting to wait: {}", e),
+    /// }
+    /// ```
+    #[unstable(feature = "child_wait_timeout", issue = "none")]
+    pub fn wait_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        Ok(self.handle.wait_timeout(timeout)?.map(ExitStatus))
+    }
+
     /// Simultaneously waits for the child to exit and collect all remaining
     /// output on the stdout/stderr handles, returning an `Output`
     /// instance.
@@ -186,6 +186,26 @@ fn test_finish_twice() {
     assert!(prog.wait().unwrap().code() == Some(1));
 }
 
+#[test]
+#[cfg_attr(any(target_os = "vxworks", target_os = "android"), ignore)]
+fn test_wait_timeout() {
+    use crate::time::Duration;
+
+    // Both of these run until their stdin is closed.
+    let mut prog = if cfg!(target_os = "windows") {
+        Command::new("findstr").arg("x").stdin(Stdio::piped()).spawn().unwrap()
+    } else {
+        Command::new("cat").stdin(Stdio::piped()).spawn().unwrap()
+    };
+    assert!(prog.wait_timeout(Duration::from_millis(100)).unwrap().is_none());
+    assert!(prog.try_wait().unwrap().is_none());
+
+    drop(prog.stdin.take());
+    let status = prog.wait_timeout(Duration::from_secs(60)).unwrap().unwrap();
+    assert_eq!(prog.wait_timeout(Duration::ZERO).unwrap(), Some(status));
+    assert_eq!(prog.wait().unwrap(), status);
+}
+
 #[test]
 #[cfg_attr(any(target_os = "vxworks", target_os = "android"), ignore)]
 fn test_wait_with_output_once() {
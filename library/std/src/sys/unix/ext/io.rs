@@ -140,6 +140,21 @@ fn into_raw_fd(self) -> RawFd {
     }
 }
 
+#[cfg(not(any(target_os = "vxworks", target_os = "redox", target_os = "l4re")))]
+#[unstable(feature = "dir_handle", issue = "none")]
+impl AsRawFd for fs::Dir {
+    fn as_raw_fd(&self) -> RawFd {
+        self.as_inner().fd().raw()
+    }
+}
+#[cfg(not(any(target_os = "vxworks", target_os = "redox", target_os = "l4re")))]
+#[unstable(feature = "dir_handle", issue = "none")]
+impl IntoRawFd for fs::Dir {
+    fn into_raw_fd(self) -> RawFd {
+        self.into_inner().into_fd().into_raw()
+    }
+}
+
 #[stable(feature = "asraw_stdio", since = "1.21.0")]
 impl AsRawFd for io::Stdin {
     fn as_raw_fd(&self) -> RawFd {
@@ -141,6 +141,76 @@ fn before_exec<F>(&mut self, f: F) -> &mut process::Command
     fn arg0<S>(&mut self, arg: S) -> &mut process::Command
     where
         S: AsRef<OsStr>;
+
+    /// Sets the process group ID of the child process. This translates to a
+    /// `setpgid` call in the child process, or to `POSIX_SPAWN_SETPGROUP`
+    /// when the child is created with `posix_spawn`.
+    ///
+    /// A `pgroup` of 0 puts the child in a new process group whose ID is the
+    /// child's PID. Otherwise the child joins the existing process group
+    /// `pgroup`, which must belong to the same session. Failure to change the
+    /// process group will cause the spawn to fail.
+    #[unstable(feature = "process_group", issue = "none")]
+    fn process_group(&mut self, pgroup: i32) -> &mut process::Command;
+
+    /// Sets whether the child process should start a new session. This
+    /// translates to a `setsid` call in the child process, or to
+    /// `POSIX_SPAWN_SETSID` when the child is created with `posix_spawn`.
+    ///
+    /// The child becomes the leader of both a new session and a new process
+    /// group, detached from the controlling terminal. As a session leader
+    /// can't change its process group, this can't be combined with
+    /// [`process_group`], and doing so will cause the spawn to fail.
+    ///
+    /// [`process_group`]: CommandExt::process_group
+    #[unstable(feature = "process_setsid", issue = "none")]
+    fn setsid(&mut self, setsid: bool) -> &mut process::Command;
+
+    /// Sets the signal that the child process receives when its parent
+    /// exits. This translates to a `prctl(PR_SET_PDEATHSIG)` call in the
+    /// child process, made after any [`uid`] and [`gid`] changes since those
+    /// clear it.
+    ///
+    /// If the parent has already exited by the time the child sets this up,
+    /// the child sends the signal to itself instead.
+    ///
+    /// # Notes
+    ///
+    /// The "parent" here is the *thread* that spawned the child: the signal
+    /// is sent when that thread exits, even if the rest of the process keeps
+    /// running. The signal is also cleared when the child executes a
+    /// set-user-ID or set-group-ID program.
+    ///
+    /// [`uid`]: CommandExt::uid
+    /// [`gid`]: CommandExt::gid
+    #[cfg(target_os = "linux")]
+    #[unstable(feature = "process_parent_death_signal", issue = "none")]
+    fn parent_death_signal(&mut self, signal: i32) -> &mut process::Command;
+
+    /// Sets whether a [pidfd](https://man7.org/linux/man-pages/man2/pidfd_open.2.html)
+    /// should be created for the child process when it is spawned.
+    ///
+    /// The pidfd is created atomically with the child using `clone3` when the
+    /// kernel supports it (Linux 5.3 and later), and with `pidfd_open` right
+    /// after the fork otherwise. While the pidfd is owned by the [`Child`], it
+    /// is used for [`Child::kill`], [`Child::wait`] and [`Child::try_wait`],
+    /// so these can never act on an unrelated process that reused the PID.
+    /// If the child was reaped by other means, such as a `waitpid` call that
+    /// doesn't go through the [`Child`], [`Child::kill`] then returns an
+    /// [`InvalidInput`] error, as it does after a successful [`Child::wait`].
+    ///
+    /// Creating a pidfd is best effort: if the kernel supports neither
+    /// syscall, the child is still spawned and [`ChildExt::pidfd`] returns an
+    /// error.
+    ///
+    /// [`Child`]: process::Child
+    /// [`Child::kill`]: process::Child::kill
+    /// [`Child::wait`]: process::Child::wait
+    /// [`Child::try_wait`]: process::Child::try_wait
+    /// [`InvalidInput`]: io::ErrorKind::InvalidInput
+    #[cfg(target_os = "linux")]
+    #[unstable(feature = "linux_pidfd", issue = "82971")]
+    fn create_pidfd(&mut self, val: bool) -> &mut process::Command;
 }
 
 #[stable(feature = "rust1", since = "1.0.0")]
@@ -193,6 +263,111 @@ fn arg0<S>(&mut self, arg: S) -> &mut process::Command
         self.as_inner_mut().set_arg_0(arg.as_ref());
         self
     }
+
+    fn process_group(&mut self, pgroup: i32) -> &mut process::Command {
+        self.as_inner_mut().pgroup(pgroup);
+        self
+    }
+
+    fn setsid(&mut self, setsid: bool) -> &mut process::Command {
+        self.as_inner_mut().setsid(setsid);
+        self
+    }
+
+    #[cfg(target_os = "linux")]
+    fn parent_death_signal(&mut self, signal: i32) -> &mut process::Command {
+        self.as_inner_mut().pdeathsig(signal);
+        self
+    }
+
+    #[cfg(target_os = "linux")]
+    fn create_pidfd(&mut self, val: bool) -> &mut process::Command {
+        self.as_inner_mut().create_pidfd(val);
+        self
+    }
+}
+
+/// A file descriptor referring to a child process, created by spawning a
+/// [`process::Command`] with [`CommandExt::create_pidfd`].
+///
+/// The file descriptor becomes readable once the process has exited, so it
+/// can be registered with `poll`, `epoll` and similar to be notified of the
+/// exit. It is closed when the `PidFd` is dropped.
+#[cfg(target_os = "linux")]
+#[unstable(feature = "linux_pidfd", issue = "82971")]
+#[derive(Debug)]
+pub struct PidFd {
+    inner: sys::fd::FileDesc,
+}
+
+#[cfg(target_os = "linux")]
+impl FromInner<sys::fd::FileDesc> for PidFd {
+    fn from_inner(inner: sys::fd::FileDesc) -> PidFd {
+        PidFd { inner }
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[unstable(feature = "linux_pidfd", issue = "82971")]
+impl AsRawFd for PidFd {
+    fn as_raw_fd(&self) -> RawFd {
+        self.inner.raw()
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[unstable(feature = "linux_pidfd", issue = "82971")]
+impl FromRawFd for PidFd {
+    unsafe fn from_raw_fd(fd: RawFd) -> PidFd {
+        PidFd::from_inner(sys::fd::FileDesc::new(fd))
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[unstable(feature = "linux_pidfd", issue = "82971")]
+impl IntoRawFd for PidFd {
+    fn into_raw_fd(self) -> RawFd {
+        self.inner.into_raw()
+    }
+}
+
+/// Linux-specific extensions to [`process::Child`].
+///
+/// This trait is sealed: it cannot be implemented outside the standard library.
+/// This is so that future additional methods are not breaking changes.
+#[cfg(target_os = "linux")]
+#[unstable(feature = "linux_pidfd", issue = "82971")]
+pub trait ChildExt: Sealed {
+    /// Obtains a reference to the [`PidFd`] created for this child.
+    ///
+    /// An error is returned if the child was not spawned with
+    /// [`CommandExt::create_pidfd`], if no pidfd could be created, or if it
+    /// was already taken with [`ChildExt::take_pidfd`].
+    fn pidfd(&self) -> io::Result<&PidFd>;
+
+    /// Takes ownership of the [`PidFd`] created for this child.
+    ///
+    /// Once the pidfd is taken, the [`process::Child`] goes back to tracking
+    /// the process by its PID alone.
+    ///
+    /// An error is returned under the same conditions as [`ChildExt::pidfd`].
+    fn take_pidfd(&mut self) -> io::Result<PidFd>;
+}
+
+#[cfg(target_os = "linux")]
+#[unstable(feature = "linux_pidfd", issue = "82971")]
+impl ChildExt for process::Child {
+    fn pidfd(&self) -> io::Result<&PidFd> {
+        self.as_inner()
+            .pidfd()
+            .ok_or_else(|| io::Error::new_const(io::ErrorKind::Other, &"no pidfd was created"))
+    }
+
+    fn take_pidfd(&mut self) -> io::Result<PidFd> {
+        self.as_inner_mut()
+            .take_pidfd()
+            .ok_or_else(|| io::Error::new_const(io::ErrorKind::Other, &"no pidfd was created"))
+    }
 }
 
 /// Unix-specific extensions to [`process::ExitStatus`].
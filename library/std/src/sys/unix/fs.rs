@@ -16,6 +16,15 @@
 
 #[cfg(any(target_os = "linux", target_os = "emscripten", target_os = "android"))]
 use libc::dirfd;
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "emscripten",
+    target_os = "android",
+    target_os = "vxworks",
+    target_os = "redox",
+    target_os = "l4re"
+)))]
+use libc::fstatat as fstatat64;
 #[cfg(any(target_os = "linux", target_os = "emscripten"))]
 use libc::fstatat64;
 #[cfg(not(any(
@@ -50,8 +59,18 @@
 
 pub use crate::sys_common::fs::remove_dir_all;
 
+// These lack some of the `*at` functions, so paths are used instead.
+#[cfg(any(target_os = "vxworks", target_os = "redox", target_os = "l4re"))]
+pub use crate::sys_common::fs::Dir;
+
 pub struct File(FileDesc);
 
+enum LockKind {
+    Shared,
+    Exclusive,
+    Unlock,
+}
+
 // FIXME: This should be available on Linux with all `target_env`.
 // But currently only glibc exposes `statx` fn and structs.
 // We don't want to import unverified raw C structs here directly.
@@ -179,7 +198,7 @@ pub struct FileAttr {
 
 // all DirEntry's will have a reference to this struct
 struct InnerReadDir {
-    dirp: Dir,
+    dirp: DirStream,
     root: PathBuf,
 }
 
@@ -194,10 +213,10 @@ pub struct ReadDir {
     end_of_stream: bool,
 }
 
-struct Dir(*mut libc::DIR);
+struct DirStream(*mut libc::DIR);
 
-unsafe impl Send for Dir {}
-unsafe impl Sync for Dir {}
+unsafe impl Send for DirStream {}
+unsafe impl Sync for DirStream {}
 
 pub struct DirEntry {
     entry: dirent64,
@@ -421,6 +440,22 @@ fn from_inner(mode: u32) -> FilePermissions {
     }
 }
 
+impl ReadDir {
+    fn new(dirp: *mut libc::DIR, root: PathBuf) -> ReadDir {
+        let inner = InnerReadDir { dirp: DirStream(dirp), root };
+        ReadDir {
+            inner: Arc::new(inner),
+            #[cfg(not(any(
+                target_os = "solaris",
+                target_os = "illumos",
+                target_os = "fuchsia",
+                target_os = "redox",
+            )))]
+            end_of_stream: false,
+        }
+    }
+}
+
 impl fmt::Debug for ReadDir {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         // This will only be called from std::fs::ReadDir, which will add a "ReadDir()" frame.
@@ -511,7 +546,7 @@ fn next(&mut self) -> Option<io::Result<DirEntry>> {
     }
 }
 
-impl Drop for Dir {
+impl Drop for DirStream {
     fn drop(&mut self) {
         let r = unsafe { libc::closedir(self.0) };
         debug_assert_eq!(r, 0);
@@ -811,6 +846,67 @@ unsafe fn os_datasync(fd: c_int) -> c_int {
         }
     }
 
+    pub fn lock_shared(&self) -> io::Result<()> {
+        self.lock(LockKind::Shared, true).map(drop)
+    }
+
+    pub fn lock_exclusive(&self) -> io::Result<()> {
+        self.lock(LockKind::Exclusive, true).map(drop)
+    }
+
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        self.lock(LockKind::Shared, false)
+    }
+
+    pub fn try_lock_exclusive(&self) -> io::Result<bool> {
+        self.lock(LockKind::Exclusive, false)
+    }
+
+    pub fn unlock(&self) -> io::Result<()> {
+        self.lock(LockKind::Unlock, false).map(drop)
+    }
+
+    // Returns `Ok(false)` if `block` is false and the lock is held elsewhere.
+    #[cfg(not(any(target_os = "solaris", target_os = "illumos", target_os = "vxworks")))]
+    fn lock(&self, kind: LockKind, block: bool) -> io::Result<bool> {
+        let mut operation = match kind {
+            LockKind::Shared => libc::LOCK_SH,
+            LockKind::Exclusive => libc::LOCK_EX,
+            LockKind::Unlock => libc::LOCK_UN,
+        };
+        if !block {
+            operation |= libc::LOCK_NB;
+        }
+        match cvt_r(|| unsafe { libc::flock(self.0.raw(), operation) }) {
+            Ok(_) => Ok(true),
+            Err(e) if e.raw_os_error() == Some(libc::EWOULDBLOCK) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    // These lack `flock`, so POSIX record locks covering the whole file are
+    // used instead. Those belong to the process rather than the open file
+    // description, so they don't conflict with other descriptors in the same
+    // process, and closing any descriptor for the file releases them.
+    #[cfg(any(target_os = "solaris", target_os = "illumos", target_os = "vxworks"))]
+    fn lock(&self, kind: LockKind, block: bool) -> io::Result<bool> {
+        let mut flock: libc::flock = unsafe { mem::zeroed() };
+        flock.l_type = match kind {
+            LockKind::Shared => libc::F_RDLCK,
+            LockKind::Exclusive => libc::F_WRLCK,
+            LockKind::Unlock => libc::F_UNLCK,
+        } as _;
+        flock.l_whence = libc::SEEK_SET as _;
+        let cmd = if block { libc::F_SETLKW } else { libc::F_SETLK };
+        match cvt_r(|| unsafe { libc::fcntl(self.0.raw(), cmd, &flock) }) {
+            Ok(_) => Ok(true),
+            Err(e) if matches!(e.raw_os_error(), Some(libc::EAGAIN) | Some(libc::EACCES)) => {
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
     pub fn truncate(&self, size: u64) -> io::Result<()> {
         #[cfg(target_os = "android")]
         return crate::sys::android::ftruncate64(self.0.raw(), size);
@@ -908,6 +1004,246 @@ pub fn set_mode(&mut self, mode: u32) {
     }
 }
 
+/// An open directory, relative to which paths are resolved with the `*at`
+/// family of functions. Unlike a path, it keeps referring to the same
+/// directory when it or one of its ancestors is renamed or replaced.
+#[cfg(not(any(target_os = "vxworks", target_os = "redox", target_os = "l4re")))]
+pub struct Dir {
+    fd: FileDesc,
+    // The path the directory was opened with, for `DirEntry::path`.
+    root: PathBuf,
+}
+
+#[cfg(not(any(target_os = "vxworks", target_os = "redox", target_os = "l4re")))]
+impl Dir {
+    pub fn open(path: &Path) -> io::Result<Dir> {
+        let p = cstr(path)?;
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+        let fd = cvt_r(|| unsafe { open64(p.as_ptr(), flags) })?;
+        Ok(Dir { fd: FileDesc::new(fd), root: path.to_path_buf() })
+    }
+
+    pub fn open_file(&self, path: &Path, opts: &OpenOptions) -> io::Result<File> {
+        let flags = libc::O_CLOEXEC
+            | opts.get_access_mode()?
+            | opts.get_creation_mode()?
+            | (opts.custom_flags as c_int & !libc::O_ACCMODE);
+        self.openat(path, flags, opts.mode).map(File)
+    }
+
+    pub fn open_dir(&self, path: &Path) -> io::Result<Dir> {
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+        let fd = self.openat(path, flags, 0)?;
+        Ok(Dir { fd, root: self.root.join(path) })
+    }
+
+    pub fn create_dir(&self, path: &Path) -> io::Result<()> {
+        let (parent, name) = self.parent_and_name(path)?;
+        let fd = parent.as_ref().unwrap_or(&self.fd).raw();
+        cvt(unsafe { libc::mkdirat(fd, name.as_ptr(), 0o777) })?;
+        Ok(())
+    }
+
+    pub fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let (parent, name) = self.parent_and_name(path)?;
+        let fd = parent.as_ref().unwrap_or(&self.fd).raw();
+        cvt(unsafe { libc::unlinkat(fd, name.as_ptr(), 0) })?;
+        Ok(())
+    }
+
+    pub fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        let (parent, name) = self.parent_and_name(path)?;
+        let fd = parent.as_ref().unwrap_or(&self.fd).raw();
+        cvt(unsafe { libc::unlinkat(fd, name.as_ptr(), libc::AT_REMOVEDIR) })?;
+        Ok(())
+    }
+
+    pub fn rename(&self, from: &Path, to_dir: &Dir, to: &Path) -> io::Result<()> {
+        let (from_parent, from) = self.parent_and_name(from)?;
+        let (to_parent, to) = to_dir.parent_and_name(to)?;
+        let from_fd = from_parent.as_ref().unwrap_or(&self.fd).raw();
+        let to_fd = to_parent.as_ref().unwrap_or(&to_dir.fd).raw();
+        cvt(unsafe { libc::renameat(from_fd, from.as_ptr(), to_fd, to.as_ptr()) })?;
+        Ok(())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn stat(&self, path: &Path) -> io::Result<FileAttr> {
+        statat(self.fd.raw(), &cstr(path)?, 0)
+    }
+
+    // Following a symlink in the last component could leave the directory, so
+    // open the file itself beneath it instead.
+    #[cfg(target_os = "linux")]
+    pub fn stat(&self, path: &Path) -> io::Result<FileAttr> {
+        let fd = self.openat(path, libc::O_PATH | libc::O_CLOEXEC, 0)?;
+        statat(fd.raw(), CStr::from_bytes_with_nul(b"\0").unwrap(), libc::AT_EMPTY_PATH)
+    }
+
+    pub fn lstat(&self, path: &Path) -> io::Result<FileAttr> {
+        let (parent, name) = self.parent_and_name(path)?;
+        let fd = parent.as_ref().unwrap_or(&self.fd).raw();
+        statat(fd, &name, libc::AT_SYMLINK_NOFOLLOW)
+    }
+
+    pub fn read_dir(&self) -> io::Result<ReadDir> {
+        // Open the directory again rather than duplicating the descriptor, as
+        // a duplicate would share its position in the directory stream.
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+        let dot = b".\0".as_ptr() as *const libc::c_char;
+        let fd = FileDesc::new(cvt_r(|| unsafe { libc::openat(self.fd.raw(), dot, flags) })?);
+        let ptr = unsafe { libc::fdopendir(fd.raw()) };
+        if ptr.is_null() {
+            return Err(Error::last_os_error());
+        }
+        // The directory stream now owns the descriptor.
+        fd.into_raw();
+        Ok(ReadDir::new(ptr, self.root.clone()))
+    }
+
+    pub fn fd(&self) -> &FileDesc {
+        &self.fd
+    }
+
+    pub fn into_fd(self) -> FileDesc {
+        self.fd
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn openat(&self, path: &Path, flags: c_int, mode: mode_t) -> io::Result<FileDesc> {
+        let path = cstr(path)?;
+        // See `File::open_c` for why the mode is passed as a `c_int`.
+        let fd =
+            cvt_r(|| unsafe { libc::openat(self.fd.raw(), path.as_ptr(), flags, mode as c_int) })?;
+        Ok(FileDesc::new(fd))
+    }
+
+    // On Linux 5.6 and later, `openat2` with `RESOLVE_BENEATH` refuses to
+    // resolve `path` to anything outside of this directory, whether through
+    // `..`, an absolute path or a symlink, and fails with `EXDEV` instead.
+    #[cfg(target_os = "linux")]
+    fn openat(&self, path: &Path, flags: c_int, mode: mode_t) -> io::Result<FileDesc> {
+        use crate::sync::atomic::{AtomicBool, Ordering};
+
+        static HAS_OPENAT2: AtomicBool = AtomicBool::new(true);
+        const RESOLVE_BENEATH: u64 = 0x08;
+
+        #[repr(C)]
+        struct OpenHow {
+            flags: u64,
+            mode: u64,
+            resolve: u64,
+        }
+
+        let path = cstr(path)?;
+        if HAS_OPENAT2.load(Ordering::Relaxed) {
+            let how = OpenHow {
+                flags: flags as u64,
+                // `openat2` rejects a mode unless a file may be created.
+                // `O_TMPFILE` includes the `O_DIRECTORY` bits, so all of them must be set.
+                mode: if flags & libc::O_CREAT != 0 || flags & libc::O_TMPFILE == libc::O_TMPFILE {
+                    mode as u64
+                } else {
+                    0
+                },
+                resolve: RESOLVE_BENEATH,
+            };
+            loop {
+                let res = cvt_r(|| unsafe {
+                    libc::syscall(
+                        libc::SYS_openat2,
+                        self.fd.raw(),
+                        path.as_ptr(),
+                        &how as *const OpenHow,
+                        mem::size_of::<OpenHow>(),
+                    )
+                });
+                match res {
+                    Ok(fd) => return Ok(FileDesc::new(fd as c_int)),
+                    Err(e) => match e.raw_os_error() {
+                        // The kernel couldn't rule out that a concurrent
+                        // rename let `..` escape the directory.
+                        Some(libc::EAGAIN) => {}
+                        // Multiple threads can race to store this, which just
+                        // means they all tried (and failed) the same syscall.
+                        Some(libc::ENOSYS) => {
+                            HAS_OPENAT2.store(false, Ordering::Relaxed);
+                            break;
+                        }
+                        // Blocked by seccomp, for example.
+                        Some(libc::EPERM) => break,
+                        _ => return Err(e),
+                    },
+                }
+            }
+        }
+
+        // See `File::open_c` for why the mode is passed as a `c_int`.
+        let fd =
+            cvt_r(|| unsafe { libc::openat(self.fd.raw(), path.as_ptr(), flags, mode as c_int) })?;
+        Ok(FileDesc::new(fd))
+    }
+
+    // Elsewhere, paths are handed to the `*at` functions as they are.
+    #[cfg(not(target_os = "linux"))]
+    fn parent_and_name(&self, path: &Path) -> io::Result<(Option<FileDesc>, CString)> {
+        Ok((None, cstr(path)?))
+    }
+
+    // Splits `path` into the directory holding its last component, opened
+    // with `openat` so that it can't be outside of this directory, and that
+    // last component, for the `*at` functions that have no `openat2`
+    // equivalent. None of them follow a symlink in the last component. The
+    // directory is `None` if it is this directory.
+    #[cfg(target_os = "linux")]
+    fn parent_and_name(&self, path: &Path) -> io::Result<(Option<FileDesc>, CString)> {
+        let bytes = path.as_os_str().as_bytes();
+        // Trailing slashes stay on the name, where they require a directory.
+        let len = bytes.iter().rposition(|&b| b != b'/').map_or(0, |i| i + 1);
+        let start = bytes[..len].iter().rposition(|&b| b == b'/').map_or(0, |i| i + 1);
+
+        let flags = libc::O_PATH | libc::O_DIRECTORY | libc::O_CLOEXEC;
+        // A last component of `.` or `..` (or none at all, for `/`) names a
+        // directory that must itself be checked, so open the whole path and
+        // operate on its `.` entry, which fails like the original path would.
+        if matches!(&bytes[start..len], b"" | b"." | b"..") {
+            let dir = self.openat(path, flags, 0)?;
+            return Ok((Some(dir), CString::new(".").unwrap()));
+        }
+        let parent = if start == 0 {
+            None
+        } else {
+            Some(self.openat(Path::new(OsStr::from_bytes(&bytes[..start])), flags, 0)?)
+        };
+        Ok((parent, CString::new(&bytes[start..])?))
+    }
+}
+
+#[cfg(not(any(target_os = "vxworks", target_os = "redox", target_os = "l4re")))]
+fn statat(fd: c_int, path: &CStr, flags: c_int) -> io::Result<FileAttr> {
+    cfg_has_statx! {
+        if let Some(ret) = unsafe { try_statx(
+            fd,
+            path.as_ptr(),
+            flags | libc::AT_STATX_SYNC_AS_STAT,
+            libc::STATX_ALL,
+        ) } {
+            return ret;
+        }
+    }
+
+    let mut stat: stat64 = unsafe { mem::zeroed() };
+    cvt(unsafe { fstatat64(fd, path.as_ptr(), &mut stat, flags) })?;
+    Ok(FileAttr::from_stat64(stat))
+}
+
+#[cfg(not(any(target_os = "vxworks", target_os = "redox", target_os = "l4re")))]
+impl fmt::Debug for Dir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dir").field("fd", &self.fd.raw()).field("path", &self.root).finish()
+    }
+}
+
 fn cstr(path: &Path) -> io::Result<CString> {
     Ok(CString::new(path.as_os_str().as_bytes())?)
 }
@@ -1001,21 +1337,7 @@ pub fn readdir(p: &Path) -> io::Result<ReadDir> {
     let p = cstr(p)?;
     unsafe {
         let ptr = libc::opendir(p.as_ptr());
-        if ptr.is_null() {
-            Err(Error::last_os_error())
-        } else {
-            let inner = InnerReadDir { dirp: Dir(ptr), root };
-            Ok(ReadDir {
-                inner: Arc::new(inner),
-                #[cfg(not(any(
-                    target_os = "solaris",
-                    target_os = "illumos",
-                    target_os = "fuchsia",
-                    target_os = "redox",
-                )))]
-                end_of_stream: false,
-            })
-        }
+        if ptr.is_null() { Err(Error::last_os_error()) } else { Ok(ReadDir::new(ptr, root)) }
     }
 }
 
@@ -17,7 +17,7 @@
 #[cfg(not(target_os = "fuchsia"))]
 use crate::sys::fs::OpenOptions;
 
-use libc::{c_char, c_int, gid_t, uid_t, EXIT_FAILURE, EXIT_SUCCESS};
+use libc::{c_char, c_int, gid_t, pid_t, uid_t, EXIT_FAILURE, EXIT_SUCCESS};
 
 cfg_if::cfg_if! {
     if #[cfg(target_os = "fuchsia")] {
@@ -79,6 +79,12 @@ pub struct Command {
     stdin: Option<Stdio>,
     stdout: Option<Stdio>,
     stderr: Option<Stdio>,
+    pgroup: Option<pid_t>,
+    setsid: bool,
+    #[cfg(target_os = "linux")]
+    create_pidfd: bool,
+    #[cfg(target_os = "linux")]
+    pdeathsig: Option<c_int>,
 }
 
 // Create a new type for argv, so that we can make it `Send` and `Sync`
@@ -141,6 +147,12 @@ pub fn new(program: &OsStr) -> Command {
             stdin: None,
             stdout: None,
             stderr: None,
+            pgroup: None,
+            setsid: false,
+            #[cfg(target_os = "linux")]
+            create_pidfd: false,
+            #[cfg(target_os = "linux")]
+            pdeathsig: None,
         }
     }
 
@@ -176,6 +188,20 @@ pub fn gid(&mut self, id: gid_t) {
     pub fn groups(&mut self, groups: &[gid_t]) {
         self.groups = Some(Box::from(groups));
     }
+    pub fn pgroup(&mut self, pgroup: pid_t) {
+        self.pgroup = Some(pgroup);
+    }
+    pub fn setsid(&mut self, setsid: bool) {
+        self.setsid = setsid;
+    }
+    #[cfg(target_os = "linux")]
+    pub fn create_pidfd(&mut self, val: bool) {
+        self.create_pidfd = val;
+    }
+    #[cfg(target_os = "linux")]
+    pub fn pdeathsig(&mut self, signal: c_int) {
+        self.pdeathsig = Some(signal);
+    }
 
     pub fn saw_nul(&self) -> bool {
         self.saw_nul
@@ -223,6 +249,32 @@ pub fn get_gid(&self) -> Option<gid_t> {
     pub fn get_groups(&self) -> Option<&[gid_t]> {
         self.groups.as_deref()
     }
+    #[cfg(target_os = "linux")]
+    pub fn get_create_pidfd(&self) -> bool {
+        self.create_pidfd
+    }
+    #[cfg(not(target_os = "linux"))]
+    #[allow(dead_code)]
+    pub fn get_create_pidfd(&self) -> bool {
+        false
+    }
+    #[allow(dead_code)]
+    pub fn get_pgroup(&self) -> Option<pid_t> {
+        self.pgroup
+    }
+    #[allow(dead_code)]
+    pub fn get_setsid(&self) -> bool {
+        self.setsid
+    }
+    #[cfg(target_os = "linux")]
+    pub fn get_pdeathsig(&self) -> Option<c_int> {
+        self.pdeathsig
+    }
+    #[cfg(not(target_os = "linux"))]
+    #[allow(dead_code)]
+    pub fn get_pdeathsig(&self) -> Option<c_int> {
+        None
+    }
 
     pub fn get_closures(&mut self) -> &mut Vec<Box<dyn FnMut() -> io::Result<()> + Send + Sync>> {
         &mut self.closures
@@ -3,6 +3,7 @@
 use crate::io;
 use crate::mem;
 use crate::ptr;
+use crate::time::Duration;
 
 use crate::sys::process::process_common::*;
 use crate::sys::process::zircon::{zx_handle_t, Handle};
@@ -230,6 +231,31 @@ pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
         }
         Ok(Some(ExitStatus(proc_info.return_code)))
     }
+
+    pub fn wait_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        use crate::sys::process::zircon::*;
+
+        let timeout = timeout.as_nanos().try_into().unwrap_or(zx_duration_t::MAX);
+        unsafe {
+            // `zx_deadline_after` saturates at `ZX_TIME_INFINITE`.
+            let deadline = zx_deadline_after(timeout);
+            let status = zx_object_wait_one(
+                self.handle.raw(),
+                ZX_TASK_TERMINATED,
+                deadline,
+                ptr::null_mut(),
+            );
+            match status {
+                0 => {} // Success
+                x if x == ERR_TIMED_OUT => {
+                    return Ok(None);
+                }
+                _ => return Err(io::Error::from_raw_os_error(status)),
+            }
+        }
+        // The process has terminated, so this won't block.
+        self.wait().map(Some)
+    }
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -1,3 +1,4 @@
+use crate::cmp;
 use crate::convert::TryInto;
 use crate::fmt;
 use crate::io::{self, Error, ErrorKind};
@@ -6,6 +7,23 @@
 use crate::sys;
 use crate::sys::cvt;
 use crate::sys::process::process_common::*;
+use crate::thread;
+use crate::time::{Duration, Instant};
+
+#[cfg(target_os = "linux")]
+use crate::os::unix::{io::AsRawFd, process::PidFd};
+#[cfg(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd",
+))]
+use crate::sys::fd::FileDesc;
+#[cfg(target_os = "linux")]
+use crate::sys_common::FromInner;
 
 #[cfg(target_os = "vxworks")]
 use libc::RTP_ID as pid_t;
@@ -51,12 +69,13 @@ pub fn spawn(
         // a lock any more because the parent won't do anything and the child is
         // in its own process. Thus the parent drops the lock guard while the child
         // forgets it to avoid unlocking it on a new thread, which would be invalid.
-        let (env_lock, pid) = unsafe { (sys::os::env_read_lock(), cvt(libc::fork())?) };
+        let parent = unsafe { libc::getpid() };
+        let (env_lock, (pid, pidfd)) = unsafe { (sys::os::env_read_lock(), self.do_fork()?) };
 
         if pid == 0 {
             mem::forget(env_lock);
             drop(input);
-            let Err(err) = unsafe { self.do_exec(theirs, envp.as_ref()) };
+            let Err(err) = unsafe { self.do_exec(theirs, envp.as_ref(), parent) };
             let errno = err.raw_os_error().unwrap_or(libc::EINVAL) as u32;
             let errno = errno.to_be_bytes();
             let bytes = [
@@ -79,7 +98,7 @@ pub fn spawn(
         drop(env_lock);
         drop(output);
 
-        let mut p = Process { pid, status: None };
+        let mut p = unsafe { Process::new(pid, pidfd) };
         let mut bytes = [0; 8];
 
         // loop to handle EINTR
@@ -111,6 +130,87 @@ pub fn spawn(
         }
     }
 
+    // Attempts to fork the process. If successful, returns `Ok((0, -1))` in
+    // the child, and `Ok((child_pid, -1))` in the parent.
+    #[cfg(not(target_os = "linux"))]
+    unsafe fn do_fork(&mut self) -> io::Result<(pid_t, c_int)> {
+        cvt(libc::fork()).map(|pid| (pid, -1))
+    }
+
+    // Attempts to fork the process. If successful, returns `Ok((0, -1))` in
+    // the child, and `Ok((child_pid, child_pidfd))` in the parent, where
+    // `child_pidfd` is -1 if no pidfd was requested or none could be created.
+    //
+    // When a pidfd is requested we first try `clone3` with `CLONE_PIDFD`,
+    // which creates it atomically with the child. On kernels without `clone3`
+    // (before 5.3), or where it is filtered out by seccomp, we fall back to
+    // `fork` followed by `pidfd_open` in the parent. That is still free of PID
+    // reuse races: the child can't be reaped before we wait on it ourselves,
+    // unless `SIGCHLD` is ignored.
+    #[cfg(target_os = "linux")]
+    unsafe fn do_fork(&mut self) -> io::Result<(pid_t, c_int)> {
+        use crate::sync::atomic::{AtomicBool, Ordering};
+
+        static HAS_CLONE3: AtomicBool = AtomicBool::new(true);
+        const CLONE_PIDFD: u64 = 0x00001000;
+
+        #[repr(C)]
+        struct CloneArgs {
+            flags: u64,
+            pidfd: u64,
+            child_tid: u64,
+            parent_tid: u64,
+            exit_signal: u64,
+            stack: u64,
+            stack_size: u64,
+            tls: u64,
+        }
+
+        if !self.get_create_pidfd() {
+            return cvt(libc::fork()).map(|pid| (pid, -1));
+        }
+
+        let mut pidfd: c_int = -1;
+        if HAS_CLONE3.load(Ordering::Relaxed) {
+            let mut args = CloneArgs {
+                flags: CLONE_PIDFD,
+                pidfd: &mut pidfd as *mut c_int as u64,
+                child_tid: 0,
+                parent_tid: 0,
+                exit_signal: libc::SIGCHLD as u64,
+                stack: 0,
+                stack_size: 0,
+                tls: 0,
+            };
+            // `clone3` is called through `syscall` directly, as libc wrappers
+            // (where they exist) expect a function to run in the child.
+            let res = libc::syscall(
+                libc::SYS_clone3,
+                &mut args as *mut CloneArgs,
+                mem::size_of::<CloneArgs>(),
+            );
+            match cvt(res) {
+                Ok(pid) => return Ok((pid as pid_t, pidfd)),
+                Err(e) => match e.raw_os_error() {
+                    // Multiple threads can race to store this, which just
+                    // means they all tried (and failed) the same syscall.
+                    Some(libc::ENOSYS) => HAS_CLONE3.store(false, Ordering::Relaxed),
+                    // Blocked by seccomp, for example.
+                    Some(libc::EPERM) => {}
+                    _ => return Err(e),
+                },
+            }
+        }
+
+        let pid = cvt(libc::fork())?;
+        if pid == 0 {
+            return Ok((0, -1));
+        }
+        // A pidfd is best effort: if `pidfd_open` fails too (before Linux
+        // 5.3), the child is still tracked by its PID alone.
+        Ok((pid, pidfd_open(pid, 0)))
+    }
+
     pub fn exec(&mut self, default: Stdio) -> io::Error {
         let envp = self.capture_env();
 
@@ -129,7 +229,7 @@ pub fn exec(&mut self, default: Stdio) -> io::Error {
                     // environment lock before we try to exec.
                     let _lock = sys::os::env_read_lock();
 
-                    let Err(e) = self.do_exec(theirs, envp.as_ref());
+                    let Err(e) = self.do_exec(theirs, envp.as_ref(), libc::getppid());
                     e
                 }
             }
@@ -167,10 +267,14 @@ pub fn exec(&mut self, default: Stdio) -> io::Error {
     // allocation). Instead we just close it manually. This will never
     // have the drop glue anyway because this code never returns (the
     // child will either exec() or invoke libc::exit)
+    //
+    // `parent` is the process that the parent death signal, if any, is tied
+    // to.
     unsafe fn do_exec(
         &mut self,
         stdio: ChildPipes,
         maybe_envp: Option<&CStringArray>,
+        #[allow(unused_variables)] parent: libc::pid_t,
     ) -> Result<!, io::Error> {
         use crate::sys::{self, cvt_r};
 
@@ -212,6 +316,24 @@ unsafe fn do_exec(
         if let Some(ref cwd) = *self.get_cwd() {
             cvt(libc::chdir(cwd.as_ptr()))?;
         }
+        if self.get_setsid() {
+            cvt(libc::setsid())?;
+        }
+        if let Some(pgroup) = self.get_pgroup() {
+            cvt(libc::setpgid(0, pgroup))?;
+        }
+
+        // This comes after the uid and gid changes, as those clear the parent
+        // death signal.
+        #[cfg(target_os = "linux")]
+        if let Some(signal) = self.get_pdeathsig() {
+            cvt(libc::prctl(libc::PR_SET_PDEATHSIG, signal as libc::c_ulong, 0, 0, 0))?;
+            // If the parent already exited, the signal will never be sent, so
+            // deliver it ourselves.
+            if libc::getppid() != parent {
+                cvt(libc::raise(signal))?;
+            }
+        }
 
         // emscripten has no signal support.
         #[cfg(not(target_os = "emscripten"))]
@@ -297,15 +419,25 @@ fn posix_spawn(
             || (self.env_saw_path() && !self.program_is_path())
             || !self.get_closures().is_empty()
             || self.get_groups().is_some()
+            || self.get_create_pidfd()
+            || self.get_pdeathsig().is_some()
         {
             return Ok(None);
         }
 
-        // Only glibc 2.24+ posix_spawn() supports returning ENOENT directly.
+        // `POSIX_SPAWN_SETSID` is a glibc and musl extension.
+        #[cfg(target_os = "linux")]
+        const POSIX_SPAWN_SETSID: libc::c_int = 0x80;
+        if self.get_setsid() && !cfg!(target_os = "linux") {
+            return Ok(None);
+        }
+
+        // Only glibc 2.24+ posix_spawn() supports returning ENOENT directly,
+        // and only glibc 2.26+ supports `POSIX_SPAWN_SETSID`.
         #[cfg(all(target_os = "linux", target_env = "gnu"))]
         {
             if let Some(version) = sys::os::glibc_version() {
-                if version < (2, 24) {
+                if version < (2, 24) || (self.get_setsid() && version < (2, 26)) {
                     return Ok(None);
                 }
             } else {
@@ -341,7 +473,7 @@ fn posix_spawn_file_actions_addchdir_np(
             None => None,
         };
 
-        let mut p = Process { pid: 0, status: None };
+        let mut p = unsafe { Process::new(0, -1) };
 
         struct PosixSpawnFileActions<'a>(&'a mut MaybeUninit<libc::posix_spawn_file_actions_t>);
 
@@ -403,7 +535,15 @@ fn drop(&mut self) {
             cvt(sigaddset(set.as_mut_ptr(), libc::SIGPIPE))?;
             cvt_nz(libc::posix_spawnattr_setsigdefault(attrs.0.as_mut_ptr(), set.as_ptr()))?;
 
-            let flags = libc::POSIX_SPAWN_SETSIGDEF | libc::POSIX_SPAWN_SETSIGMASK;
+            let mut flags = libc::POSIX_SPAWN_SETSIGDEF | libc::POSIX_SPAWN_SETSIGMASK;
+            if let Some(pgroup) = self.get_pgroup() {
+                flags |= libc::POSIX_SPAWN_SETPGROUP;
+                cvt_nz(libc::posix_spawnattr_setpgroup(attrs.0.as_mut_ptr(), pgroup))?;
+            }
+            #[cfg(target_os = "linux")]
+            if self.get_setsid() {
+                flags |= POSIX_SPAWN_SETSID;
+            }
             cvt_nz(libc::posix_spawnattr_setflags(attrs.0.as_mut_ptr(), flags as _))?;
 
             // Make sure we synchronize access to the global `environ` resource
@@ -430,23 +570,65 @@ fn drop(&mut self) {
 pub struct Process {
     pid: pid_t,
     status: Option<ExitStatus>,
+    // On Linux, the pidfd created for this child, if one was requested and
+    // could be created. While it is open it is used instead of the PID, which
+    // may be recycled once the child is reaped.
+    #[cfg(target_os = "linux")]
+    pidfd: Option<PidFd>,
 }
 
 impl Process {
+    // `pidfd` must either be -1 or an open pidfd owned by the caller, which
+    // is then closed along with the `Process`.
+    #[cfg(target_os = "linux")]
+    unsafe fn new(pid: pid_t, pidfd: c_int) -> Process {
+        let pidfd = if pidfd >= 0 { Some(PidFd::from_inner(FileDesc::new(pidfd))) } else { None };
+        Process { pid, status: None, pidfd }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    unsafe fn new(pid: pid_t, _pidfd: c_int) -> Process {
+        Process { pid, status: None }
+    }
+
     pub fn id(&self) -> u32 {
         self.pid as u32
     }
 
+    #[cfg(target_os = "linux")]
+    pub fn pidfd(&self) -> Option<&PidFd> {
+        self.pidfd.as_ref()
+    }
+
+    #[cfg(target_os = "linux")]
+    pub fn take_pidfd(&mut self) -> Option<PidFd> {
+        self.pidfd.take()
+    }
+
     pub fn kill(&mut self) -> io::Result<()> {
+        let exited = || {
+            Error::new_const(
+                ErrorKind::InvalidInput,
+                &"invalid argument: can't kill an exited process",
+            )
+        };
         // If we've already waited on this process then the pid can be recycled
         // and used for another process, and we probably shouldn't be killing
         // random processes, so just return an error.
         if self.status.is_some() {
-            Err(Error::new_const(
-                ErrorKind::InvalidInput,
-                &"invalid argument: can't kill an exited process",
-            ))
+            Err(exited())
         } else {
+            #[cfg(target_os = "linux")]
+            if let Some(ref pidfd) = self.pidfd {
+                // A child that exited but wasn't reaped yet can still be
+                // signalled. `ESRCH` means it was reaped behind our back, by
+                // a `waitpid` elsewhere, which is reported like a child we
+                // waited on.
+                return match pidfd_kill(pidfd) {
+                    Err(ref e) if e.raw_os_error() == Some(libc::ESRCH) => Err(exited()),
+                    res => res,
+                };
+            }
             cvt(unsafe { libc::kill(self.pid, libc::SIGKILL) }).map(drop)
         }
     }
@@ -456,6 +638,13 @@ pub fn wait(&mut self) -> io::Result<ExitStatus> {
         if let Some(status) = self.status {
             return Ok(status);
         }
+        #[cfg(target_os = "linux")]
+        if let Some(ref pidfd) = self.pidfd {
+            // Without `WNOHANG`, `waitid` only returns once the child exited.
+            let status = pidfd_wait(pidfd, self.pid, 0)?.unwrap();
+            self.status = Some(status);
+            return Ok(status);
+        }
         let mut status = 0 as c_int;
         cvt_r(|| unsafe { libc::waitpid(self.pid, &mut status, 0) })?;
         self.status = Some(ExitStatus::new(status));
@@ -466,6 +655,12 @@ pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
         if let Some(status) = self.status {
             return Ok(Some(status));
         }
+        #[cfg(target_os = "linux")]
+        if let Some(ref pidfd) = self.pidfd {
+            let status = pidfd_wait(pidfd, self.pid, libc::WNOHANG)?;
+            self.status = status;
+            return Ok(status);
+        }
         let mut status = 0 as c_int;
         let pid = cvt(unsafe { libc::waitpid(self.pid, &mut status, libc::WNOHANG) })?;
         if pid == 0 {
@@ -475,6 +670,212 @@ pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
             Ok(Some(ExitStatus::new(status)))
         }
     }
+
+    pub fn wait_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        if let Some(status) = self.try_wait()? {
+            return Ok(Some(status));
+        }
+        // A deadline too far away to represent is as good as no deadline.
+        let deadline = Instant::now().checked_add(timeout);
+
+        // On Linux, the child's pidfd becomes readable when it exits. If the
+        // child wasn't spawned with one, we open one just for this wait,
+        // which can't race with PID reuse as we haven't reaped the child yet.
+        #[cfg(target_os = "linux")]
+        {
+            let exited = match self.pidfd {
+                Some(ref pidfd) => Some(poll_readable(pidfd.as_raw_fd(), deadline)?),
+                None => match cvt(unsafe { pidfd_open(self.pid, 0) }) {
+                    Ok(fd) => {
+                        let pidfd = FileDesc::new(fd);
+                        Some(poll_readable(pidfd.raw(), deadline)?)
+                    }
+                    // Before Linux 5.3, fall back to polling below.
+                    Err(_) => None,
+                },
+            };
+            if let Some(exited) = exited {
+                return if exited { self.try_wait() } else { Ok(None) };
+            }
+        }
+
+        // On macOS and the BSDs, a kqueue reports the child's exit.
+        #[cfg(any(
+            target_os = "macos",
+            target_os = "ios",
+            target_os = "freebsd",
+            target_os = "dragonfly",
+            target_os = "netbsd",
+            target_os = "openbsd",
+        ))]
+        {
+            // The child may not be reapable yet when the event is reported,
+            // so wait for it, which won't block for long.
+            return if kqueue_wait_exit(self.pid, deadline)? {
+                self.wait().map(Some)
+            } else {
+                Ok(None)
+            };
+        }
+
+        // This is a last resort, for Linux before 5.3 and for the platforms
+        // that can't wait for a specific child with a timeout short of
+        // installing a process-wide `SIGCHLD` handler, which would conflict
+        // with any the application has. Poll instead, backing off so that
+        // long waits don't keep the thread busy.
+        #[allow(unreachable_code)]
+        let mut delay = Duration::from_millis(1);
+        loop {
+            let now = Instant::now();
+            let remaining = match deadline {
+                Some(deadline) if deadline <= now => return Ok(None),
+                Some(deadline) => deadline - now,
+                None => return self.wait().map(Some),
+            };
+            thread::sleep(cmp::min(delay, remaining));
+            if let Some(status) = self.try_wait()? {
+                return Ok(Some(status));
+            }
+            delay = cmp::min(delay * 2, Duration::from_millis(50));
+        }
+    }
+}
+
+// Sends `SIGKILL` through a pidfd, which can't reach an unrelated process
+// even if the child has already been reaped.
+#[cfg(target_os = "linux")]
+fn pidfd_kill(pidfd: &PidFd) -> io::Result<()> {
+    syscall! {
+        fn pidfd_send_signal(
+            pidfd: c_int,
+            sig: c_int,
+            info: *mut libc::siginfo_t,
+            flags: libc::c_uint
+        ) -> c_int
+    }
+    cvt(unsafe { pidfd_send_signal(pidfd.as_raw_fd(), libc::SIGKILL, ptr::null_mut(), 0) })
+        .map(drop)
+}
+
+// Waits for the child through its pidfd. `P_PIDFD` is only supported since
+// Linux 5.4, one release after pidfds themselves, so on Linux 5.3 this falls
+// back to `waitpid`, which is still safe as the child can't have been reaped.
+#[cfg(target_os = "linux")]
+fn pidfd_wait(pidfd: &PidFd, pid: pid_t, options: c_int) -> io::Result<Option<ExitStatus>> {
+    use crate::sys::cvt_r;
+
+    const P_PIDFD: libc::idtype_t = 3;
+
+    let mut siginfo: libc::siginfo_t = unsafe { mem::zeroed() };
+    let res = cvt_r(|| unsafe {
+        libc::waitid(
+            P_PIDFD,
+            pidfd.as_raw_fd() as libc::id_t,
+            &mut siginfo,
+            libc::WEXITED | options,
+        )
+    });
+    match res {
+        Ok(_) => {}
+        Err(ref e) if e.raw_os_error() == Some(libc::EINVAL) => {
+            let mut status = 0 as c_int;
+            let pid = cvt_r(|| unsafe { libc::waitpid(pid, &mut status, options) })?;
+            return Ok(if pid == 0 { None } else { Some(ExitStatus::new(status)) });
+        }
+        Err(e) => return Err(e),
+    }
+    // With `WNOHANG`, `waitid` leaves `siginfo` zeroed if the child is still
+    // running.
+    if unsafe { siginfo.si_pid() } == 0 {
+        Ok(None)
+    } else {
+        Ok(Some(ExitStatus::from_waitid_siginfo(&siginfo)))
+    }
+}
+
+#[cfg(target_os = "linux")]
+syscall! {
+    fn pidfd_open(pid: pid_t, flags: libc::c_uint) -> c_int
+}
+
+// Waits until `fd` is readable or `deadline` passes, returning whether it
+// became readable.
+#[cfg(target_os = "linux")]
+fn poll_readable(fd: c_int, deadline: Option<Instant>) -> io::Result<bool> {
+    let mut pollfd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+    loop {
+        let timeout = match deadline {
+            None => -1,
+            Some(deadline) => {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                // Round up, so that we don't wake up just before the deadline.
+                let millis = (remaining.as_nanos() + 999_999) / 1_000_000;
+                cmp::min(millis, c_int::MAX as u128) as c_int
+            }
+        };
+        match cvt(unsafe { libc::poll(&mut pollfd, 1, timeout) }) {
+            Ok(0) => {
+                // Very long timeouts are split up over several calls.
+                if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                    return Ok(false);
+                }
+            }
+            Ok(_) => return Ok(true),
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Waits until the process `pid` exits or `deadline` passes, returning whether
+// it exited.
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "dragonfly",
+    target_os = "netbsd",
+    target_os = "openbsd",
+))]
+fn kqueue_wait_exit(pid: pid_t, deadline: Option<Instant>) -> io::Result<bool> {
+    // The kqueue is not inherited by children, so it needs no `FD_CLOEXEC`.
+    let kq = FileDesc::new(cvt(unsafe { libc::kqueue() })?);
+
+    // The field types of `kevent` differ between platforms, hence the casts.
+    let mut change: libc::kevent = unsafe { mem::zeroed() };
+    change.ident = pid as _;
+    change.filter = libc::EVFILT_PROC as _;
+    change.flags = (libc::EV_ADD | libc::EV_ONESHOT) as _;
+    change.fflags = libc::NOTE_EXIT as _;
+    // Some platforms refuse to watch a process that already exited.
+    match cvt(unsafe { libc::kevent(kq.raw(), &change, 1, ptr::null_mut(), 0, ptr::null()) }) {
+        Ok(_) => {}
+        Err(ref e) if e.raw_os_error() == Some(libc::ESRCH) => return Ok(true),
+        Err(e) => return Err(e),
+    }
+
+    let mut event: libc::kevent = unsafe { mem::zeroed() };
+    loop {
+        let timeout = deadline.map(|deadline| {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            libc::timespec {
+                // Very long timeouts are split up over several calls.
+                tv_sec: cmp::min(remaining.as_secs(), c_int::MAX as u64) as libc::time_t,
+                tv_nsec: remaining.subsec_nanos() as _,
+            }
+        });
+        let timeout = timeout.as_ref().map_or(ptr::null(), |timeout| timeout as *const _);
+        match cvt(unsafe { libc::kevent(kq.raw(), ptr::null(), 0, &mut event, 1, timeout) }) {
+            Ok(0) => {
+                if deadline.map_or(false, |deadline| Instant::now() >= deadline) {
+                    return Ok(false);
+                }
+            }
+            Ok(_) => return Ok(true),
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
 }
 
 /// Unix exit statuses
@@ -486,6 +887,21 @@ pub fn new(status: c_int) -> ExitStatus {
         ExitStatus(status)
     }
 
+    // Rebuilds the wait status that `waitpid` would have returned from the
+    // `siginfo_t` filled in by `waitid`.
+    #[cfg(target_os = "linux")]
+    fn from_waitid_siginfo(siginfo: &libc::siginfo_t) -> ExitStatus {
+        let status = unsafe { siginfo.si_status() };
+        match siginfo.si_code {
+            libc::CLD_EXITED => ExitStatus((status & 0xff) << 8),
+            libc::CLD_KILLED => ExitStatus(status),
+            libc::CLD_DUMPED => ExitStatus(status | 0x80),
+            libc::CLD_CONTINUED => ExitStatus(0xffff),
+            libc::CLD_STOPPED | libc::CLD_TRAPPED => ExitStatus(((status & 0xff) << 8) | 0x7f),
+            _ => unreachable!("waitid() should only return the above codes"),
+        }
+    }
+
     fn exited(&self) -> bool {
         libc::WIFEXITED(self.0)
     }
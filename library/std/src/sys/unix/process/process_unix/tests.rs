@@ -28,3 +28,168 @@ fn exitstatus_display_tests() {
         t(0x000ff, "unrecognised wait status: 255 0xff");
     }
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_command_create_pidfd() {
+    use crate::io::{self, ErrorKind};
+    use crate::mem;
+    use crate::os::unix::io::AsRawFd;
+    use crate::os::unix::process::{ChildExt, CommandExt, ExitStatusExt};
+    use crate::process::Command;
+
+    // Returns whether `fd` becomes readable within `timeout_ms`.
+    let poll_readable = |fd, timeout_ms| {
+        let mut pollfd = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+        let res = unsafe { libc::poll(&mut pollfd, 1, timeout_ms) };
+        assert!(res >= 0, "poll failed: {}", io::Error::last_os_error());
+        res == 1
+    };
+
+    // The pidfd becomes readable once the child exits.
+    let mut child = Command::new("sleep").arg("1000").create_pidfd(true).spawn().unwrap();
+    let pidfd = match child.pidfd() {
+        Ok(pidfd) => pidfd.as_raw_fd(),
+        // Kernels before 5.3 support neither `clone3` nor `pidfd_open`.
+        Err(_) => {
+            child.kill().unwrap();
+            child.wait().unwrap();
+            return;
+        }
+    };
+    assert!(!poll_readable(pidfd, 0));
+    assert_eq!(child.try_wait().unwrap(), None);
+    child.kill().unwrap();
+    assert!(poll_readable(pidfd, 60_000));
+    let status = child.wait().unwrap();
+    assert_eq!(status.signal(), Some(libc::SIGKILL));
+
+    // A child that exited but wasn't waited for can still be killed.
+    let mut child = Command::new("true").create_pidfd(true).spawn().unwrap();
+    assert!(poll_readable(child.pidfd().unwrap().as_raw_fd(), 60_000));
+    child.kill().unwrap();
+    assert!(child.wait().unwrap().success());
+
+    // A child reaped behind the `Child`'s back is reported as exited.
+    let mut child = Command::new("true").create_pidfd(true).spawn().unwrap();
+    let pid = child.id() as libc::pid_t;
+    let mut status = 0;
+    assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+    assert_eq!(child.kill().unwrap_err().kind(), ErrorKind::InvalidInput);
+
+    // `kill` and `try_wait` go through the pidfd: once it refers to another
+    // process, they act on that one and leave the child alone.
+    let mut child = Command::new("sleep").arg("1000").create_pidfd(true).spawn().unwrap();
+    let other = Command::new("sleep").arg("1000").spawn().unwrap();
+    let pidfd = child.pidfd().unwrap().as_raw_fd();
+    let other_pidfd = unsafe { super::pidfd_open(other.id() as libc::pid_t, 0) };
+    assert!(other_pidfd >= 0);
+    assert_eq!(unsafe { libc::dup2(other_pidfd, pidfd) }, pidfd);
+    unsafe { libc::close(other_pidfd) };
+
+    // `waitid` accepts pidfds since Linux 5.4, one release after pidfds.
+    const P_PIDFD: libc::idtype_t = 3;
+    let mut siginfo: libc::siginfo_t = unsafe { mem::zeroed() };
+    let options = libc::WEXITED | libc::WNOHANG;
+    if unsafe { libc::waitid(P_PIDFD, pidfd as libc::id_t, &mut siginfo, options) } == 0 {
+        child.kill().unwrap();
+        assert!(poll_readable(pidfd, 60_000));
+        let status = child.try_wait().unwrap().unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+    } else {
+        unsafe { libc::kill(other.id() as libc::pid_t, libc::SIGKILL) };
+        unsafe { libc::waitpid(other.id() as libc::pid_t, &mut status, 0) };
+    }
+    let pid = child.id() as libc::pid_t;
+    assert_eq!(unsafe { libc::waitpid(pid, &mut status, libc::WNOHANG) }, 0);
+    unsafe { libc::kill(pid, libc::SIGKILL) };
+    assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+
+    let mut child = Command::new("true").create_pidfd(true).spawn().unwrap();
+    let _pidfd = child.take_pidfd();
+    assert!(child.pidfd().is_err());
+    assert!(child.wait().unwrap().success());
+
+    let mut child = Command::new("true").spawn().unwrap();
+    assert!(child.pidfd().is_err());
+    assert!(child.wait().unwrap().success());
+}
+
+#[test]
+fn test_command_process_group_and_setsid() {
+    use crate::os::unix::process::CommandExt;
+    use crate::process::Command;
+
+    // A `pre_exec` closure forces the fork/exec path instead of `posix_spawn`.
+    for &force_fork in &[false, true] {
+        let mut cmd = Command::new("sleep");
+        cmd.arg("1000").process_group(0);
+        if force_fork {
+            unsafe { cmd.pre_exec(|| Ok(())) };
+        }
+        let mut child = cmd.spawn().unwrap();
+        let pid = child.id() as libc::pid_t;
+        assert_eq!(unsafe { libc::getpgid(pid) }, pid);
+        assert_eq!(unsafe { libc::getsid(pid) }, unsafe { libc::getsid(0) });
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        let mut cmd = Command::new("sleep");
+        cmd.arg("1000").setsid(true);
+        if force_fork {
+            unsafe { cmd.pre_exec(|| Ok(())) };
+        }
+        let mut child = cmd.spawn().unwrap();
+        let pid = child.id() as libc::pid_t;
+        assert_eq!(unsafe { libc::getsid(pid) }, pid);
+        assert_eq!(unsafe { libc::getpgid(pid) }, pid);
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_command_parent_death_signal() {
+    use crate::env;
+    use crate::io::{BufRead, BufReader, Read};
+    use crate::os::unix::process::CommandExt;
+    use crate::process::{self, Command, Stdio};
+
+    // The test runs itself again as the intermediate child, which spawns the grandchild with a
+    // parent death signal and exits. The grandchild reports the signal on the stderr it shares
+    // with the intermediate child, and gives up after about a minute.
+    const INTERMEDIATE: &str = "RUST_TEST_PARENT_DEATH_SIGNAL_INTERMEDIATE";
+    if env::var_os(INTERMEDIATE).is_some() {
+        let mut grandchild = Command::new("sh")
+            .arg("-c")
+            .arg(
+                "trap 'echo terminated >&2; exit' TERM; echo started; \
+                 i=0; while [ $i -lt 600 ]; do sleep 0.1; i=$((i + 1)); done",
+            )
+            .stdout(Stdio::piped())
+            .parent_death_signal(libc::SIGTERM)
+            .spawn()
+            .unwrap();
+        // Only exit once the grandchild has set up its trap.
+        let mut line = String::new();
+        BufReader::new(grandchild.stdout.take().unwrap()).read_line(&mut line).unwrap();
+        assert_eq!(line, "started\n");
+        process::exit(0);
+    }
+
+    let test_name = module_path!().strip_prefix("std::").unwrap().to_owned()
+        + "::test_command_parent_death_signal";
+    let mut intermediate = Command::new(env::current_exe().unwrap())
+        .args(&["--exact", &test_name])
+        .env(INTERMEDIATE, "1")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    // The grandchild holds on to the pipe until it exits.
+    let mut stderr = String::new();
+    intermediate.stderr.take().unwrap().read_to_string(&mut stderr).unwrap();
+    assert!(intermediate.wait().unwrap().success());
+    assert_eq!(stderr, "terminated\n");
+}
@@ -6,6 +6,8 @@
 use crate::sys::time::SystemTime;
 use crate::sys::{unsupported, Void};
 
+pub use crate::sys_common::fs::Dir;
+
 pub struct File(Void);
 
 pub struct FileAttr(Void);
@@ -190,6 +192,26 @@ pub fn datasync(&self) -> io::Result<()> {
         match self.0 {}
     }
 
+    pub fn lock_shared(&self) -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn lock_exclusive(&self) -> io::Result<()> {
+        match self.0 {}
+    }
+
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        match self.0 {}
+    }
+
+    pub fn try_lock_exclusive(&self) -> io::Result<bool> {
+        match self.0 {}
+    }
+
+    pub fn unlock(&self) -> io::Result<()> {
+        match self.0 {}
+    }
+
     pub fn truncate(&self, _size: u64) -> io::Result<()> {
         match self.0 {}
     }
@@ -7,6 +7,7 @@
 use crate::sys::pipe::AnonPipe;
 use crate::sys::{unsupported, Void};
 use crate::sys_common::process::{CommandEnv, CommandEnvs};
+use crate::time::Duration;
 
 pub use crate::ffi::OsString as EnvKey;
 
@@ -164,6 +165,10 @@ pub fn wait(&mut self) -> io::Result<ExitStatus> {
     pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
         match self.0 {}
     }
+
+    pub fn wait_timeout(&mut self, _timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        match self.0 {}
+    }
 }
 
 pub struct CommandArgs<'a> {
@@ -1,9 +1,11 @@
+use crate::cmp;
 use crate::fmt;
 use crate::io::{self, Error, ErrorKind};
 use crate::sys;
 use crate::sys::cvt;
 use crate::sys::process::process_common::*;
 use crate::sys_common::thread;
+use crate::time::{Duration, Instant};
 use libc::RTP_ID;
 use libc::{self, c_char, c_int};
 
@@ -170,6 +172,27 @@ pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
             Ok(Some(ExitStatus::new(status)))
         }
     }
+
+    pub fn wait_timeout(&mut self, timeout: Duration) -> io::Result<Option<ExitStatus>> {
+        // There is no way to wait for a child with a timeout, so poll,
+        // backing off so that long waits don't keep the thread busy.
+        let deadline = match Instant::now().checked_add(timeout) {
+            Some(deadline) => deadline,
+            None => return self.wait().map(Some),
+        };
+        let mut delay = Duration::from_millis(1);
+        loop {
+            if let Some(status) = self.try_wait()? {
+                return Ok(Some(status));
+            }
+            let now = Instant::now();
+            if deadline <= now {
+                return Ok(None);
+            }
+            crate::thread::sleep(cmp::min(delay, deadline - now));
+            delay = cmp::min(delay * 2, Duration::from_millis(50));
+        }
+    }
 }
 
 /// Unix exit statuses
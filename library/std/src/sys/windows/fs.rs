@@ -15,6 +15,8 @@
 
 use super::to_u16s;
 
+pub use crate::sys_common::fs::Dir;
+
 pub struct File {
     handle: Handle,
 }
@@ -308,6 +310,58 @@ pub fn datasync(&self) -> io::Result<()> {
         self.fsync()
     }
 
+    pub fn lock_shared(&self) -> io::Result<()> {
+        self.lock(0).map(drop)
+    }
+
+    pub fn lock_exclusive(&self) -> io::Result<()> {
+        self.lock(c::LOCKFILE_EXCLUSIVE_LOCK).map(drop)
+    }
+
+    pub fn try_lock_shared(&self) -> io::Result<bool> {
+        self.lock(c::LOCKFILE_FAIL_IMMEDIATELY)
+    }
+
+    pub fn try_lock_exclusive(&self) -> io::Result<bool> {
+        self.lock(c::LOCKFILE_EXCLUSIVE_LOCK | c::LOCKFILE_FAIL_IMMEDIATELY)
+    }
+
+    pub fn unlock(&self) -> io::Result<()> {
+        // `LockFileEx` locks stack, and `UnlockFile` releases one of them, so
+        // unlock until none is left. Like `flock`, unlocking a file that isn't
+        // locked is not an error.
+        loop {
+            let handle = self.handle.raw();
+            match cvt(unsafe { c::UnlockFile(handle, 0, 0, c::DWORD::MAX, c::DWORD::MAX) }) {
+                Ok(_) => {}
+                Err(e) if e.raw_os_error() == Some(c::ERROR_NOT_LOCKED as i32) => return Ok(()),
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // Locks the whole file, including any part of it that doesn't exist yet,
+    // returning `Ok(false)` if `LOCKFILE_FAIL_IMMEDIATELY` was passed and
+    // another handle holds a conflicting lock.
+    fn lock(&self, flags: c::DWORD) -> io::Result<bool> {
+        let mut overlapped: c::OVERLAPPED = unsafe { mem::zeroed() };
+        let res = cvt(unsafe {
+            c::LockFileEx(
+                self.handle.raw(),
+                flags,
+                0,
+                c::DWORD::MAX,
+                c::DWORD::MAX,
+                &mut overlapped,
+            )
+        });
+        match res {
+            Ok(_) => Ok(true),
+            Err(e) if e.raw_os_error() == Some(c::ERROR_LOCK_VIOLATION as i32) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
     pub fn truncate(&self, size: u64) -> io::Result<()> {
         let mut info = c::FILE_END_OF_FILE_INFO { EndOfFile: size as c::LARGE_INTEGER };
         let size = mem::size_of_val(&info);
@@ -2,7 +2,8 @@
 
 use crate::fs;
 use crate::io::{self, Error, ErrorKind};
-use crate::path::Path;
+use crate::path::{Path, PathBuf};
+use crate::sys::fs as fs_imp;
 
 pub(crate) const NOT_FILE_ERROR: Error = Error::new_const(
     ErrorKind::InvalidInput,
@@ -41,3 +42,61 @@ fn remove_dir_all_recursive(path: &Path) -> io::Result<()> {
     }
     fs::remove_dir(path)
 }
+
+/// A directory handle emulated with paths, for platforms without the `*at`
+/// family of functions. Unlike a real handle it is only a path, so it doesn't
+/// follow the directory when it is renamed, and doesn't protect against one
+/// of its ancestors being replaced.
+#[derive(Debug)]
+pub struct Dir {
+    root: PathBuf,
+}
+
+impl Dir {
+    pub fn open(path: &Path) -> io::Result<Dir> {
+        if !fs_imp::stat(path)?.file_type().is_dir() {
+            return Err(Error::new_const(ErrorKind::Other, &"not a directory"));
+        }
+        Ok(Dir { root: path.to_path_buf() })
+    }
+
+    pub fn open_file(&self, path: &Path, opts: &fs_imp::OpenOptions) -> io::Result<fs_imp::File> {
+        fs_imp::File::open(&self.root.join(path), opts)
+    }
+
+    pub fn open_dir(&self, path: &Path) -> io::Result<Dir> {
+        Dir::open(&self.root.join(path))
+    }
+
+    pub fn create_dir(&self, path: &Path) -> io::Result<()> {
+        fs_imp::DirBuilder::new().mkdir(&self.root.join(path))
+    }
+
+    pub fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs_imp::unlink(&self.root.join(path))
+    }
+
+    pub fn remove_dir(&self, path: &Path) -> io::Result<()> {
+        fs_imp::rmdir(&self.root.join(path))
+    }
+
+    pub fn rename(&self, from: &Path, to_dir: &Dir, to: &Path) -> io::Result<()> {
+        fs_imp::rename(&self.root.join(from), &to_dir.root.join(to))
+    }
+
+    pub fn stat(&self, path: &Path) -> io::Result<fs_imp::FileAttr> {
+        fs_imp::stat(&self.root.join(path))
+    }
+
+    pub fn lstat(&self, path: &Path) -> io::Result<fs_imp::FileAttr> {
+        fs_imp::lstat(&self.root.join(path))
+    }
+
+    pub fn read_dir(&self) -> io::Result<fs_imp::ReadDir> {
+        fs_imp::readdir(&self.root)
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
@@ -0,0 +1,261 @@
+//! Module `baseline` contains everything related to benchmark baselines:
+//! - Saving the summaries of a benchmark run under a name (`--save-baseline`).
+//! - Loading them back and comparing a later run against them (`--baseline`).
+
+use std::collections::BTreeMap;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, prelude::Write};
+use std::path::{Component, Path, PathBuf};
+
+use super::stats::{self, Summary};
+
+/// Environment variable for overriding the directory baselines are stored in.
+pub const BASELINE_DIR_ENV_NAME: &str = "RUST_TEST_BASELINE_DIR";
+
+/// Significance level below which a slowdown is considered real rather than noise.
+pub const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// Default minimum slowdown, in percent, for a benchmark to count as regressed.
+pub const DEFAULT_THRESHOLD_PCT: f64 = 5.0;
+
+/// First line of every baseline file, used to reject files in an unknown format.
+const FILE_HEADER: &str = "# libtest benchmark baseline v1";
+
+/// Structure with parameters for saving and comparing benchmark baselines.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BaselineOptions {
+    /// Name to save the results of this run under.
+    pub save: Option<String>,
+    /// Name of a previously saved baseline to compare this run against.
+    pub compare: Option<String>,
+    /// Minimum statistically significant slowdown, in percent, that makes the run fail.
+    pub threshold_pct: f64,
+}
+
+/// The saved summary of a single benchmark.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BaselineEntry {
+    pub summary: Summary,
+    /// Number of samples the summary was computed from.
+    pub samples: usize,
+}
+
+impl BaselineEntry {
+    pub fn new(summary: Summary, samples: usize) -> Self {
+        Self { summary, samples }
+    }
+
+    /// Compares a new measurement of this benchmark against the saved one.
+    pub fn compare(&self, new: &BaselineEntry, threshold_pct: f64) -> BenchComparison {
+        let (old_mean, new_mean) = (self.summary.mean, new.summary.mean);
+        let change_pct =
+            if old_mean == 0.0 { 0.0 } else { (new_mean - old_mean) / old_mean * 100.0 };
+        let p_value = if self.samples > 1 && new.samples > 1 {
+            stats::welch_t_test(&self.summary, self.samples, &new.summary, new.samples)
+        } else {
+            1.0
+        };
+        let regressed = p_value < SIGNIFICANCE_LEVEL && change_pct > threshold_pct;
+        BenchComparison { old: *self, new: *new, change_pct, p_value, regressed }
+    }
+}
+
+/// Result of comparing a benchmark against its saved baseline.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct BenchComparison {
+    pub old: BaselineEntry,
+    pub new: BaselineEntry,
+    /// Change of the mean time per iteration, in percent. Positive means slower.
+    pub change_pct: f64,
+    /// p-value of the hypothesis that the benchmark became slower.
+    pub p_value: f64,
+    /// Whether the slowdown is both significant and above the configured threshold.
+    pub regressed: bool,
+}
+
+impl fmt::Display for BenchComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:+.2}% ({:.0} -> {:.0} ns/iter, p = {:.3})",
+            self.change_pct, self.old.summary.mean, self.new.summary.mean, self.p_value
+        )?;
+        if self.regressed {
+            f.write_str(" REGRESSED")?;
+        } else if self.p_value < SIGNIFICANCE_LEVEL {
+            f.write_str(" (slower, below threshold)")?;
+        }
+        Ok(())
+    }
+}
+
+/// A named set of benchmark summaries, as saved by `--save-baseline`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Baseline {
+    entries: BTreeMap<String, BaselineEntry>,
+}
+
+impl Baseline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, bench_name: &str, entry: BaselineEntry) {
+        self.entries.insert(bench_name.to_owned(), entry);
+    }
+
+    pub fn get(&self, bench_name: &str) -> Option<&BaselineEntry> {
+        self.entries.get(bench_name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Checks that `name` can be used as a baseline name, i.e. that it is a
+    /// single file name that stays inside the baseline directory once joined
+    /// onto it.
+    pub fn check_name(name: &str) -> Result<(), String> {
+        let mut components = Path::new(name).components();
+        let is_file_name = match (components.next(), components.next()) {
+            (Some(Component::Normal(c)), None) => c == name,
+            _ => false,
+        };
+        if !is_file_name || name.contains(|c| c == '/' || c == '\\') {
+            return Err(format!(
+                "invalid baseline name `{}`: must be a file name without path separators",
+                name
+            ));
+        }
+        Ok(())
+    }
+
+    /// Returns the file the baseline with the given name is stored in.
+    ///
+    /// Baselines live in the directory named by `RUST_TEST_BASELINE_DIR`, or in a
+    /// `bench-baselines` directory next to the test binary by default, and are
+    /// keyed by both the baseline name and the name of the test binary.
+    pub fn path(name: &str) -> io::Result<PathBuf> {
+        Self::check_name(name).map_err(|msg| io::Error::new(io::ErrorKind::InvalidInput, msg))?;
+        let current_exe = env::current_exe()?;
+        let dir = match env::var_os(BASELINE_DIR_ENV_NAME) {
+            Some(dir) => PathBuf::from(dir),
+            None => current_exe
+                .parent()
+                .map(|dir| dir.join("bench-baselines"))
+                .unwrap_or_else(|| PathBuf::from("bench-baselines")),
+        };
+        let binary = current_exe.file_stem().map(|s| s.to_owned()).unwrap_or_default();
+        let mut file_name = binary;
+        file_name.push(".txt");
+        Ok(dir.join(name).join(file_name))
+    }
+
+    /// Loads the baseline saved under `name`.
+    pub fn load(name: &str) -> io::Result<Self> {
+        let path = Self::path(name)?;
+        let contents = fs::read_to_string(&path).map_err(|e| {
+            io::Error::new(
+                e.kind(),
+                format!("failed to read baseline `{}` from {}: {}", name, path.display(), e),
+            )
+        })?;
+        Self::parse(&contents).map_err(|msg| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("invalid baseline file {}: {}", path.display(), msg),
+            )
+        })
+    }
+
+    /// Saves this baseline under `name`, replacing any previous baseline of that name.
+    pub fn save(&self, name: &str) -> io::Result<()> {
+        let path = Self::path(name)?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut contents = Vec::new();
+        self.write_to(&mut contents)?;
+        fs::write(path, contents)
+    }
+
+    /// Writes the baseline with one tab-separated line per benchmark: its name,
+    /// the number of samples and then the fields of its `Summary`.
+    ///
+    /// Floating point values are written with `{:?}` so that they round-trip exactly.
+    pub(crate) fn write_to(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "{}", FILE_HEADER)?;
+        for (name, entry) in &self.entries {
+            let s = &entry.summary;
+            let (q1, q2, q3) = s.quartiles;
+            write!(out, "{}\t{}", name, entry.samples)?;
+            for value in &[
+                s.sum,
+                s.min,
+                s.max,
+                s.mean,
+                s.median,
+                s.var,
+                s.std_dev,
+                s.std_dev_pct,
+                s.median_abs_dev,
+                s.median_abs_dev_pct,
+                q1,
+                q2,
+                q3,
+                s.iqr,
+            ] {
+                write!(out, "\t{:?}", value)?;
+            }
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn parse(contents: &str) -> Result<Self, String> {
+        const FIELDS: usize = 16;
+
+        let mut lines = contents.lines();
+        if lines.next() != Some(FILE_HEADER) {
+            return Err("unrecognized file header".to_owned());
+        }
+
+        let mut baseline = Self::new();
+        for (i, line) in lines.enumerate().filter(|(_, line)| !line.is_empty()) {
+            let line_no = i + 2;
+            // The benchmark name comes first and is the only field that might
+            // itself contain a tab, so split from the right.
+            let mut fields: Vec<&str> = line.rsplitn(FIELDS, '\t').collect();
+            if fields.len() != FIELDS {
+                return Err(format!("line {}: expected {} fields", line_no, FIELDS));
+            }
+            fields.reverse();
+
+            let samples =
+                fields[1].parse::<usize>().map_err(|e| format!("line {}: {}", line_no, e))?;
+            let values = fields[2..]
+                .iter()
+                .map(|field| field.parse::<f64>())
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("line {}: {}", line_no, e))?;
+            let summary = Summary {
+                sum: values[0],
+                min: values[1],
+                max: values[2],
+                mean: values[3],
+                median: values[4],
+                var: values[5],
+                std_dev: values[6],
+                std_dev_pct: values[7],
+                median_abs_dev: values[8],
+                median_abs_dev_pct: values[9],
+                quartiles: (values[10], values[11], values[12]),
+                iqr: values[13],
+            };
+            baseline.insert(fields[0], BaselineEntry { summary, samples });
+        }
+        Ok(baseline)
+    }
+}
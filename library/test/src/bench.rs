@@ -16,6 +16,9 @@
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
 
+/// Number of samples the `Summary` of an auto-tuned benchmark is computed from.
+const SAMPLE_COUNT: usize = 50;
+
 /// Manager of the benchmarking runs.
 ///
 /// This is fed into functions marked with `#[bench]` to allow for
@@ -55,6 +58,8 @@ pub fn bench<F>(&mut self, mut f: F) -> Option<stats::Summary>
 pub struct BenchSamples {
     pub ns_iter_summ: stats::Summary,
     pub mb_s: usize,
+    /// Number of samples `ns_iter_summ` was computed from.
+    pub samples: usize,
 }
 
 pub fn fmt_bench_samples(bs: &BenchSamples) -> String {
@@ -132,7 +137,7 @@ pub fn iter<T, F>(inner: &mut F) -> stats::Summary
     n = cmp::max(1, n);
 
     let mut total_run = Duration::new(0, 0);
-    let samples: &mut [f64] = &mut [0.0_f64; 50];
+    let samples: &mut [f64] = &mut [0.0_f64; SAMPLE_COUNT];
     loop {
         let loop_start = Instant::now();
 
@@ -208,14 +213,18 @@ pub fn benchmark<F>(
             let ns_iter = cmp::max(ns_iter_summ.median as u64, 1);
             let mb_s = bs.bytes * 1000 / ns_iter;
 
-            let bs = BenchSamples { ns_iter_summ, mb_s: mb_s as usize };
+            let bs = BenchSamples { ns_iter_summ, mb_s: mb_s as usize, samples: SAMPLE_COUNT };
             TestResult::TrBench(bs)
         }
         Ok(None) => {
             // iter not called, so no data.
             // FIXME: error in this case?
             let samples: &mut [f64] = &mut [0.0_f64; 1];
-            let bs = BenchSamples { ns_iter_summ: stats::Summary::new(samples), mb_s: 0 };
+            let bs = BenchSamples {
+                ns_iter_summ: stats::Summary::new(samples),
+                mb_s: 0,
+                samples: samples.len(),
+            };
             TestResult::TrBench(bs)
         }
         Err(_) => TestResult::TrFailed,
@@ -2,9 +2,11 @@
 
 use std::env;
 use std::path::PathBuf;
+use std::time::Duration;
 
+use super::baseline::{Baseline, BaselineOptions, DEFAULT_THRESHOLD_PCT};
 use super::helpers::isatty;
-use super::options::{ColorConfig, Options, OutputFormat, RunIgnored};
+use super::options::{ColorConfig, Options, OutputFormat, RunIgnored, Shard};
 use super::time::TestTimeOptions;
 
 #[derive(Debug)]
@@ -23,7 +25,12 @@ pub struct TestOpts {
     pub format: OutputFormat,
     pub test_threads: Option<usize>,
     pub skip: Vec<String>,
+    pub shard: Option<Shard>,
     pub time_options: Option<TestTimeOptions>,
+    pub test_timeout: Option<Duration>,
+    pub baseline_options: Option<BaselineOptions>,
+    pub shuffle: bool,
+    pub shuffle_seed: Option<u64>,
     pub options: Options,
 }
 
@@ -80,6 +87,19 @@ fn optgroups() -> getopts::Options {
              Alias to --format=terse",
         )
         .optflag("", "exact", "Exactly match filters rather than by substring")
+        .optopt(
+            "",
+            "shard-index",
+            "Only run the tests of shard N out of the number given by
+            --shard-count (zero-based)",
+            "N",
+        )
+        .optopt(
+            "",
+            "shard-count",
+            "Split the tests into M disjoint shards; requires --shard-index",
+            "M",
+        )
         .optopt(
             "",
             "color",
@@ -95,8 +115,9 @@ fn optgroups() -> getopts::Options {
             "Configure formatting of output:
             pretty = Print verbose output;
             terse  = Display one character per test;
-            json   = Output a json document",
-            "pretty|terse|json",
+            json   = Output a json document
+            junit  = Output a JUnit document",
+            "pretty|terse|json|junit",
         )
         .optflag("", "show-output", "Show captured stdout of successful tests")
         .optopt(
@@ -137,6 +158,41 @@ fn optgroups() -> getopts::Options {
 
             `CRITICAL_TIME` here means the limit that should not be exceeded by test.
             ",
+        )
+        .optopt(
+            "",
+            "test-timeout",
+            "Kill tests that run for longer than SECS seconds and report
+            them as failed. Requires tests to run in subprocesses
+            (i.e. with `-Z panic-abort-tests`).",
+            "SECS",
+        )
+        .optopt(
+            "",
+            "save-baseline",
+            "Save the results of the benchmarks of this run under NAME",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "baseline",
+            "Compare the results of the benchmarks of this run with those
+            saved under NAME by a previous --save-baseline run",
+            "NAME",
+        )
+        .optopt(
+            "",
+            "baseline-threshold",
+            "Fail the run if a benchmark became statistically significantly
+            slower than its --baseline by more than PCT percent (default: 5)",
+            "PCT",
+        )
+        .optflag("", "shuffle", "Run tests in random order")
+        .optopt(
+            "",
+            "shuffle-seed",
+            "Run tests in random order; seed the random number generator with SEED",
+            "SEED",
         );
     opts
 }
@@ -226,12 +282,17 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
     let force_run_in_process = unstable_optflag!(matches, allow_unstable, "force-run-in-process");
     let exclude_should_panic = unstable_optflag!(matches, allow_unstable, "exclude-should-panic");
     let time_options = get_time_options(&matches, allow_unstable)?;
+    let test_timeout = get_test_timeout(&matches, allow_unstable)?;
+    let baseline_options = get_baseline_options(&matches, allow_unstable)?;
+    let shuffle = get_shuffle(&matches, allow_unstable)?;
+    let shuffle_seed = get_shuffle_seed(&matches, allow_unstable)?;
 
     let include_ignored = matches.opt_present("include-ignored");
     let quiet = matches.opt_present("quiet");
     let exact = matches.opt_present("exact");
     let list = matches.opt_present("list");
     let skip = matches.opt_strs("skip");
+    let shard = get_shard(&matches, allow_unstable)?;
 
     let bench_benchmarks = matches.opt_present("bench");
     let run_tests = !bench_benchmarks || matches.opt_present("test");
@@ -244,6 +305,13 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
     let color = get_color_config(&matches)?;
     let format = get_format(&matches, quiet, allow_unstable)?;
 
+    // The JUnit report always carries per-test timings, so measure them
+    // even if `--report-time` wasn't requested.
+    let time_options = match (time_options, format) {
+        (None, OutputFormat::Junit) => Some(TestTimeOptions::new_from_env(false, false)),
+        (time_options, _) => time_options,
+    };
+
     let options = Options::new().display_output(matches.opt_present("show-output"));
 
     let test_opts = TestOpts {
@@ -261,7 +329,12 @@ fn parse_opts_impl(matches: getopts::Matches) -> OptRes {
         format,
         test_threads,
         skip,
+        shard,
         time_options,
+        test_timeout,
+        baseline_options,
+        shuffle,
+        shuffle_seed,
         options,
     };
 
@@ -302,6 +375,151 @@ fn get_time_options(
     Ok(options)
 }
 
+fn get_shard(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<Option<Shard>> {
+    let parse = |name: &str| -> OptPartRes<Option<usize>> {
+        match matches.opt_str(name) {
+            Some(_) if !allow_unstable => Err(format!(
+                "The \"{}\" flag is only accepted on the nightly compiler with -Z unstable-options",
+                name
+            )),
+            Some(n_str) => match n_str.parse::<usize>() {
+                Ok(n) => Ok(Some(n)),
+                Err(e) => Err(format!("argument for --{} must be a number (error: {})", name, e)),
+            },
+            None => Ok(None),
+        }
+    };
+
+    let shard = match (parse("shard-index")?, parse("shard-count")?) {
+        (None, None) => None,
+        (Some(_), None) | (None, Some(_)) => {
+            return Err("the options --shard-index and --shard-count must be used together".into());
+        }
+        (Some(_), Some(0)) => return Err("argument for --shard-count must not be 0".into()),
+        (Some(index), Some(count)) if index >= count => {
+            return Err(format!(
+                "argument for --shard-index must be less than --shard-count ({} >= {})",
+                index, count
+            ));
+        }
+        (Some(index), Some(count)) => Some(Shard { index, count }),
+    };
+
+    Ok(shard)
+}
+
+fn get_test_timeout(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<Duration>> {
+    let test_timeout = match matches.opt_str("test-timeout") {
+        Some(_) if !allow_unstable => {
+            return Err("The \"test-timeout\" flag is only accepted on the nightly compiler \
+                        with -Z unstable-options"
+                .into());
+        }
+        Some(secs_str) => match secs_str.parse::<u64>() {
+            Ok(0) => return Err("argument for --test-timeout must not be 0".to_string()),
+            Ok(secs) => Some(Duration::from_secs(secs)),
+            Err(e) => {
+                return Err(format!(
+                    "argument for --test-timeout must be a number > 0 \
+                     (error: {})",
+                    e
+                ));
+            }
+        },
+        None => None,
+    };
+
+    Ok(test_timeout)
+}
+
+// Gets the CLI options associated with benchmark baselines.
+fn get_baseline_options(
+    matches: &getopts::Matches,
+    allow_unstable: bool,
+) -> OptPartRes<Option<BaselineOptions>> {
+    let save = matches.opt_str("save-baseline");
+    let compare = matches.opt_str("baseline");
+    let threshold = matches.opt_str("baseline-threshold");
+
+    if save.is_none() && compare.is_none() {
+        if threshold.is_some() {
+            return Err("the option --baseline-threshold requires --baseline".into());
+        }
+        return Ok(None);
+    }
+    if !allow_unstable {
+        return Err("The \"save-baseline\" and \"baseline\" flags are only accepted on \
+                    the nightly compiler with -Z unstable-options"
+            .into());
+    }
+    for name in save.iter().chain(compare.iter()) {
+        Baseline::check_name(name)?;
+    }
+
+    let threshold_pct = match threshold {
+        Some(pct_str) => match pct_str.parse::<f64>() {
+            Ok(pct) if pct >= 0.0 => pct,
+            _ => {
+                return Err(format!(
+                    "argument for --baseline-threshold must be a non-negative number (was {})",
+                    pct_str
+                ));
+            }
+        },
+        None => DEFAULT_THRESHOLD_PCT,
+    };
+
+    Ok(Some(BaselineOptions { save, compare, threshold_pct }))
+}
+
+fn get_shuffle(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<bool> {
+    let mut shuffle = unstable_optflag!(matches, allow_unstable, "shuffle");
+    if !shuffle && allow_unstable {
+        shuffle = match env::var("RUST_TEST_SHUFFLE") {
+            Ok(val) => &val != "0",
+            Err(_) => false,
+        };
+    }
+
+    Ok(shuffle)
+}
+
+fn get_shuffle_seed(matches: &getopts::Matches, allow_unstable: bool) -> OptPartRes<Option<u64>> {
+    let mut shuffle_seed = match matches.opt_str("shuffle-seed") {
+        Some(n_str) => match n_str.parse::<u64>() {
+            Ok(_) if !allow_unstable => {
+                return Err("The \"shuffle-seed\" flag is only accepted on the nightly compiler \
+                            with -Z unstable-options"
+                    .into());
+            }
+            Ok(n) => Some(n),
+            Err(e) => {
+                return Err(format!(
+                    "argument for --shuffle-seed must be a number \
+                     (error: {})",
+                    e
+                ));
+            }
+        },
+        None => None,
+    };
+
+    if shuffle_seed.is_none() && allow_unstable {
+        shuffle_seed = match env::var("RUST_TEST_SHUFFLE_SEED") {
+            Ok(val) => match val.parse::<u64>() {
+                Ok(n) => Some(n),
+                Err(_) => panic!("RUST_TEST_SHUFFLE_SEED is `{}`, should be a number.", val),
+            },
+            Err(_) => None,
+        };
+    }
+
+    Ok(shuffle_seed)
+}
+
 fn get_test_threads(matches: &getopts::Matches) -> OptPartRes<Option<usize>> {
     let test_threads = match matches.opt_str("test-threads") {
         Some(n_str) => match n_str.parse::<usize>() {
@@ -336,10 +554,16 @@ fn get_format(
             }
             OutputFormat::Json
         }
+        Some("junit") => {
+            if !allow_unstable {
+                return Err("The \"junit\" format is only accepted on the nightly compiler".into());
+            }
+            OutputFormat::Junit
+        }
 
         Some(v) => {
             return Err(format!(
-                "argument for --format must be pretty, terse, or json (was \
+                "argument for --format must be pretty, terse, json or junit (was \
                  {})",
                 v
             ));
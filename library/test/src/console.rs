@@ -6,11 +6,12 @@
 use std::time::Instant;
 
 use super::{
+    baseline::{Baseline, BaselineEntry, BaselineOptions, BenchComparison},
     bench::fmt_bench_samples,
     cli::TestOpts,
     event::{CompletedTest, TestEvent},
     filter_tests,
-    formatters::{JsonFormatter, OutputFormatter, PrettyFormatter, TerseFormatter},
+    formatters::{JsonFormatter, JunitFormatter, OutputFormatter, PrettyFormatter, TerseFormatter},
     helpers::{concurrency::get_concurrency, metrics::MetricMap},
     options::{Options, OutputFormat},
     run_tests,
@@ -55,6 +56,12 @@ pub struct ConsoleTestState {
     pub failures: Vec<(TestDesc, Vec<u8>)>,
     pub not_failures: Vec<(TestDesc, Vec<u8>)>,
     pub time_failures: Vec<(TestDesc, Vec<u8>)>,
+    /// Saved baseline the benchmarks of this run are compared against.
+    pub baseline: Option<Baseline>,
+    /// Summaries of the benchmarks of this run, to be saved as a new baseline.
+    pub bench_results: Baseline,
+    pub bench_comparisons: Vec<(TestDesc, BenchComparison)>,
+    pub regressed: usize,
     pub options: Options,
 }
 
@@ -65,6 +72,14 @@ pub fn new(opts: &TestOpts) -> io::Result<ConsoleTestState> {
             None => None,
         };
 
+        let baseline = match opts.baseline_options {
+            Some(ref baseline_options) => match baseline_options.compare {
+                Some(ref name) => Some(Baseline::load(name)?),
+                None => None,
+            },
+            None => None,
+        };
+
         Ok(ConsoleTestState {
             log_out,
             total: 0,
@@ -79,6 +94,10 @@ pub fn new(opts: &TestOpts) -> io::Result<ConsoleTestState> {
             failures: Vec::new(),
             not_failures: Vec::new(),
             time_failures: Vec::new(),
+            baseline,
+            bench_results: Baseline::new(),
+            bench_comparisons: Vec::new(),
+            regressed: 0,
             options: opts.options,
         })
     }
@@ -115,6 +134,7 @@ pub fn write_log_result(
                     TestResult::TrAllowedFail => "failed (allowed)".to_owned(),
                     TestResult::TrBench(ref bs) => fmt_bench_samples(bs),
                     TestResult::TrTimedFail => "failed (time limit exceeded)".to_owned(),
+                    TestResult::TrTimedOut => "failed (timed out)".to_owned(),
                 },
                 test.name,
             )
@@ -182,7 +202,7 @@ fn plural(count: u32, s: &str) -> String {
 }
 
 // Updates `ConsoleTestState` depending on result of the test execution.
-fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest) {
+fn handle_test_result(opts: &TestOpts, st: &mut ConsoleTestState, completed_test: CompletedTest) {
     let test = completed_test.desc;
     let stdout = completed_test.stdout;
     match completed_test.result {
@@ -198,7 +218,20 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
                 bs.ns_iter_summ.median,
                 bs.ns_iter_summ.max - bs.ns_iter_summ.min,
             );
-            st.measured += 1
+            st.measured += 1;
+
+            let entry = BaselineEntry::new(bs.ns_iter_summ, bs.samples);
+            st.bench_results.insert(test.name.as_slice(), entry);
+            if let (Some(baseline), Some(baseline_options)) = (&st.baseline, &opts.baseline_options)
+            {
+                if let Some(old) = baseline.get(test.name.as_slice()) {
+                    let comparison = old.compare(&entry, baseline_options.threshold_pct);
+                    if comparison.regressed {
+                        st.regressed += 1;
+                    }
+                    st.bench_comparisons.push((test, comparison));
+                }
+            }
         }
         TestResult::TrFailed => {
             st.failed += 1;
@@ -214,20 +247,29 @@ fn handle_test_result(st: &mut ConsoleTestState, completed_test: CompletedTest)
             st.failed += 1;
             st.time_failures.push((test, stdout));
         }
+        TestResult::TrTimedOut => {
+            st.failed += 1;
+            let mut stdout = stdout;
+            stdout.extend_from_slice(
+                b"note: test exceeded the `--test-timeout` limit and was killed\n",
+            );
+            st.failures.push((test, stdout));
+        }
     }
 }
 
 // Handler for events that occur during test execution.
 // It is provided as a callback to the `run_tests` function.
 fn on_test_event(
+    opts: &TestOpts,
     event: &TestEvent,
     st: &mut ConsoleTestState,
     out: &mut dyn OutputFormatter,
 ) -> io::Result<()> {
     match (*event).clone() {
-        TestEvent::TeFiltered(ref filtered_tests) => {
+        TestEvent::TeFiltered(ref filtered_tests, shuffle_seed) => {
             st.total = filtered_tests.len();
-            out.write_run_start(filtered_tests.len())?;
+            out.write_run_start(filtered_tests.len(), shuffle_seed)?;
         }
         TestEvent::TeFilteredOut(filtered_out) => {
             st.filtered_out = filtered_out;
@@ -242,7 +284,7 @@ fn on_test_event(
 
             st.write_log_result(test, result, exec_time.as_ref())?;
             out.write_result(test, result, exec_time.as_ref(), &*stdout, st)?;
-            handle_test_result(st, completed_test);
+            handle_test_result(opts, st, completed_test);
         }
     }
 
@@ -277,6 +319,7 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
             Box::new(TerseFormatter::new(output, opts.use_color(), max_name_len, is_multithreaded))
         }
         OutputFormat::Json => Box::new(JsonFormatter::new(output)),
+        OutputFormat::Junit => Box::new(JunitFormatter::new(output)),
     };
     let mut st = ConsoleTestState::new(opts)?;
 
@@ -286,11 +329,19 @@ pub fn run_tests_console(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> io::Resu
     let is_instant_supported = !cfg!(target_arch = "wasm32") && !cfg!(miri);
 
     let start_time = is_instant_supported.then(Instant::now);
-    run_tests(opts, tests, |x| on_test_event(&x, &mut st, &mut *out))?;
+    run_tests(opts, tests, |x| on_test_event(opts, &x, &mut st, &mut *out))?;
     st.exec_time = start_time.map(|t| TestSuiteExecTime(t.elapsed()));
 
     assert!(st.current_test_count() == st.total);
 
+    if let Some(BaselineOptions { save: Some(ref name), .. }) = opts.baseline_options {
+        // Don't clobber a saved baseline with the results of a run that
+        // didn't measure any benchmarks, e.g. one without `--bench`.
+        if !st.bench_results.is_empty() {
+            st.bench_results.save(name)?;
+        }
+    }
+
     out.write_run_finish(&st)
 }
 
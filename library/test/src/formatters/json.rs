@@ -60,10 +60,15 @@ fn write_event(
 }
 
 impl<T: Write> OutputFormatter for JsonFormatter<T> {
-    fn write_run_start(&mut self, test_count: usize) -> io::Result<()> {
+    fn write_run_start(&mut self, test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        let shuffle_seed_json = if let Some(shuffle_seed) = shuffle_seed {
+            format!(r#", "shuffle_seed": {}"#, shuffle_seed)
+        } else {
+            String::new()
+        };
         self.writeln_message(&*format!(
-            r#"{{ "type": "suite", "event": "started", "test_count": {} }}"#,
-            test_count
+            r#"{{ "type": "suite", "event": "started", "test_count": {}{} }}"#,
+            test_count, shuffle_seed_json
         ))
     }
 
@@ -106,6 +111,15 @@ fn write_result(
                 Some(r#""reason": "time limit exceeded""#),
             ),
 
+            TestResult::TrTimedOut => self.write_event(
+                "test",
+                desc.name.as_slice(),
+                "failed",
+                exec_time,
+                stdout,
+                Some(r#""reason": "timed out""#),
+            ),
+
             TestResult::TrFailedMsg(ref m) => self.write_event(
                 "test",
                 desc.name.as_slice(),
@@ -162,6 +176,25 @@ fn write_timeout(&mut self, desc: &TestDesc) -> io::Result<()> {
     }
 
     fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        for &(ref desc, ref comparison) in &state.bench_comparisons {
+            self.writeln_message(&*format!(
+                "{{ \"type\": \"bench_comparison\", \
+                 \"name\": \"{}\", \
+                 \"baseline_mean\": {}, \
+                 \"mean\": {}, \
+                 \"change_pct\": {}, \
+                 \"p_value\": {}, \
+                 \"regressed\": {} }}",
+                EscapedString(desc.name.as_slice()),
+                comparison.old.summary.mean,
+                comparison.new.summary.mean,
+                comparison.change_pct,
+                comparison.p_value,
+                comparison.regressed,
+            ))?;
+        }
+
+        let success = state.failed == 0 && state.regressed == 0;
         self.write_message(&*format!(
             "{{ \"type\": \"suite\", \
              \"event\": \"{}\", \
@@ -171,7 +204,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
              \"ignored\": {}, \
              \"measured\": {}, \
              \"filtered_out\": {}",
-            if state.failed == 0 { "ok" } else { "failed" },
+            if success { "ok" } else { "failed" },
             state.passed,
             state.failed + state.allowed_fail,
             state.allowed_fail,
@@ -180,6 +213,10 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
             state.filtered_out,
         ))?;
 
+        if !state.bench_comparisons.is_empty() {
+            self.write_message(&*format!(", \"regressed\": {}", state.regressed))?;
+        }
+
         if let Some(ref exec_time) = state.exec_time {
             let time_str = format!(", \"exec_time\": {}", exec_time.0.as_secs_f64());
             self.write_message(&time_str)?;
@@ -187,7 +224,7 @@ fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
 
         self.writeln_message(" }")?;
 
-        Ok(state.failed == 0)
+        Ok(success)
     }
 }
 
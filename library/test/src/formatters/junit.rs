@@ -0,0 +1,268 @@
+use std::io::{self, prelude::Write};
+use std::time::Duration;
+
+use super::OutputFormatter;
+use crate::{
+    console::{ConsoleTestState, OutputLocation},
+    test_result::TestResult,
+    time,
+    types::TestDesc,
+};
+
+/// Formatter emitting a single JUnit XML document for the whole test run.
+///
+/// JUnit requires the aggregated counts to be known when the `<testsuite>`
+/// element is opened, so results are buffered and only written out once the
+/// run has finished.
+pub(crate) struct JunitFormatter<T> {
+    out: OutputLocation<T>,
+    results: Vec<(TestDesc, TestResult, Duration, Vec<u8>)>,
+    shuffle_seed: Option<u64>,
+}
+
+impl<T: Write> JunitFormatter<T> {
+    pub fn new(out: OutputLocation<T>) -> Self {
+        Self { out, results: Vec::new(), shuffle_seed: None }
+    }
+
+    #[cfg(test)]
+    pub fn output_location(&self) -> &OutputLocation<T> {
+        &self.out
+    }
+
+    fn write_message(&mut self, s: &str) -> io::Result<()> {
+        assert!(!s.contains('\n'));
+
+        self.out.write_all(s.as_ref())
+    }
+
+    fn write_system_out(&mut self, stdout: &[u8]) -> io::Result<()> {
+        if stdout.is_empty() {
+            return Ok(());
+        }
+        self.write_message(&*format!(
+            "<system-out>{}</system-out>",
+            CData(&String::from_utf8_lossy(stdout))
+        ))
+    }
+}
+
+impl<T: Write> OutputFormatter for JunitFormatter<T> {
+    fn write_run_start(&mut self, _test_count: usize, shuffle_seed: Option<u64>) -> io::Result<()> {
+        self.shuffle_seed = shuffle_seed;
+        // The XML declaration must be the very first thing in the document.
+        self.write_message("<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+        self.out.write_all(b"\n")
+    }
+
+    fn write_test_start(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // We do not output anything on test start.
+        Ok(())
+    }
+
+    fn write_timeout(&mut self, _desc: &TestDesc) -> io::Result<()> {
+        // We do not output anything on test timeout.
+        Ok(())
+    }
+
+    fn write_result(
+        &mut self,
+        desc: &TestDesc,
+        result: &TestResult,
+        exec_time: Option<&time::TestExecTime>,
+        stdout: &[u8],
+        state: &ConsoleTestState,
+    ) -> io::Result<()> {
+        // Captured output is only kept for failing tests, unless the user
+        // explicitly asked for the output of successful ones as well.
+        let keep_stdout = state.options.display_output
+            || matches!(
+                result,
+                TestResult::TrFailed
+                    | TestResult::TrFailedMsg(_)
+                    | TestResult::TrTimedFail
+                    | TestResult::TrTimedOut
+            );
+        let stdout = if keep_stdout { stdout.to_vec() } else { Vec::new() };
+        let duration = exec_time.map(|t| t.0).unwrap_or_default();
+        self.results.push((desc.clone(), result.clone(), duration, stdout));
+        Ok(())
+    }
+
+    fn write_run_finish(&mut self, state: &ConsoleTestState) -> io::Result<bool> {
+        self.write_message("<testsuites>")?;
+
+        self.write_message(&*format!(
+            "<testsuite name=\"{}\" package=\"test\" id=\"0\" \
+             errors=\"0\" \
+             failures=\"{}\" \
+             tests=\"{}\" \
+             skipped=\"{}\" \
+             time=\"{:.3}\">",
+            XmlEscaped(&suite_name()),
+            state.failed + state.regressed,
+            state.total,
+            state.ignored,
+            state.exec_time.as_ref().map(|t| t.0).unwrap_or_default().as_secs_f64(),
+        ))?;
+        if let Some(shuffle_seed) = self.shuffle_seed {
+            self.write_message(&*format!(
+                "<properties><property name=\"shuffle_seed\" value=\"{}\"/></properties>",
+                shuffle_seed
+            ))?;
+        }
+        for (desc, result, duration, stdout) in std::mem::take(&mut self.results) {
+            let (class_name, test_name) = parse_class_name(&desc);
+            let testcase = format!(
+                "<testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                XmlEscaped(&class_name),
+                XmlEscaped(&test_name),
+                duration.as_secs_f64()
+            );
+            match result {
+                TestResult::TrIgnored => {
+                    self.write_message(&*format!("{}><skipped/></testcase>", testcase))?;
+                }
+
+                TestResult::TrFailed => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_message("<failure type=\"assert\"/>")?;
+                    self.write_system_out(&stdout)?;
+                    self.write_message("</testcase>")?;
+                }
+
+                TestResult::TrFailedMsg(ref m) => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_message(&*format!(
+                        "<failure type=\"assert\" message=\"{}\"/>",
+                        XmlEscaped(m)
+                    ))?;
+                    self.write_system_out(&stdout)?;
+                    self.write_message("</testcase>")?;
+                }
+
+                TestResult::TrTimedFail => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_message(
+                        "<failure type=\"timeout\" message=\"time limit exceeded\"/>",
+                    )?;
+                    self.write_system_out(&stdout)?;
+                    self.write_message("</testcase>")?;
+                }
+
+                TestResult::TrTimedOut => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_message("<failure type=\"timeout\" message=\"timed out\"/>")?;
+                    self.write_system_out(&stdout)?;
+                    self.write_message("</testcase>")?;
+                }
+
+                TestResult::TrBench(ref b) => {
+                    // Benchmarks report the median of a single iteration as their time.
+                    self.write_message(&*format!(
+                        "<testcase classname=\"benchmark::{}\" name=\"{}\" time=\"{:.3}\">",
+                        XmlEscaped(&class_name),
+                        XmlEscaped(&test_name),
+                        b.ns_iter_summ.median / 1_000_000_000.0
+                    ))?;
+                    let comparison = state
+                        .bench_comparisons
+                        .iter()
+                        .find(|(bench, _)| bench.name.as_slice() == desc.name.as_slice());
+                    if let Some((_, comparison)) = comparison.filter(|(_, c)| c.regressed) {
+                        self.write_message(&*format!(
+                            "<failure type=\"regression\" message=\"{}\"/>",
+                            XmlEscaped(&comparison.to_string())
+                        ))?;
+                    }
+                    self.write_system_out(&stdout)?;
+                    self.write_message("</testcase>")?;
+                }
+
+                TestResult::TrOk | TestResult::TrAllowedFail => {
+                    self.write_message(&*format!("{}>", testcase))?;
+                    self.write_system_out(&stdout)?;
+                    self.write_message("</testcase>")?;
+                }
+            }
+        }
+        self.write_message("<system-out/>")?;
+        self.write_message("<system-err/>")?;
+        self.write_message("</testsuite>")?;
+        self.write_message("</testsuites>")?;
+
+        self.out.write_all(b"\n")?;
+
+        Ok(state.failed == 0 && state.regressed == 0)
+    }
+}
+
+/// Name of the `<testsuite>` element: the file stem of the running test binary.
+fn suite_name() -> String {
+    std::env::args_os()
+        .next()
+        .as_ref()
+        .and_then(|arg0| std::path::Path::new(arg0).file_stem())
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "test".to_owned())
+}
+
+/// Splits a test name such as `module::submodule::test` into the JUnit
+/// `classname` (`module::submodule`) and `name` (`test`) attributes.
+fn parse_class_name(desc: &TestDesc) -> (String, String) {
+    match desc.name.as_slice().rsplit_once("::") {
+        Some((class_name, test_name)) => (class_name.to_owned(), test_name.to_owned()),
+        None => ("".to_owned(), desc.name.as_slice().to_owned()),
+    }
+}
+
+/// Characters which are not allowed anywhere in an XML 1.0 document,
+/// not even escaped.
+fn is_invalid_xml_char(c: char) -> bool {
+    matches!(c, '\u{0}'..='\u{8}' | '\u{b}' | '\u{c}' | '\u{e}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}')
+}
+
+/// A formatting utility used to print strings as XML attribute values.
+struct XmlEscaped<'a>(&'a str);
+
+impl std::fmt::Display for XmlEscaped<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&apos;")?,
+                '\t' => f.write_str("&#9;")?,
+                '\n' => f.write_str("&#10;")?,
+                '\r' => f.write_str("&#13;")?,
+                c if is_invalid_xml_char(c) => f.write_str("\u{fffd}")?,
+                c => std::fmt::Write::write_char(f, c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A formatting utility used to embed arbitrary text in a `CDATA` section.
+///
+/// A literal `]]>` would terminate the section early, so it is split across
+/// two sections. Newlines are written as character references so that the
+/// document stays on a single line, like the other machine-readable formatters.
+struct CData<'a>(&'a str);
+
+impl std::fmt::Display for CData<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<![CDATA[")?;
+        for c in self.0.replace("]]>", "]]]]><![CDATA[>").chars() {
+            match c {
+                '\n' => f.write_str("]]>&#10;<![CDATA[")?,
+                '\r' => f.write_str("]]>&#13;<![CDATA[")?,
+                c if is_invalid_xml_char(c) => f.write_str("\u{fffd}")?,
+                c => std::fmt::Write::write_char(f, c)?,
+            }
+        }
+        f.write_str("]]>")
+    }
+}
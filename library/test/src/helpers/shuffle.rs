@@ -0,0 +1,62 @@
+//! Helper module for the `--shuffle` option: picks a seed and deterministically
+//! permutes the tests to be run with it.
+
+use crate::cli::TestOpts;
+use crate::types::{TestDescAndFn, TestId};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Returns the seed the test list should be shuffled with, if shuffling was requested.
+///
+/// An explicit `--shuffle-seed` wins; otherwise `--shuffle` picks a fresh seed
+/// from the current time, which is reported so the order can be replayed.
+pub fn get_shuffle_seed(opts: &TestOpts) -> Option<u64> {
+    opts.shuffle_seed.or_else(|| {
+        if opts.shuffle {
+            Some(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .expect("Failed to get system time")
+                    .as_nanos() as u64,
+            )
+        } else {
+            None
+        }
+    })
+}
+
+/// Permutes `tests` with a Fisher-Yates shuffle driven by `shuffle_seed`.
+///
+/// The same seed applied to the same (filtered) list of tests always yields
+/// the same order, independently of the platform or the standard library's
+/// hasher implementation.
+pub fn shuffle_tests(shuffle_seed: u64, tests: &mut [(TestId, TestDescAndFn)]) {
+    let mut rng = SplitMix64(shuffle_seed);
+    for i in (1..tests.len()).rev() {
+        let j = rng.gen_range(i as u64 + 1) as usize;
+        tests.swap(i, j);
+    }
+}
+
+/// A small, well-known PRNG whose output is fully determined by its seed.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a number in `0..bound`, rejecting samples that would bias the result.
+    fn gen_range(&mut self, bound: u64) -> u64 {
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let x = self.next_u64();
+            if x < zone {
+                return x % bound;
+            }
+        }
+    }
+}
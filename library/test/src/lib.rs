@@ -34,7 +34,7 @@
 // Public reexports
 pub use self::bench::{black_box, Bencher};
 pub use self::console::run_tests_console;
-pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, ShouldPanic};
+pub use self::options::{ColorConfig, Options, OutputFormat, RunIgnored, Shard, ShouldPanic};
 pub use self::types::TestName::*;
 pub use self::types::*;
 pub use self::ColorConfig::*;
@@ -48,7 +48,7 @@ pub mod test {
         cli::{parse_opts, TestOpts},
         filter_tests,
         helpers::metrics::{Metric, MetricMap},
-        options::{Options, RunIgnored, RunStrategy, ShouldPanic},
+        options::{Options, RunIgnored, RunStrategy, Shard, ShouldPanic},
         run_test, test_main, test_main_static,
         test_result::{TestResult, TrFailed, TrFailedMsg, TrIgnored, TrOk},
         time::{TestExecTime, TestTimeOptions},
@@ -60,6 +60,7 @@ pub mod test {
 }
 
 use std::{
+    cmp,
     collections::VecDeque,
     env, io,
     io::prelude::Write,
@@ -71,6 +72,7 @@ pub mod test {
     time::{Duration, Instant},
 };
 
+mod baseline;
 pub mod bench;
 mod cli;
 mod console;
@@ -89,6 +91,7 @@ pub mod test {
 use event::{CompletedTest, TestEvent};
 use helpers::concurrency::get_concurrency;
 use helpers::exit_code::get_exit_code;
+use helpers::shuffle::{get_shuffle_seed, shuffle_tests};
 use options::{Concurrent, RunStrategy};
 use test_result::*;
 use time::TestExecTime;
@@ -112,6 +115,15 @@ pub fn test_main(args: &[String], tests: Vec<TestDescAndFn>, options: Option<Opt
     if let Some(options) = options {
         opts.options = options;
     }
+    // Only subprocesses can be killed, so don't pretend to enforce a timeout
+    // on tests running in this process.
+    if opts.test_timeout.is_some() && (!opts.options.panic_abort || opts.force_run_in_process) {
+        eprintln!(
+            "error: --test-timeout requires tests to run in subprocesses \
+             (with `-Z panic-abort-tests` and without `--force-run-in-process`)"
+        );
+        process::exit(ERROR_EXIT_CODE);
+    }
     if opts.list {
         if let Err(e) = console::list_tests_console(&opts, tests) {
             eprintln!("error: io error when listing tests: {:?}", e);
@@ -245,15 +257,21 @@ struct TimeoutEntry {
 
     let filtered_descs = filtered_tests.iter().map(|t| t.desc.clone()).collect();
 
-    let event = TestEvent::TeFiltered(filtered_descs);
+    let shuffle_seed = get_shuffle_seed(opts);
+
+    let event = TestEvent::TeFiltered(filtered_descs, shuffle_seed);
     notify_about_test_event(event)?;
 
-    let (filtered_tests, filtered_benchs): (Vec<_>, _) = filtered_tests
+    let (mut filtered_tests, filtered_benchs): (Vec<_>, _) = filtered_tests
         .into_iter()
         .enumerate()
         .map(|(i, e)| (TestId(i), e))
         .partition(|(_, e)| matches!(e.testfn, StaticTestFn(_) | DynTestFn(_)));
 
+    if let Some(shuffle_seed) = shuffle_seed {
+        shuffle_tests(shuffle_seed, &mut filtered_tests);
+    }
+
     let concurrency = opts.test_threads.unwrap_or_else(get_concurrency);
 
     let mut remaining = filtered_tests;
@@ -411,6 +429,11 @@ pub fn filter_tests(opts: &TestOpts, tests: Vec<TestDescAndFn>) -> Vec<TestDescA
         filtered.retain(|test| test.desc.should_panic == ShouldPanic::No);
     }
 
+    // Only keep the tests assigned to this runner's shard
+    if let Some(shard) = opts.shard {
+        filtered.retain(|test| shard.contains(test.desc.name.as_slice()));
+    }
+
     // maybe unignore tests
     match opts.run_ignored {
         RunIgnored::Yes => {
@@ -475,6 +498,7 @@ struct TestRunOpts {
         pub nocapture: bool,
         pub concurrency: Concurrent,
         pub time: Option<time::TestTimeOptions>,
+        pub timeout: Option<Duration>,
     }
 
     fn run_test_inner(
@@ -504,6 +528,7 @@ fn run_test_inner(
                 opts.time.is_some(),
                 monitor_ch,
                 opts.time,
+                opts.timeout,
             ),
         };
 
@@ -531,8 +556,13 @@ fn run_test_inner(
         }
     }
 
-    let test_run_opts =
-        TestRunOpts { strategy, nocapture: opts.nocapture, concurrency, time: opts.time_options };
+    let test_run_opts = TestRunOpts {
+        strategy,
+        nocapture: opts.nocapture,
+        concurrency,
+        time: opts.time_options,
+        timeout: opts.test_timeout,
+    };
 
     match testfn {
         DynBenchFn(bencher) => {
@@ -620,6 +650,7 @@ fn spawn_test_subprocess(
     report_time: bool,
     monitor_ch: Sender<CompletedTest>,
     time_opts: Option<time::TestTimeOptions>,
+    timeout: Option<Duration>,
 ) {
     let (result, test_output, exec_time) = (|| {
         let args = env::args().collect::<Vec<_>>();
@@ -627,13 +658,29 @@ fn spawn_test_subprocess(
 
         let mut command = Command::new(current_exe);
         command.env(SECONDARY_TEST_INVOKER_VAR, desc.name.as_slice());
+        // Give the test its own process group, so that a timeout also kills
+        // any processes it spawned, which could otherwise hold its output
+        // pipes open.
+        #[cfg(unix)]
+        {
+            if timeout.is_some() {
+                set_own_process_group(&mut command);
+            }
+        }
         if nocapture {
             command.stdout(process::Stdio::inherit());
             command.stderr(process::Stdio::inherit());
+        } else {
+            command.stdout(process::Stdio::piped());
+            command.stderr(process::Stdio::piped());
         }
 
         let start = report_time.then(Instant::now);
-        let output = match command.output() {
+        let output = match command.spawn() {
+            Ok(child) => wait_with_output_and_timeout(child, timeout),
+            Err(e) => Err(e),
+        };
+        let (output, timed_out) = match output {
             Ok(out) => out,
             Err(e) => {
                 let err = format!("Failed to spawn {} as child for test: {:?}", args[0], e);
@@ -650,6 +697,10 @@ fn spawn_test_subprocess(
         formatters::write_stderr_delimiter(&mut test_output, &desc.name);
         test_output.extend_from_slice(&stderr);
 
+        if timed_out {
+            return (TrTimedOut, test_output, exec_time);
+        }
+
         let result = match (|| -> Result<TestResult, String> {
             let exit_code = get_exit_code(status)?;
             Ok(get_result_from_exit_code(&desc, exit_code, &time_opts, &exec_time))
@@ -668,6 +719,141 @@ fn spawn_test_subprocess(
     monitor_ch.send(message).unwrap();
 }
 
+/// Waits for a test subprocess to finish while collecting its output, like
+/// `Child::wait_with_output`, but kills the child once `timeout` has elapsed.
+///
+/// The returned flag tells whether the child had to be killed. In that case,
+/// output still arriving after `TIMED_OUT_OUTPUT_GRACE` is dropped, since a
+/// surviving descendant of the test may keep the pipes open indefinitely.
+fn wait_with_output_and_timeout(
+    mut child: process::Child,
+    timeout: Option<Duration>,
+) -> io::Result<(process::Output, bool)> {
+    use std::io::Read;
+    use std::sync::mpsc::Receiver;
+
+    const TIMED_OUT_OUTPUT_GRACE: Duration = Duration::from_secs(1);
+
+    struct PipeReader {
+        buf: Arc<Mutex<Vec<u8>>>,
+        done: Receiver<io::Result<()>>,
+    }
+
+    impl PipeReader {
+        fn spawn<R: Read + Send + 'static>(pipe: Option<R>) -> PipeReader {
+            let buf = Arc::new(Mutex::new(Vec::new()));
+            let (tx, done) = channel();
+            match pipe {
+                Some(mut pipe) => {
+                    let buf = buf.clone();
+                    thread::spawn(move || {
+                        let mut chunk = [0; 8192];
+                        let res = loop {
+                            match pipe.read(&mut chunk) {
+                                Ok(0) => break Ok(()),
+                                Ok(n) => buf.lock().unwrap().extend_from_slice(&chunk[..n]),
+                                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                                Err(e) => break Err(e),
+                            }
+                        };
+                        let _ = tx.send(res);
+                    });
+                }
+                None => tx.send(Ok(())).unwrap(),
+            }
+            PipeReader { buf, done }
+        }
+
+        /// Waits for the pipe to be closed, or only until `deadline` if given,
+        /// and returns what was read so far.
+        fn finish(self, deadline: Option<Instant>) -> io::Result<Vec<u8>> {
+            let res = match deadline {
+                None => self.done.recv().ok(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    self.done.recv_timeout(deadline.saturating_duration_since(now)).ok()
+                }
+            };
+            // A reader that panicked or is still blocked just contributes
+            // what it read until then.
+            if let Some(Err(e)) = res {
+                return Err(e);
+            }
+            let mut buf = self.buf.lock().unwrap_or_else(|e| e.into_inner());
+            Ok(std::mem::take(&mut *buf))
+        }
+    }
+
+    // Both pipes have to be drained concurrently, otherwise a chatty test
+    // could block on a full pipe and be reported as hung.
+    let stdout = PipeReader::spawn(child.stdout.take());
+    let stderr = PipeReader::spawn(child.stderr.take());
+
+    let status = match timeout {
+        None => Some(child.wait()?),
+        Some(timeout) => wait_timeout(&mut child, timeout)?,
+    };
+    let (status, timed_out) = match status {
+        Some(status) => (status, false),
+        None => {
+            kill_test_subprocess(&mut child);
+            (child.wait()?, true)
+        }
+    };
+
+    let deadline = if timed_out { Some(Instant::now() + TIMED_OUT_OUTPUT_GRACE) } else { None };
+    let stdout = stdout.finish(deadline)?;
+    let stderr = stderr.finish(deadline)?;
+
+    Ok((process::Output { status, stdout, stderr }, timed_out))
+}
+
+/// Makes the spawned process the leader of a new process group.
+#[cfg(unix)]
+fn set_own_process_group(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    unsafe {
+        command.pre_exec(|| {
+            if libc::setpgid(0, 0) == -1 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+}
+
+/// Polls a test subprocess until it exits or `timeout` has elapsed.
+fn wait_timeout(
+    child: &mut process::Child,
+    timeout: Duration,
+) -> io::Result<Option<process::ExitStatus>> {
+    const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(cmp::min(deadline - now, POLL_INTERVAL));
+    }
+}
+
+/// Kills a timed out test subprocess along with its process group.
+fn kill_test_subprocess(child: &mut process::Child) {
+    #[cfg(unix)]
+    unsafe {
+        // The child leads its own group, see `spawn_test_subprocess`.
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    // The child may have exited in the meantime, in which case the kill fails
+    // and `wait` simply reaps it.
+    let _ = child.kill();
+}
+
 fn run_test_in_spawned_subprocess(desc: TestDesc, testfn: Box<dyn FnOnce() + Send>) -> ! {
     let builtin_panic_hook = panic::take_hook();
     let record_result = Arc::new(move |panic_info: Option<&'_ PanicInfo<'_>>| {
@@ -39,6 +39,8 @@ pub enum OutputFormat {
     Terse,
     /// JSON output
     Json,
+    /// JUnit output
+    Junit,
 }
 
 /// Whether ignored test should be run or not
@@ -50,6 +52,33 @@ pub enum RunIgnored {
     Only,
 }
 
+/// Disjoint subset of the tests to run when a test suite is split across
+/// several runners
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Shard {
+    /// Zero-based index of this shard, always less than `count`
+    pub index: usize,
+    /// Total number of shards the tests are split into
+    pub count: usize,
+}
+
+impl Shard {
+    /// Whether the test with the given name belongs to this shard.
+    ///
+    /// Tests are assigned by a hash of their name that is stable across
+    /// platforms, runs and compiler versions, so a test always ends up in the
+    /// same shard regardless of which other tests exist.
+    pub fn contains(&self, test_name: &str) -> bool {
+        // 64-bit FNV-1a
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        for byte in test_name.bytes() {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        hash % self.count as u64 == self.index as u64
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum RunStrategy {
     /// Runs the test in the current process, and sends the result back over the
@@ -301,3 +301,128 @@ pub fn winsorize(samples: &mut [f64], pct: f64) {
         }
     }
 }
+
+/// One-sided Welch's t-test on two summarized sample sets.
+///
+/// Returns the p-value of the hypothesis that the mean of the population `new`
+/// was drawn from is larger than the mean of the population behind `old`,
+/// without assuming that both populations have the same variance. `old_len` and
+/// `new_len` are the number of samples each summary was computed from.
+///
+/// See: <https://en.wikipedia.org/wiki/Welch%27s_t-test>
+pub fn welch_t_test(old: &Summary, old_len: usize, new: &Summary, new_len: usize) -> f64 {
+    assert!(old_len > 1 && new_len > 1);
+    let old_len = old_len as f64;
+    let new_len = new_len as f64;
+
+    let old_se = old.var / old_len;
+    let new_se = new.var / new_len;
+    let se = old_se + new_se;
+    if se == 0.0 {
+        // Both sample sets are constant: the difference is either certain or absent.
+        return if new.mean > old.mean { 0.0 } else { 1.0 };
+    }
+
+    let t = (new.mean - old.mean) / se.sqrt();
+    // Welch–Satterthwaite approximation of the degrees of freedom.
+    let df = se * se / (old_se * old_se / (old_len - 1.0) + new_se * new_se / (new_len - 1.0));
+
+    students_t_sf(t, df)
+}
+
+/// Survival function (`1 - CDF`) of Student's t-distribution with `df` degrees of freedom.
+fn students_t_sf(t: f64, df: f64) -> f64 {
+    let tail = 0.5 * regularized_incomplete_beta(df / (df + t * t), df / 2.0, 0.5);
+    if t > 0.0 {
+        tail
+    } else {
+        1.0 - tail
+    }
+}
+
+/// Regularized incomplete beta function `I_x(a, b)`, evaluated with Lentz's
+/// algorithm for its continued fraction representation.
+///
+/// See: <https://en.wikipedia.org/wiki/Beta_function#Incomplete_beta_function>
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    // The continued fraction converges quickly only for `x < (a + 1) / (a + b + 2)`,
+    // use the symmetry `I_x(a, b) = 1 - I_{1-x}(b, a)` otherwise.
+    if x > (a + 1.0) / (a + b + 2.0) {
+        return 1.0 - regularized_incomplete_beta(1.0 - x, b, a);
+    }
+
+    let ln_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+    let front = ln_front.exp() / a;
+
+    const TINY: f64 = 1.0e-30;
+    const EPSILON: f64 = 1.0e-14;
+    let mut f = 1.0;
+    let mut c = 1.0;
+    let mut d = 0.0;
+    for i in 0..=400 {
+        let m = (i / 2) as f64;
+        let numerator = if i == 0 {
+            1.0
+        } else if i % 2 == 0 {
+            (m * (b - m) * x) / ((a + 2.0 * m - 1.0) * (a + 2.0 * m))
+        } else {
+            -((a + m) * (a + b + m) * x) / ((a + 2.0 * m) * (a + 2.0 * m + 1.0))
+        };
+
+        d = 1.0 + numerator * d;
+        if d.abs() < TINY {
+            d = TINY;
+        }
+        d = 1.0 / d;
+
+        c = 1.0 + numerator / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+
+        let cd = c * d;
+        f *= cd;
+        if (1.0 - cd).abs() < EPSILON {
+            break;
+        }
+    }
+
+    front * (f - 1.0)
+}
+
+/// Natural logarithm of the gamma function, using the Lanczos approximation.
+///
+/// See: <https://en.wikipedia.org/wiki/Lanczos_approximation>
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_93,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_13,
+        -176.615_029_162_140_59,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_571_6e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        // Reflection formula
+        let pi = std::f64::consts::PI;
+        return (pi / (pi * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let mut sum = COEFFICIENTS[0];
+    for (i, &coefficient) in COEFFICIENTS.iter().enumerate().skip(1) {
+        sum += coefficient / (x + i as f64);
+    }
+    let t = x + 7.5;
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + sum.ln()
+}
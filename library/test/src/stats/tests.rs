@@ -571,6 +571,38 @@ fn test_sum_f64_between_ints_that_sum_to_0() {
     assert_eq!([1e30f64, 1.2f64, -1e30f64].sum(), 1.2);
 }
 
+#[test]
+fn test_ln_gamma() {
+    assert_approx_eq!(ln_gamma(1.0), 0.0);
+    assert_approx_eq!(ln_gamma(5.0), 24f64.ln());
+    assert_approx_eq!(ln_gamma(0.5), std::f64::consts::PI.sqrt().ln());
+}
+
+#[test]
+fn test_students_t_sf() {
+    // Reference values computed by numerically integrating the density.
+    assert_approx_eq!(students_t_sf(0.0, 10.0), 0.5);
+    assert_approx_eq!(students_t_sf(2.0, 10.0), 0.03669402);
+    assert_approx_eq!(students_t_sf(-2.0, 10.0), 0.96330598);
+    assert_approx_eq!(students_t_sf(1.5, 3.5), 0.10890909);
+}
+
+#[test]
+fn test_welch_t_test() {
+    let old = Summary::new(&[10.0, 11.0, 9.0, 10.5, 9.5, 10.0, 10.2, 9.8]);
+    let same = Summary::new(&[10.0, 11.0, 9.0, 10.5, 9.5, 10.0, 10.2, 9.8]);
+    let slower = Summary::new(&[12.0, 13.0, 11.5, 12.5, 12.2, 11.8, 12.1, 12.4]);
+
+    assert_approx_eq!(welch_t_test(&old, 8, &same, 8), 0.5);
+    assert!(welch_t_test(&old, 8, &slower, 8) < 0.001);
+    assert!(welch_t_test(&slower, 8, &old, 8) > 0.999);
+
+    let constant = Summary::new(&[1.0, 1.0]);
+    let constant_slower = Summary::new(&[2.0, 2.0]);
+    assert_eq!(welch_t_test(&constant, 2, &constant_slower, 2), 0.0);
+    assert_eq!(welch_t_test(&constant, 2, &constant, 2), 1.0);
+}
+
 #[bench]
 pub fn sum_three_items(b: &mut Bencher) {
     b.iter(|| {
@@ -22,6 +22,8 @@ pub enum TestResult {
     TrAllowedFail,
     TrBench(BenchSamples),
     TrTimedFail,
+    /// The test subprocess ran longer than `--test-timeout` and was killed.
+    TrTimedOut,
 }
 
 /// Creates a `TestResult` depending on the raw result of test execution
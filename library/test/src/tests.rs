@@ -3,7 +3,7 @@
 use crate::{
     bench::Bencher,
     console::OutputLocation,
-    formatters::PrettyFormatter,
+    formatters::{JunitFormatter, OutputFormatter, PrettyFormatter},
     options::OutputFormat,
     test::{
         filter_tests,
@@ -14,6 +14,7 @@
         MetricMap,
         RunIgnored,
         RunStrategy,
+        Shard,
         ShouldPanic,
         StaticTestName,
         TestDesc,
@@ -25,7 +26,7 @@
         // ShouldPanic, StaticTestName, TestDesc, TestDescAndFn, TestOpts, TestTimeOptions,
         // TestType, TrFailedMsg, TrIgnored, TrOk,
     },
-    time::{TestTimeOptions, TimeThreshold},
+    time::{TestExecTime, TestTimeOptions, TimeThreshold},
 };
 use std::sync::mpsc::channel;
 use std::time::Duration;
@@ -47,7 +48,12 @@ fn new() -> TestOpts {
             format: OutputFormat::Pretty,
             test_threads: None,
             skip: vec![],
+            shard: None,
             time_options: None,
+            test_timeout: None,
+            baseline_options: None,
+            shuffle: false,
+            shuffle_seed: None,
             options: Options::new(),
         }
     }
@@ -404,6 +410,78 @@ fn parse_include_ignored_flag() {
     assert_eq!(opts.run_ignored, RunIgnored::Yes);
 }
 
+#[test]
+fn parse_test_timeout_option() {
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--test-timeout=30".to_string(),
+    ];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(opts.test_timeout, Some(Duration::from_secs(30)));
+
+    let args = vec!["progname".to_string(), "--test-timeout=30".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
+#[test]
+#[cfg(unix)]
+fn subprocess_is_killed_after_timeout() {
+    let child = process::Command::new("sh")
+        .args(&["-c", "echo started; exec sleep 60"])
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let start = Instant::now();
+    let (output, timed_out) =
+        wait_with_output_and_timeout(child, Some(Duration::from_millis(200))).unwrap();
+    assert!(timed_out);
+    assert!(!output.status.success());
+    assert_eq!(output.stdout, b"started\n");
+    assert!(start.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+#[cfg(unix)]
+fn grandchild_holding_pipes_does_not_block_timeout() {
+    // The backgrounded `sleep` inherits stdout, so the pipe stays open after
+    // the shell itself is killed unless its whole group is.
+    let mut command = process::Command::new("sh");
+    command
+        .args(&["-c", "sleep 60 & echo started; wait"])
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped());
+    set_own_process_group(&mut command);
+    let child = command.spawn().unwrap();
+
+    let start = Instant::now();
+    let (output, timed_out) =
+        wait_with_output_and_timeout(child, Some(Duration::from_millis(200))).unwrap();
+    assert!(timed_out);
+    assert_eq!(output.stdout, b"started\n");
+    assert!(start.elapsed() < Duration::from_secs(30));
+}
+
+#[test]
+#[cfg(unix)]
+fn subprocess_finishing_in_time_is_not_killed() {
+    let child = process::Command::new("sh")
+        .args(&["-c", "echo out; echo err >&2"])
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    let (output, timed_out) =
+        wait_with_output_and_timeout(child, Some(Duration::from_secs(60))).unwrap();
+    assert!(!timed_out);
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"out\n");
+    assert_eq!(output.stderr, b"err\n");
+}
+
 #[test]
 pub fn filter_for_ignored_option() {
     // When we run ignored tests the test filter should filter out all the
@@ -537,6 +615,65 @@ fn tests() -> Vec<TestDescAndFn> {
     assert_eq!(exact.len(), 2);
 }
 
+#[test]
+pub fn shards_are_disjoint_and_cover_all_tests() {
+    fn testfn() {}
+    let tests = || {
+        (0..100)
+            .map(|i| TestDescAndFn {
+                desc: TestDesc {
+                    name: DynTestName(format!("module::test_{}", i)),
+                    ignore: false,
+                    should_panic: ShouldPanic::No,
+                    allow_fail: false,
+                    test_type: TestType::Unknown,
+                },
+                testfn: DynTestFn(Box::new(testfn)),
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let count = 3;
+    let mut all = Vec::new();
+    for index in 0..count {
+        let opts = TestOpts { shard: Some(Shard { index, count }), ..TestOpts::new() };
+        let shard = filter_tests(&opts, tests());
+        assert!(!shard.is_empty());
+        // The assignment must not depend on which other tests are present.
+        for test in &shard {
+            let desc = test.desc.clone();
+            let single = filter_tests(
+                &opts,
+                vec![TestDescAndFn { desc, testfn: DynTestFn(Box::new(testfn)) }],
+            );
+            assert_eq!(single.len(), 1);
+        }
+        all.extend(shard.into_iter().map(|t| t.desc.name.to_string()));
+    }
+
+    all.sort();
+    let mut expected: Vec<_> = tests().into_iter().map(|t| t.desc.name.to_string()).collect();
+    expected.sort();
+    assert_eq!(all, expected);
+}
+
+#[test]
+fn parse_shard_options() {
+    let args = |extra: &[&str]| {
+        let mut args = vec!["progname".to_string(), "-Zunstable-options".to_string()];
+        args.extend(extra.iter().map(|s| s.to_string()));
+        parse_opts(&args).unwrap()
+    };
+
+    let opts = args(&["--shard-index=1", "--shard-count=4"]).unwrap();
+    assert_eq!(opts.shard, Some(Shard { index: 1, count: 4 }));
+
+    assert!(args(&["--shard-index=1"]).is_err());
+    assert!(args(&["--shard-count=4"]).is_err());
+    assert!(args(&["--shard-index=4", "--shard-count=4"]).is_err());
+    assert!(args(&["--shard-index=0", "--shard-count=0"]).is_err());
+}
+
 #[test]
 pub fn sort_tests() {
     let mut opts = TestOpts::new();
@@ -594,6 +731,56 @@ fn testfn() {}
     }
 }
 
+#[test]
+pub fn shuffle_tests_is_deterministic_per_seed() {
+    fn testfn() {}
+    let tests = || -> Vec<(TestId, TestDescAndFn)> {
+        (0..20)
+            .map(|i| {
+                let test = TestDescAndFn {
+                    desc: TestDesc {
+                        name: DynTestName(format!("test{}", i)),
+                        ignore: false,
+                        should_panic: ShouldPanic::No,
+                        allow_fail: false,
+                        test_type: TestType::Unknown,
+                    },
+                    testfn: DynTestFn(Box::new(testfn)),
+                };
+                (TestId(i), test)
+            })
+            .collect()
+    };
+    let order = |seed| {
+        let mut tests = tests();
+        crate::helpers::shuffle::shuffle_tests(seed, &mut tests);
+        tests.into_iter().map(|(id, _)| id.0).collect::<Vec<_>>()
+    };
+
+    let first = order(1);
+    assert_eq!(first, order(1));
+    assert_ne!(first, order(2));
+
+    let mut sorted = first.clone();
+    sorted.sort();
+    assert_eq!(sorted, (0..20).collect::<Vec<_>>());
+}
+
+#[test]
+fn parse_shuffle_seed_option() {
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--shuffle-seed=123".to_string(),
+    ];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(opts.shuffle_seed, Some(123));
+    assert_eq!(crate::helpers::shuffle::get_shuffle_seed(&opts), Some(123));
+
+    let args = vec!["progname".to_string(), "--shuffle".to_string()];
+    assert!(parse_opts(&args).unwrap().is_err());
+}
+
 #[test]
 pub fn test_metricmap_compare() {
     let mut m1 = MetricMap::new();
@@ -704,6 +891,10 @@ fn should_sort_failures_before_printing_them() {
         options: Options::new(),
         not_failures: Vec::new(),
         time_failures: Vec::new(),
+        baseline: None,
+        bench_results: crate::baseline::Baseline::new(),
+        bench_comparisons: Vec::new(),
+        regressed: 0,
     };
 
     out.write_failures(&st).unwrap();
@@ -716,3 +907,118 @@ fn should_sort_failures_before_printing_them() {
     let bpos = s.find("b").unwrap();
     assert!(apos < bpos);
 }
+
+#[test]
+fn junit_formatter_reports_skipped_failures_and_output() {
+    let desc = |name| TestDesc {
+        name: StaticTestName(name),
+        ignore: false,
+        should_panic: ShouldPanic::No,
+        allow_fail: false,
+        test_type: TestType::Unknown,
+    };
+
+    let mut st = console::ConsoleTestState::new(&TestOpts::new()).unwrap();
+    let mut out = JunitFormatter::new(OutputLocation::Raw(Vec::new()));
+
+    out.write_run_start(3, None).unwrap();
+    let time = TestExecTime(Duration::from_millis(1500));
+    out.write_result(&desc("m::passes"), &TrOk, Some(&time), b"not shown", &st).unwrap();
+    out.write_result(&desc("m::ignored"), &TrIgnored, None, b"", &st).unwrap();
+    let failure = TestResult::TrFailedMsg("a <b>".to_string());
+    out.write_result(&desc("fails"), &failure, None, b"out]]>put", &st).unwrap();
+    st.total = 3;
+    st.passed = 1;
+    st.ignored = 1;
+    st.failed = 1;
+    // Tests that were never selected are not skips.
+    st.filtered_out = 2;
+    assert!(!out.write_run_finish(&st).unwrap());
+
+    let s = match out.output_location() {
+        &OutputLocation::Raw(ref m) => String::from_utf8_lossy(&m[..]).into_owned(),
+        &OutputLocation::Pretty(_) => unreachable!(),
+    };
+
+    assert!(s.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<testsuites><testsuite "));
+    assert!(s.contains(r#"failures="1" tests="3" skipped="1""#));
+    assert!(s.contains(r#"<testcase classname="m" name="passes" time="1.500"></testcase>"#));
+    assert!(s.contains(r#"<testcase classname="m" name="ignored" time="0.000"><skipped/>"#));
+    assert!(s.contains(r#"<failure type="assert" message="a &lt;b&gt;"/>"#));
+    assert!(s.contains("<system-out><![CDATA[out]]]]><![CDATA[>put]]></system-out>"));
+    assert!(!s.contains("not shown"));
+    assert!(s.ends_with("</testsuite></testsuites>\n"));
+}
+
+#[test]
+fn parse_junit_format_implies_time_measurement() {
+    let args = vec![
+        "progname".to_string(),
+        "-Zunstable-options".to_string(),
+        "--format=junit".to_string(),
+    ];
+    let opts = parse_opts(&args).unwrap().unwrap();
+    assert_eq!(opts.format, OutputFormat::Junit);
+    assert!(opts.time_options.is_some());
+}
+
+#[test]
+fn baseline_file_roundtrip() {
+    use crate::baseline::{Baseline, BaselineEntry};
+
+    let mut baseline = Baseline::new();
+    let summary = stats::Summary::new(&[1.5, 2.25, 1e-7, 12345.0]);
+    baseline.insert("bench::with\ttab", BaselineEntry::new(summary, 4));
+    baseline.insert("other", BaselineEntry::new(stats::Summary::new(&[3.0]), 1));
+
+    let mut out = Vec::new();
+    baseline.write_to(&mut out).unwrap();
+    let parsed = Baseline::parse(&String::from_utf8(out).unwrap()).unwrap();
+    assert_eq!(parsed, baseline);
+
+    assert!(Baseline::parse("not a baseline\n").is_err());
+    assert!(Baseline::parse("# libtest benchmark baseline v1\nname\t50\t1.0\n").is_err());
+}
+
+#[test]
+fn baseline_comparison_flags_significant_slowdowns() {
+    use crate::baseline::{BaselineEntry, DEFAULT_THRESHOLD_PCT};
+
+    let entry = |samples: &[f64]| BaselineEntry::new(stats::Summary::new(samples), samples.len());
+    let old = entry(&[100.0, 101.0, 99.0, 100.5, 99.5, 100.0]);
+    let noise = entry(&[100.5, 99.0, 101.0, 100.0, 99.5, 100.2]);
+    let slower = entry(&[110.0, 111.0, 109.0, 110.5, 109.5, 110.0]);
+    let faster = entry(&[90.0, 91.0, 89.0, 90.5, 89.5, 90.0]);
+
+    assert!(!old.compare(&noise, 0.0).regressed);
+    assert!(!old.compare(&faster, 0.0).regressed);
+
+    let comparison = old.compare(&slower, DEFAULT_THRESHOLD_PCT);
+    assert!(comparison.regressed);
+    assert!((comparison.change_pct - 10.0).abs() < 1e-9);
+    assert!(!old.compare(&slower, 20.0).regressed);
+}
+
+#[test]
+fn parse_baseline_options() {
+    let args = |extra: &[&str]| {
+        let mut args = vec!["progname".to_string(), "-Zunstable-options".to_string()];
+        args.extend(extra.iter().map(|s| s.to_string()));
+        parse_opts(&args).unwrap()
+    };
+
+    let opts = args(&["--bench", "--baseline=main", "--baseline-threshold=2.5"]).unwrap();
+    let baseline_options = opts.baseline_options.unwrap();
+    assert_eq!(baseline_options.compare.as_deref(), Some("main"));
+    assert_eq!(baseline_options.save, None);
+    assert_eq!(baseline_options.threshold_pct, 2.5);
+
+    assert!(args(&["--baseline-threshold=2.5"]).is_err());
+    assert!(args(&["--baseline=main", "--baseline-threshold=-1"]).is_err());
+    assert!(args(&["--save-baseline=../main"]).is_err());
+    assert!(args(&["--baseline=/tmp/main"]).is_err());
+    assert!(args(&["--baseline=a/b"]).is_err());
+    assert!(args(&["--save-baseline=.."]).is_err());
+    assert!(args(&["--save-baseline="]).is_err());
+    assert!(args(&[]).unwrap().baseline_options.is_none());
+}
@@ -360,6 +360,7 @@ macro_rules! describe {
                 tool::Cargo,
                 tool::Rls,
                 tool::RustAnalyzer,
+                tool::RustCoverage,
                 tool::RustDemangler,
                 tool::Rustdoc,
                 tool::Clippy,
@@ -420,6 +421,7 @@ macro_rules! describe {
                 test::Rustfmt,
                 test::Miri,
                 test::Clippy,
+                test::RustCoverage,
                 test::RustDemangler,
                 test::CompiletestTest,
                 test::RustdocJSStd,
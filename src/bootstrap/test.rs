@@ -351,6 +351,49 @@ fn run(self, builder: &Builder<'_>) {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct RustCoverage {
+    stage: u32,
+    host: TargetSelection,
+}
+
+impl Step for RustCoverage {
+    type Output = ();
+    const ONLY_HOSTS: bool = true;
+
+    fn should_run(run: ShouldRun<'_>) -> ShouldRun<'_> {
+        run.path("src/tools/rust-coverage")
+    }
+
+    fn make_run(run: RunConfig<'_>) {
+        run.builder.ensure(RustCoverage { stage: run.builder.top_stage, host: run.target });
+    }
+
+    /// Runs `cargo test` for rust-coverage.
+    fn run(self, builder: &Builder<'_>) {
+        let stage = self.stage;
+        let host = self.host;
+        let compiler = builder.compiler(stage, host);
+
+        let mut cargo = tool::prepare_tool_cargo(
+            builder,
+            compiler,
+            Mode::ToolRustc,
+            host,
+            "test",
+            "src/tools/rust-coverage",
+            SourceType::InTree,
+            &[],
+        );
+
+        cargo.arg("--").args(builder.config.cmd.test_args());
+
+        cargo.add_rustc_lib_path(builder, compiler);
+
+        builder.run(&mut cargo.into());
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub struct RustDemangler {
     stage: u32,
@@ -1182,6 +1225,10 @@ fn run(self, builder: &Builder<'_>) {
                 .ensure(tool::RustDemangler { compiler, target, extra_features: Vec::new() })
                 .expect("in-tree tool");
             cmd.arg("--rust-demangler-path").arg(rust_demangler);
+            let rust_coverage = builder
+                .ensure(tool::RustCoverage { compiler, target, extra_features: Vec::new() })
+                .expect("in-tree tool");
+            cmd.arg("--rust-coverage-path").arg(rust_coverage);
         }
 
         cmd.arg("--src-base").arg(builder.src.join("src/test").join(suite));
@@ -736,6 +736,7 @@ fn run(mut $sel, $builder: &Builder<'_>) -> Option<PathBuf> {
         });
         self.extra_features.push("clippy".to_owned());
     };
+    RustCoverage, rust_coverage, "src/tools/rust-coverage", "rust-coverage", stable=false, in_tree=true, {};
     RustDemangler, rust_demangler, "src/tools/rust-demangler", "rust-demangler", stable=false, in_tree=true, {};
     Rustfmt, rustfmt, "src/tools/rustfmt", "rustfmt", stable=true, {};
     RustAnalyzer, rust_analyzer, "src/tools/rust-analyzer/crates/rust-analyzer", "rust-analyzer", stable=false, {};
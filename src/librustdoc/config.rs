@@ -2,10 +2,14 @@
 use std::convert::TryFrom;
 use std::ffi::OsStr;
 use std::fmt;
+use std::mem;
 use std::path::PathBuf;
 use std::str::FromStr;
 
 use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::sync::{Lock, Lrc};
+use rustc_errors::json::JsonSchemaVersion;
+use rustc_errors::Diagnostic;
 use rustc_session::config::{self, parse_crate_types_from_list, parse_externs, CrateType};
 use rustc_session::config::{
     build_codegen_options, build_debugging_options, get_cmd_lint_options, host_triple,
@@ -155,6 +159,11 @@ fn try_from(value: &str) -> Result<Self, Self::Error> {
     crate run_check: bool,
     /// Whether doctests should emit unused externs
     crate json_unused_externs: bool,
+    /// The schema of JSON diagnostics, selected with `--json=schema-v2`.
+    crate json_schema_version: JsonSchemaVersion,
+    /// The diagnostics reported while parsing the options with `--error-format=sarif`, for the
+    /// handler of the session to emit.
+    crate early_diagnostics: Vec<Diagnostic>,
 }
 
 impl fmt::Debug for Options {
@@ -197,6 +206,8 @@ fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
             .field("runtool_args", &self.runtool_args)
             .field("enable-per-target-ignores", &self.enable_per_target_ignores)
             .field("run_check", &self.run_check)
+            .field("json_schema_version", &self.json_schema_version)
+            .field("early_diagnostics", &self.early_diagnostics)
             .finish()
     }
 }
@@ -301,7 +312,13 @@ impl RenderOptions {
 impl Options {
     /// Parses the given command-line for options. If an error message or other early-return has
     /// been printed, returns `Err` with the exit code.
-    crate fn from_matches(matches: &getopts::Matches) -> Result<Options, i32> {
+    ///
+    /// With `--error-format=sarif`, diagnostics are added to `sarif_buffer` instead (see
+    /// `new_handler`).
+    crate fn from_matches(
+        matches: &getopts::Matches,
+        sarif_buffer: &Lrc<Lock<Vec<Diagnostic>>>,
+    ) -> Result<Options, i32> {
         // Check for unstable options.
         nightly_options::check_nightly_options(&matches, &opts());
 
@@ -353,14 +370,25 @@ fn println_condition(condition: Condition) {
         }
 
         let color = config::parse_color(&matches);
-        let config::JsonConfig { json_rendered, json_unused_externs, .. } =
-            config::parse_json(&matches);
+        let config::JsonConfig {
+            json_rendered, json_unused_externs, json_schema_version, ..
+        } = config::parse_json(&matches);
         let error_format = config::parse_error_format(&matches, color, json_rendered);
 
         let codegen_options = build_codegen_options(matches, error_format);
         let debugging_opts = build_debugging_options(matches, error_format);
 
-        let diag = new_handler(error_format, None, &debugging_opts);
+        let diag =
+            new_handler(error_format, None, &debugging_opts, json_schema_version, sarif_buffer);
+
+        if json_schema_version != JsonSchemaVersion::V1 && !debugging_opts.unstable_options {
+            diag.struct_err(
+                "the `-Z unstable-options` flag must also be passed to enable \
+                 the flag `--json=schema-v2`",
+            )
+            .emit();
+            return Err(1);
+        }
 
         // check for deprecated options
         check_deprecated_options(&matches, &diag);
@@ -696,6 +724,8 @@ fn println_condition(condition: Condition) {
             crate_name,
             output_format,
             json_unused_externs,
+            json_schema_version,
+            early_diagnostics: mem::take(&mut *sarif_buffer.lock()),
         })
     }
 
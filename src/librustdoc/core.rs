@@ -1,9 +1,11 @@
 use rustc_ast as ast;
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
-use rustc_data_structures::sync::{self, Lrc};
+use rustc_data_structures::sync::{self, Lock, Lrc};
 use rustc_driver::abort_on_err;
 use rustc_errors::emitter::{Emitter, EmitterWriter};
-use rustc_errors::json::JsonEmitter;
+use rustc_errors::json::{JsonEmitter, JsonSchemaVersion};
+use rustc_errors::sarif::SarifEmitter;
+use rustc_errors::Diagnostic;
 use rustc_feature::UnstableFeatures;
 use rustc_hir::def::Res;
 use rustc_hir::def_id::{CrateNum, DefId, DefIndex, LocalDefId, LOCAL_CRATE};
@@ -185,10 +187,16 @@ impl<'tcx> DocContext<'tcx> {
 ///
 /// If the given `error_format` is `ErrorOutputType::Json` and no `SourceMap` is given, a new one
 /// will be created for the handler.
+///
+/// Only the handler of the session writes a SARIF log, so for `ErrorOutputType::Sarif` the
+/// diagnostics are added to `sarif_buffer` instead, to be passed on to it with
+/// `Handler::emit_buffered_diagnostics`, or to `write_sarif_log` if there is no session.
 crate fn new_handler(
     error_format: ErrorOutputType,
     source_map: Option<Lrc<source_map::SourceMap>>,
     debugging_opts: &DebuggingOptions,
+    json_schema_version: JsonSchemaVersion,
+    sarif_buffer: &Lrc<Lock<Vec<Diagnostic>>>,
 ) -> rustc_errors::Handler {
     let emitter: Box<dyn Emitter + sync::Send> = match error_format {
         ErrorOutputType::HumanReadable(kind) => {
@@ -218,9 +226,11 @@ impl<'tcx> DocContext<'tcx> {
                     debugging_opts.terminal_width,
                     false,
                 )
-                .ui_testing(debugging_opts.ui_testing),
+                .ui_testing(debugging_opts.ui_testing)
+                .schema_version(json_schema_version),
             )
         }
+        ErrorOutputType::Sarif => Box::new(SarifEmitter::buffered(sarif_buffer.clone())),
     };
 
     rustc_errors::Handler::with_emitter_and_flags(
@@ -229,6 +239,15 @@ impl<'tcx> DocContext<'tcx> {
     )
 }
 
+/// Writes the SARIF log of `diagnostics`, for runs of rustdoc that create no session whose
+/// handler would write it.
+crate fn write_sarif_log(diagnostics: Vec<Diagnostic>) {
+    let emitter = Box::new(SarifEmitter::basic(false));
+    let handler = rustc_errors::Handler::with_emitter(true, None, emitter);
+    handler.emit_buffered_diagnostics(diagnostics);
+    handler.finish_emitter();
+}
+
 /// Parse, resolve, and typecheck the given crate.
 crate fn create_config(
     RustdocOptions {
@@ -248,6 +267,8 @@ impl<'tcx> DocContext<'tcx> {
         describe_lints,
         lint_cap,
         display_warnings,
+        json_schema_version,
+        early_diagnostics,
         ..
     }: RustdocOptions,
 ) -> rustc_interface::Config {
@@ -295,6 +316,8 @@ impl<'tcx> DocContext<'tcx> {
         actually_rustdoc: true,
         debugging_opts,
         error_format,
+        json_schema_version,
+        early_diagnostics,
         edition,
         describe_lints,
         crate_name,
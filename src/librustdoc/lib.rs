@@ -72,8 +72,10 @@
 
 use std::default::Default;
 use std::env;
+use std::mem;
 use std::process;
 
+use rustc_data_structures::sync::{Lock, Lrc};
 use rustc_driver::{abort_on_err, describe_lints};
 use rustc_errors::ErrorReported;
 use rustc_interface::interface;
@@ -490,7 +492,7 @@ fn opts() -> Vec<RustcOptGroup> {
                 "",
                 "error-format",
                 "How errors and other messages are produced",
-                "human|json|short",
+                "human|json|short|sarif",
             )
         }),
         stable("json", |o| {
@@ -630,9 +632,17 @@ fn main_args(at_args: &[String]) -> MainResult {
 
     // Note that we discard any distinction between different non-zero exit
     // codes from `from_matches` here.
-    let options = match config::Options::from_matches(&matches) {
+    let sarif_buffer = Lrc::new(Lock::new(Vec::new()));
+    let options = match config::Options::from_matches(&matches, &sarif_buffer) {
         Ok(opts) => opts,
-        Err(code) => return if code == 0 { Ok(()) } else { Err(ErrorReported) },
+        Err(code) => {
+            // There won't be a session to write the SARIF log of what was reported.
+            let diagnostics = mem::take(&mut *sarif_buffer.lock());
+            if !diagnostics.is_empty() {
+                core::write_sarif_log(diagnostics);
+            }
+            return if code == 0 { Ok(()) } else { Err(ErrorReported) };
+        }
     };
     rustc_interface::util::setup_callbacks_and_run_in_thread_pool_with_globals(
         options.edition,
@@ -675,24 +685,43 @@ fn run_renderer<'tcx, T: formats::FormatRenderer<'tcx>>(
     }
 }
 
-fn main_options(options: config::Options) -> MainResult {
-    let diag = core::new_handler(options.error_format, None, &options.debugging_opts);
+fn main_options(mut options: config::Options) -> MainResult {
+    let sarif_buffer = Lrc::new(Lock::new(Vec::new()));
+    let diag = core::new_handler(
+        options.error_format,
+        None,
+        &options.debugging_opts,
+        options.json_schema_version,
+        &sarif_buffer,
+    );
 
-    match (options.should_test, options.markdown_input()) {
-        (true, true) => return wrap_return(&diag, markdown::test(options)),
-        (true, false) => return doctest::run(options),
-        (false, true) => {
-            return wrap_return(
+    if options.should_test || options.markdown_input() {
+        // Markdown files and doctests are not handled by a session of rustdoc's own, which would
+        // write the SARIF log, so it is written here.
+        let error_format = options.error_format;
+        sarif_buffer.lock().append(&mut options.early_diagnostics);
+        let res = match (options.should_test, options.markdown_input()) {
+            (true, true) => wrap_return(&diag, markdown::test(options)),
+            (true, false) => doctest::run(options),
+            (false, _) => wrap_return(
                 &diag,
                 markdown::render(&options.input, options.render_options, options.edition),
-            );
+            ),
+        };
+        if let ErrorOutputType::Sarif = error_format {
+            core::write_sarif_log(mem::take(&mut *sarif_buffer.lock()));
         }
-        (false, false) => {}
+        return res;
     }
 
     // need to move these items separately because we lose them by the time the closure is called,
     // but we can't create the Handler ahead of time because it's not Send
-    let diag_opts = (options.error_format, options.edition, options.debugging_opts.clone());
+    let diag_opts = (
+        options.error_format,
+        options.edition,
+        options.debugging_opts.clone(),
+        options.json_schema_version,
+    );
     let show_coverage = options.show_coverage;
     let run_check = options.run_check;
 
@@ -758,9 +787,16 @@ fn main_options(options: config::Options) -> MainResult {
                 }
 
                 info!("going to format");
-                let (error_format, edition, debugging_options) = diag_opts;
-                let diag = core::new_handler(error_format, None, &debugging_options);
-                match output_format {
+                let (error_format, edition, debugging_options, json_schema_version) = diag_opts;
+                let sarif_buffer = Lrc::new(Lock::new(Vec::new()));
+                let diag = core::new_handler(
+                    error_format,
+                    None,
+                    &debugging_options,
+                    json_schema_version,
+                    &sarif_buffer,
+                );
+                let res = match output_format {
                     config::OutputFormat::Html => sess.time("render_html", || {
                         run_renderer::<html::render::Context<'_>>(
                             krate,
@@ -781,7 +817,9 @@ fn main_options(options: config::Options) -> MainResult {
                             tcx,
                         )
                     }),
-                }
+                };
+                sess.diagnostic().emit_buffered_diagnostics(mem::take(&mut *sarif_buffer.lock()));
+                res
             })
         })
     })
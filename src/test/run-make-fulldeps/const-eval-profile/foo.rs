@@ -0,0 +1,14 @@
+#![crate_type = "lib"]
+
+const fn sum(n: u64) -> u64 {
+    let mut total = 0;
+    let mut i = 0;
+    while i < n {
+        total += i;
+        i += 1;
+    }
+    total
+}
+
+pub const SMALL: u64 = sum(10);
+pub static LARGE: u64 = sum(1000);
@@ -0,0 +1,37 @@
+fn is_big(x: usize) -> bool {
+    if x > 10 { true } else { false }
+}
+
+fn main() {
+    let one = std::env::args().len();
+    let mut big = 0;
+    for i in 0..3 {
+        if is_big(one * 20 * i) {
+            big += 1;
+        }
+    }
+    assert_eq!(big, 2);
+
+    let names: Vec<_> = [0, 1, 5, 7].iter().map(|&x| classify(x * one)).collect();
+    assert_eq!(names, ["zero", "one", "many", "many"]);
+
+    let (t, f) = (one == 1, one != 1);
+    assert_eq!([both(t, t), both(t, f), both(f, t)], [true, false, false]);
+    assert_eq!([either(f, f), either(t, f), either(f, t)], [false, true, true]);
+}
+
+fn classify(x: usize) -> &'static str {
+    match x {
+        0 => "zero",
+        1 => "one",
+        _ => "many",
+    }
+}
+
+fn both(a: bool, b: bool) -> bool {
+    a && b
+}
+
+fn either(a: bool, b: bool) -> bool {
+    a || b
+}
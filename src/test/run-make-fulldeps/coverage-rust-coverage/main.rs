@@ -0,0 +1,25 @@
+fn describe(n: usize) -> &'static str {
+    match n % 3 {
+        0 => "fizz",
+        1 if n > 5 => "big",
+        _ => "other",
+    }
+}
+
+fn unused() {
+    println!("never called");
+}
+
+fn main() {
+    let count = std::env::args().len() * 10;
+    let mut fizzes = 0;
+    for n in 0..count {
+        if describe(n) == "fizz" && n > 0 {
+            fizzes += 1;
+        }
+    }
+    assert_eq!(fizzes, 3);
+    if count == 0 {
+        unused();
+    }
+}
@@ -0,0 +1,5 @@
+#![crate_type = "lib"]
+
+pub fn bar() {
+    let w = 4;
+}
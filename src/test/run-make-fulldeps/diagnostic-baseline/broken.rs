@@ -0,0 +1,6 @@
+#![crate_type = "lib"]
+
+pub fn broken() -> u32 {
+    let v = 5;
+    "not a number"
+}
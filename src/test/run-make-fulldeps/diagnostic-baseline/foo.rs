@@ -0,0 +1,9 @@
+#![crate_type = "lib"]
+
+pub fn foo() {
+    let x = 1;
+}
+
+pub fn bar() {
+    let y = 2;
+}
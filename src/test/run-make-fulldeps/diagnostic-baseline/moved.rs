@@ -0,0 +1,11 @@
+#![crate_type = "lib"]
+
+// The existing warnings moved, and a new one appeared.
+
+pub fn foo() {
+    let x = 1;
+}
+
+pub fn baz() {
+    let z = 3;
+}
@@ -0,0 +1,3 @@
+pub fn c() -> u32 {
+    1
+}
@@ -0,0 +1,11 @@
+#![crate_type = "lib"]
+#![deny(unused)]
+
+#[allow(dead_code)]
+fn unused() {}
+
+pub mod inner {
+    #![warn(missing_docs)]
+
+    pub fn documented() {}
+}
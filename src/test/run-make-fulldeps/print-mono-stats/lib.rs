@@ -0,0 +1,9 @@
+pub fn wrap<T: Copy>(value: T) -> Vec<T> {
+    let mut values = Vec::new();
+    values.push(value);
+    values
+}
+
+pub fn wrap_all() -> (Vec<u8>, Vec<u16>, Vec<u32>) {
+    (wrap(1), wrap(2), wrap(3))
+}
@@ -0,0 +1,26 @@
+#![crate_type = "lib"]
+
+use std::ptr;
+
+#[inline(never)]
+pub fn leaf() {
+    let xs = [0u32; 16];
+
+    // force LLVM to allocate `xs` on the stack
+    unsafe { ptr::read_volatile(&xs.as_ptr()); }
+}
+
+#[inline(never)]
+pub fn caller() {
+    leaf();
+}
+
+#[inline(never)]
+pub fn recursive(n: u32) -> u32 {
+    if n == 0 { 0 } else { recursive(n - 1) + 1 }
+}
+
+#[inline(never)]
+pub fn indirect(f: fn()) {
+    f();
+}
@@ -0,0 +1,22 @@
+#![crate_type = "lib"]
+
+#[repr(C)]
+pub struct Padded {
+    pub a: u8,
+    pub b: bool,
+    pub c: u32,
+}
+
+pub enum MaybePadded {
+    Nothing,
+    Just(Padded),
+}
+
+pub enum Direct {
+    A(u32),
+    B(u16),
+}
+
+pub fn sizes(padded: Padded, direct: Direct) -> (MaybePadded, Direct) {
+    (MaybePadded::Just(padded), direct)
+}
@@ -0,0 +1,40 @@
+// run-pass
+#![feature(core_intrinsics)]
+#![feature(const_heap)]
+#![feature(const_raw_ptr_deref)]
+#![feature(const_mut_refs)]
+#![feature(const_ptr_offset)]
+use std::intrinsics;
+use std::mem::{align_of, size_of};
+
+const SUM: usize = sum_of_squares(10);
+
+const fn sum_of_squares(n: usize) -> usize {
+    unsafe {
+        let size = n * size_of::<usize>();
+        let buf = intrinsics::const_allocate(size, align_of::<usize>()) as *mut usize;
+        let mut i = 0;
+        while i < n {
+            *buf.add(i) = i * i;
+            i += 1;
+        }
+        let mut sum = 0;
+        i = 0;
+        while i < n {
+            sum += *buf.add(i);
+            i += 1;
+        }
+        intrinsics::const_deallocate(buf as *mut u8, size, align_of::<usize>());
+        sum
+    }
+}
+
+fn main() {
+    assert_eq!(SUM, 285);
+    unsafe {
+        // Nothing is allocated at runtime.
+        let ptr = intrinsics::const_allocate(4, 4);
+        assert!(ptr.is_null());
+        intrinsics::const_deallocate(ptr, 4, 4);
+    }
+}
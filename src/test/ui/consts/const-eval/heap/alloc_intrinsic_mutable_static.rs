@@ -0,0 +1,27 @@
+#![feature(core_intrinsics)]
+#![feature(const_heap)]
+#![feature(const_raw_ptr_deref)]
+#![feature(const_mut_refs)]
+use std::intrinsics;
+
+const fn make() -> &'static mut i32 {
+    unsafe {
+        let i = intrinsics::const_allocate(4, 4) as *mut i32;
+        *i = 20;
+        &mut *i
+    }
+}
+
+// Reading from heap memory at runtime is fine, but mutating it is not.
+static SHARED: &i32 = make();
+
+static mut MUTABLE: &mut i32 = make();
+//~^ ERROR mutable heap allocation in the final value of a static
+
+// The memory would be immutable here, but the type still claims to own it.
+static NOT_MUTABLE: &mut i32 = make();
+//~^ ERROR mutable heap allocation in the final value of a static
+
+fn main() {
+    assert_eq!(*SHARED, 20);
+}
@@ -6,8 +6,8 @@
 
 fn main() {}
 
+// The box is allocated on the const heap, which cannot be owned mutably by a static.
 static TEST_BAD: &mut i32 = {
     &mut *(box 0)
-    //~^ ERROR could not evaluate static initializer
-    //~| NOTE heap allocations
 };
+//~^^^ ERROR mutable heap allocation in the final value of a static
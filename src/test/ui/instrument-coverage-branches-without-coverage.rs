@@ -0,0 +1,6 @@
+// check-pass
+// compile-flags: -Z instrument-coverage-branches
+
+// Branch coverage needs the counters of `-Z instrument-coverage`, so it is ignored without them.
+
+fn main() {}
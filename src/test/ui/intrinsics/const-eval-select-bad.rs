@@ -0,0 +1,42 @@
+#![feature(core_intrinsics)]
+
+use std::intrinsics::const_eval_select;
+
+const fn in_const(x: i32) -> i32 {
+    x
+}
+
+fn at_rt(x: i32) -> i32 {
+    x
+}
+
+fn wrong(_: bool) -> i32 {
+    0
+}
+
+fn closure() -> i32 {
+    unsafe { const_eval_select((1,), in_const, |x: i32| x) }
+    //~^ ERROR this argument must be a function item
+}
+
+fn fn_pointer(rt: fn(i32) -> i32) -> i32 {
+    unsafe { const_eval_select((1,), in_const, rt) }
+    //~^ ERROR this argument must be a function item
+}
+
+fn generic<F: FnOnce(i32) -> i32>(rt: F) -> i32 {
+    unsafe { const_eval_select((1,), in_const, rt) }
+    //~^ ERROR this argument must be a function item
+}
+
+fn mismatch() -> i32 {
+    unsafe { const_eval_select((1,), in_const, wrong) }
+    //~^ ERROR the signature of this function does not match the call to `const_eval_select`
+}
+
+fn not_a_tuple() -> i32 {
+    unsafe { const_eval_select(1, in_const, at_rt) }
+    //~^ ERROR this argument must be a tuple
+}
+
+fn main() {}
@@ -58,6 +58,7 @@ pub fn parse_config(args: Vec<String>) -> Config {
         .reqopt("", "rustc-path", "path to rustc to use for compiling", "PATH")
         .optopt("", "rustdoc-path", "path to rustdoc to use for compiling", "PATH")
         .optopt("", "rust-demangler-path", "path to rust-demangler to use in tests", "PATH")
+        .optopt("", "rust-coverage-path", "path to rust-coverage to use in tests", "PATH")
         .reqopt("", "lldb-python", "path to python to use for doc tests", "PATH")
         .reqopt("", "docck-python", "path to python to use for doc tests", "PATH")
         .optopt("", "jsondocck-path", "path to jsondocck to use for doc tests", "PATH")
@@ -213,6 +214,7 @@ fn make_absolute(path: PathBuf) -> PathBuf {
         rustc_path: opt_path(matches, "rustc-path"),
         rustdoc_path: matches.opt_str("rustdoc-path").map(PathBuf::from),
         rust_demangler_path: matches.opt_str("rust-demangler-path").map(PathBuf::from),
+        rust_coverage_path: matches.opt_str("rust-coverage-path").map(PathBuf::from),
         lldb_python: matches.opt_str("lldb-python").unwrap(),
         docck_python: matches.opt_str("docck-python").unwrap(),
         jsondocck_path: matches.opt_str("jsondocck-path"),
@@ -283,6 +285,7 @@ pub fn log_config(config: &Config) {
     logv(c, format!("rustc_path: {:?}", config.rustc_path.display()));
     logv(c, format!("rustdoc_path: {:?}", config.rustdoc_path));
     logv(c, format!("rust_demangler_path: {:?}", config.rust_demangler_path));
+    logv(c, format!("rust_coverage_path: {:?}", config.rust_coverage_path));
     logv(c, format!("src_base: {:?}", config.src_base.display()));
     logv(c, format!("build_base: {:?}", config.build_base.display()));
     logv(c, format!("stage_id: {}", config.stage_id));
@@ -487,10 +490,15 @@ pub fn test_opts(config: &Config) -> test::TestOpts {
         color: config.color,
         test_threads: None,
         skip: vec![],
+        shard: None,
         list: false,
         options: test::Options::new(),
         time_options: None,
         force_run_in_process: false,
+        test_timeout: None,
+        baseline_options: None,
+        shuffle: false,
+        shuffle_seed: None,
     }
 }
 
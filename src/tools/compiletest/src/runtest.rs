@@ -3000,6 +3000,10 @@ fn run_rmake_test(&self) {
             cmd.env("RUST_DEMANGLER", cwd.join(rust_demangler));
         }
 
+        if let Some(ref rust_coverage) = self.config.rust_coverage_path {
+            cmd.env("RUST_COVERAGE", cwd.join(rust_coverage));
+        }
+
         if let Some(ref node) = self.config.nodejs {
             cmd.env("NODE", node);
         }
@@ -0,0 +1,310 @@
+//! Reads the coverage map that `rustc_codegen_llvm::coverageinfo` embeds in instrumented
+//! binaries, as defined by the
+//! [LLVM Code Coverage Mapping Format](https://github.com/rust-lang/llvm-project/blob/rustc/11.0-2020-10-12/llvm/docs/CoverageMappingFormat.rst).
+//!
+//! The `__llvm_covmap` section holds one record per object file (codegen unit): a header and the
+//! table of filenames referenced by that object's functions. The `__llvm_covfun` section holds
+//! one record per function, referring to its filename table by the hash of the encoded table.
+
+use crate::reader::Reader;
+use crate::{decompress, md5_hash, Error, FunctionNames, Result};
+use object::{Object, ObjectSection};
+use std::collections::HashMap;
+
+/// The zero-based versions (as stored in the `__llvm_covmap` header) that can be read: Version 4
+/// and Version 5 (which only adds branch regions).
+const SUPPORTED_VERSIONS: std::ops::RangeInclusive<u32> = 3..=4;
+
+/// The size of a `__llvm_covfun` record header: the packed `NameRef`, `DataSize`, `FuncHash` and
+/// `FilenamesRef` fields.
+const FUNCTION_RECORD_HEADER_SIZE: usize = 28;
+
+const COVMAP_SECTION_NAMES: &[&str] = &["__llvm_covmap", ".lcovmap$M"];
+const COVFUN_SECTION_NAMES: &[&str] = &["__llvm_covfun", ".lcovfun$M"];
+const NAMES_SECTION_NAMES: &[&str] = &["__llvm_prf_names", ".lprfn$M"];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Counter {
+    Zero,
+    /// A zero-based index into the function's counters, in the raw profile.
+    CounterValueReference(u32),
+    /// An index into the function's `expressions`.
+    Expression(u32),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExprKind {
+    Subtract,
+    Add,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Expression {
+    pub kind: ExprKind,
+    pub lhs: Counter,
+    pub rhs: Counter,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RegionKind {
+    Code,
+    Expansion,
+    Skipped,
+    Gap,
+    Branch,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Region {
+    pub kind: RegionKind,
+    pub counter: Counter,
+    /// The count of the `false` outcome, for a `RegionKind::Branch`.
+    pub false_counter: Counter,
+    /// An index into the function's `filenames`.
+    pub file_id: u32,
+    pub expanded_file_id: u32,
+    pub start_line: u32,
+    pub start_col: u32,
+    pub end_line: u32,
+    pub end_col: u32,
+}
+
+/// The coverage mapping of a single function.
+#[derive(Clone, Debug)]
+pub struct FunctionRecord {
+    /// The MD5-based hash of the function's mangled name.
+    pub name_ref: u64,
+    /// The hash of the function's source, which must match the hash in the raw profile.
+    pub func_hash: u64,
+    /// The files referenced by the function's regions (its "virtual file mapping").
+    pub filenames: Vec<String>,
+    pub expressions: Vec<Expression>,
+    pub regions: Vec<Region>,
+}
+
+/// Reads the coverage map of an instrumented binary (or object file, or library), and adds the
+/// names of its instrumented functions to `names`.
+pub fn read_object(data: &[u8], names: &mut FunctionNames) -> Result<Vec<FunctionRecord>> {
+    let file = object::File::parse(data)
+        .map_err(|e| Error::new(format!("failed to parse object file: {}", e)))?;
+    let section_data = |section_names: &[&str]| -> Result<Option<&[u8]>> {
+        for &section_name in section_names {
+            if let Some(section) = file.section_by_name(section_name) {
+                let data = section.data().map_err(|e| {
+                    Error::new(format!("failed to read section `{}`: {}", section_name, e))
+                })?;
+                return Ok(Some(data));
+            }
+        }
+        Ok(None)
+    };
+
+    if let Some(names_data) = section_data(NAMES_SECTION_NAMES)? {
+        names.add_names_section(names_data)?;
+    }
+    match (section_data(COVMAP_SECTION_NAMES)?, section_data(COVFUN_SECTION_NAMES)?) {
+        (Some(covmap), Some(covfun)) => read_coverage_sections(covmap, covfun),
+        _ => Err(Error::new(
+            "no coverage map found; was the binary compiled with `-Z instrument-coverage`?",
+        )),
+    }
+}
+
+/// Reads the function records from the contents of the `__llvm_covmap` and `__llvm_covfun`
+/// sections.
+pub fn read_coverage_sections(covmap: &[u8], covfun: &[u8]) -> Result<Vec<FunctionRecord>> {
+    let mut filename_tables = HashMap::new();
+    let mut reader = Reader::new(covmap);
+    while reader.remaining() >= 16 {
+        let _n_records = reader.read_u32()?;
+        let filenames_size = reader.read_u32()?;
+        let _coverage_size = reader.read_u32()?;
+        let version = reader.read_u32()?;
+        if filenames_size == 0 && version == 0 {
+            // Padding between the records of different object files.
+            continue;
+        }
+        if !SUPPORTED_VERSIONS.contains(&version) {
+            return Err(Error::new(format!(
+                "coverage mapping format version {} is not supported (only versions {} to {} are)",
+                version + 1,
+                SUPPORTED_VERSIONS.start() + 1,
+                SUPPORTED_VERSIONS.end() + 1,
+            )));
+        }
+        let encoded_filenames = reader.read_bytes(u64::from(filenames_size))?;
+        filename_tables.insert(md5_hash(encoded_filenames), read_filenames(encoded_filenames)?);
+        reader.align(8);
+    }
+
+    let mut records = Vec::new();
+    let mut reader = Reader::new(covfun);
+    while reader.remaining() >= FUNCTION_RECORD_HEADER_SIZE {
+        let name_ref = reader.read_u64()?;
+        let data_size = reader.read_u32()?;
+        let func_hash = reader.read_u64()?;
+        let filenames_ref = reader.read_u64()?;
+        let mapping = reader.read_bytes(u64::from(data_size))?;
+        // Each function record is aligned to 8 bytes.
+        reader.align(8);
+        if name_ref == 0 && data_size == 0 {
+            continue;
+        }
+        let filenames = filename_tables.get(&filenames_ref).ok_or_else(|| {
+            Error::new(format!("function {:#x} refers to an unknown filename table", name_ref))
+        })?;
+        records.push(read_function_mapping(name_ref, func_hash, filenames, mapping)?);
+    }
+    Ok(records)
+}
+
+/// Decodes a filename table: the LEB128 number of filenames, the LEB128 uncompressed and
+/// compressed sizes of the table (the compressed size is zero if it is not compressed), and the
+/// (zlib-compressed) filenames, each a LEB128 length and the filename's bytes.
+fn read_filenames(data: &[u8]) -> Result<Vec<String>> {
+    let mut reader = Reader::new(data);
+    let num_filenames = reader.read_uleb128()?;
+    let uncompressed_size = reader.read_uleb128()?;
+    let compressed_size = reader.read_uleb128()?;
+    let uncompressed;
+    let mut reader = if compressed_size > 0 {
+        uncompressed = decompress(reader.read_bytes(compressed_size)?, uncompressed_size)?;
+        Reader::new(&uncompressed)
+    } else {
+        reader
+    };
+    let mut filenames = Vec::new();
+    for _ in 0..num_filenames {
+        let len = reader.read_uleb128()?;
+        filenames.push(String::from_utf8_lossy(reader.read_bytes(len)?).into_owned());
+    }
+    Ok(filenames)
+}
+
+/// Decodes a function's coverage mapping: its virtual file mapping, expressions, and the
+/// regions of each of its files.
+pub fn read_function_mapping(
+    name_ref: u64,
+    func_hash: u64,
+    filenames: &[String],
+    data: &[u8],
+) -> Result<FunctionRecord> {
+    let mut reader = Reader::new(data);
+
+    let num_file_ids = reader.read_uleb128_u32()?;
+    let mut function_filenames = Vec::new();
+    for _ in 0..num_file_ids {
+        let index = reader.read_uleb128()?;
+        let filename = filenames.get(index as usize).ok_or_else(|| {
+            Error::new(format!("function {:#x} refers to unknown file {}", name_ref, index))
+        })?;
+        function_filenames.push(filename.clone());
+    }
+
+    let num_expressions = reader.read_uleb128_u32()?;
+    let mut expressions = vec![
+        Expression { kind: ExprKind::Subtract, lhs: Counter::Zero, rhs: Counter::Zero };
+        num_expressions as usize
+    ];
+    for index in 0..num_expressions as usize {
+        let lhs = reader.read_uleb128()?;
+        let rhs = reader.read_uleb128()?;
+        expressions[index].lhs = decode_counter(lhs, &mut expressions)?;
+        expressions[index].rhs = decode_counter(rhs, &mut expressions)?;
+    }
+
+    let mut regions = Vec::new();
+    for file_id in 0..num_file_ids {
+        let num_regions = reader.read_uleb128()?;
+        let mut start_line = 0u32;
+        for _ in 0..num_regions {
+            let mut kind = RegionKind::Code;
+            let mut counter = Counter::Zero;
+            let mut false_counter = Counter::Zero;
+            let mut expanded_file_id = 0;
+
+            // The counter, or (for regions with a zero count) a pseudo-counter giving the kind of
+            // the region: bit 2 marks an expansion region (the file id follows in the higher
+            // bits), otherwise the higher bits are the region kind.
+            let encoded = reader.read_uleb128_u32()?;
+            if encoded & 0b11 != 0 {
+                counter = decode_counter(u64::from(encoded), &mut expressions)?;
+            } else if encoded & 0b100 != 0 {
+                kind = RegionKind::Expansion;
+                expanded_file_id = encoded >> 3;
+                if expanded_file_id >= num_file_ids {
+                    return Err(Error::new(format!(
+                        "function {:#x} has an expansion of unknown file {}",
+                        name_ref, expanded_file_id
+                    )));
+                }
+            } else {
+                match encoded >> 3 {
+                    0 => {}
+                    2 => kind = RegionKind::Skipped,
+                    4 => {
+                        kind = RegionKind::Branch;
+                        counter = decode_counter(reader.read_uleb128()?, &mut expressions)?;
+                        false_counter = decode_counter(reader.read_uleb128()?, &mut expressions)?;
+                    }
+                    other => {
+                        return Err(Error::new(format!(
+                            "function {:#x} has a region of unknown kind {}",
+                            name_ref, other
+                        )));
+                    }
+                }
+            }
+
+            let start_line_delta = reader.read_uleb128_u32()?;
+            let mut start_col = reader.read_uleb128_u32()?;
+            let num_lines = reader.read_uleb128_u32()?;
+            let mut end_col = reader.read_uleb128_u32()?;
+            if end_col & (1 << 31) != 0 {
+                kind = RegionKind::Gap;
+                end_col &= !(1 << 31);
+            }
+            if start_col == 0 && end_col == 0 {
+                // A region covering whole lines.
+                start_col = 1;
+                end_col = u32::MAX;
+            }
+            start_line = start_line.saturating_add(start_line_delta);
+            regions.push(Region {
+                kind,
+                counter,
+                false_counter,
+                file_id,
+                expanded_file_id,
+                start_line,
+                start_col,
+                end_line: start_line.saturating_add(num_lines),
+                end_col,
+            });
+        }
+    }
+
+    Ok(FunctionRecord { name_ref, func_hash, filenames: function_filenames, expressions, regions })
+}
+
+/// Decodes a counter: the low two bits are the tag (zero, counter, subtract or add expression),
+/// and the remaining bits the counter or expression index. The kind of an expression is only
+/// known from the counters that refer to it, so it is recorded in `expressions`.
+fn decode_counter(encoded: u64, expressions: &mut [Expression]) -> Result<Counter> {
+    let index = encoded >> 2;
+    if index > u64::from(u32::MAX) {
+        return Err(Error::new(format!("counter index {} is out of range", index)));
+    }
+    let index = index as u32;
+    match encoded & 0b11 {
+        0 => Ok(Counter::Zero),
+        1 => Ok(Counter::CounterValueReference(index)),
+        tag => {
+            let expression = expressions.get_mut(index as usize).ok_or_else(|| {
+                Error::new(format!("expression index {} is out of range", index))
+            })?;
+            expression.kind = if tag == 2 { ExprKind::Subtract } else { ExprKind::Add };
+            Ok(Counter::Expression(index))
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Writes coverage reports: `lcov` tracefiles, Cobertura XML, a single-page HTML summary, and a
+//! plain text summary.
+
+use crate::report::{Coverage, FileCoverage};
+use std::io::{self, Write};
+
+/// Writes an `lcov` tracefile (the format of `geninfo`, read by `genhtml` and most coverage
+/// services).
+pub fn write_lcov(coverage: &Coverage, out: &mut impl Write) -> io::Result<()> {
+    for file in &coverage.files {
+        writeln!(out, "SF:{}", file.path)?;
+        for function in &file.functions {
+            writeln!(out, "FN:{},{}", function.line, function.name)?;
+        }
+        for function in &file.functions {
+            writeln!(out, "FNDA:{},{}", function.execution_count, function.name)?;
+        }
+        writeln!(out, "FNF:{}", file.functions.len())?;
+        writeln!(out, "FNH:{}", file.functions_hit())?;
+        for (block, branch) in file.branches.iter().enumerate() {
+            writeln!(out, "BRDA:{},{},0,{}", branch.line, block, branch.true_count)?;
+            writeln!(out, "BRDA:{},{},1,{}", branch.line, block, branch.false_count)?;
+        }
+        if !file.branches.is_empty() {
+            writeln!(out, "BRF:{}", file.branches.len() * 2)?;
+            writeln!(out, "BRH:{}", file.branch_outcomes_hit())?;
+        }
+        for (line, count) in &file.lines {
+            writeln!(out, "DA:{},{}", line, count)?;
+        }
+        writeln!(out, "LF:{}", file.lines.len())?;
+        writeln!(out, "LH:{}", file.lines_hit())?;
+        writeln!(out, "end_of_record")?;
+    }
+    Ok(())
+}
+
+/// Writes a Cobertura XML report, with one class per source file.
+pub fn write_cobertura(coverage: &Coverage, out: &mut impl Write) -> io::Result<()> {
+    let lines_valid: usize = coverage.files.iter().map(|file| file.lines.len()).sum();
+    let lines_covered: usize = coverage.files.iter().map(FileCoverage::lines_hit).sum();
+    let branches_valid: usize = coverage.files.iter().map(|file| file.branches.len() * 2).sum();
+    let branches_covered: usize =
+        coverage.files.iter().map(FileCoverage::branch_outcomes_hit).sum();
+
+    writeln!(out, r#"<?xml version="1.0" ?>"#)?;
+    writeln!(
+        out,
+        r#"<!DOCTYPE coverage SYSTEM "http://cobertura.sourceforge.net/xml/coverage-04.dtd">"#
+    )?;
+    writeln!(
+        out,
+        r#"<coverage line-rate="{}" branch-rate="{}" lines-covered="{}" lines-valid="{}" branches-covered="{}" branches-valid="{}" complexity="0" version="rust-coverage {}" timestamp="0">"#,
+        rate(lines_covered, lines_valid),
+        rate(branches_covered, branches_valid),
+        lines_covered,
+        lines_valid,
+        branches_covered,
+        branches_valid,
+        env!("CARGO_PKG_VERSION"),
+    )?;
+    writeln!(out, r#"  <sources><source>.</source></sources>"#)?;
+    writeln!(out, r#"  <packages>"#)?;
+    writeln!(
+        out,
+        r#"    <package name="" line-rate="{}" branch-rate="{}" complexity="0">"#,
+        rate(lines_covered, lines_valid),
+        rate(branches_covered, branches_valid),
+    )?;
+    writeln!(out, r#"      <classes>"#)?;
+    for file in &coverage.files {
+        let path = escape(&file.path);
+        writeln!(
+            out,
+            r#"        <class name="{}" filename="{}" line-rate="{}" branch-rate="{}" complexity="0">"#,
+            path,
+            path,
+            rate(file.lines_hit(), file.lines.len()),
+            rate(file.branch_outcomes_hit(), file.branches.len() * 2),
+        )?;
+        writeln!(out, r#"          <methods>"#)?;
+        for function in &file.functions {
+            writeln!(
+                out,
+                r#"            <method name="{}" signature="" line-rate="{}" branch-rate="0" complexity="0">"#,
+                escape(&function.name),
+                if function.execution_count > 0 { 1 } else { 0 },
+            )?;
+            writeln!(
+                out,
+                r#"              <lines><line number="{}" hits="{}"/></lines>"#,
+                function.line, function.execution_count
+            )?;
+            writeln!(out, r#"            </method>"#)?;
+        }
+        writeln!(out, r#"          </methods>"#)?;
+        writeln!(out, r#"          <lines>"#)?;
+        for (&line, count) in &file.lines {
+            let branches: Vec<_> =
+                file.branches.iter().filter(|branch| branch.line == line).collect();
+            if branches.is_empty() {
+                writeln!(out, r#"            <line number="{}" hits="{}"/>"#, line, count)?;
+            } else {
+                let outcomes = branches.len() * 2;
+                let taken: usize = branches
+                    .iter()
+                    .map(|branch| {
+                        (branch.true_count > 0) as usize + (branch.false_count > 0) as usize
+                    })
+                    .sum();
+                writeln!(
+                    out,
+                    r#"            <line number="{}" hits="{}" branch="true" condition-coverage="{}% ({}/{})"/>"#,
+                    line,
+                    count,
+                    taken * 100 / outcomes,
+                    taken,
+                    outcomes,
+                )?;
+            }
+        }
+        writeln!(out, r#"          </lines>"#)?;
+        writeln!(out, r#"        </class>"#)?;
+    }
+    writeln!(out, r#"      </classes>"#)?;
+    writeln!(out, r#"    </package>"#)?;
+    writeln!(out, r#"  </packages>"#)?;
+    writeln!(out, r#"</coverage>"#)?;
+    Ok(())
+}
+
+/// Writes a self-contained HTML page summarizing the coverage of each file, and listing the
+/// functions that were never executed.
+pub fn write_html(coverage: &Coverage, out: &mut impl Write) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>")?;
+    writeln!(out, "<head>")?;
+    writeln!(out, r#"<meta charset="utf-8">"#)?;
+    writeln!(out, "<title>Coverage report</title>")?;
+    writeln!(
+        out,
+        "<style>\n\
+        body {{ font-family: sans-serif; }}\n\
+        table {{ border-collapse: collapse; }}\n\
+        th, td {{ padding: 0.2em 0.8em; border-bottom: 1px solid #ddd; text-align: right; }}\n\
+        th:first-child, td:first-child {{ text-align: left; }}\n\
+        .low {{ background-color: #fdd; }}\n\
+        .medium {{ background-color: #ffd; }}\n\
+        .high {{ background-color: #dfd; }}\n\
+        </style>"
+    )?;
+    writeln!(out, "</head>")?;
+    writeln!(out, "<body>")?;
+    writeln!(out, "<h1>Coverage report</h1>")?;
+    writeln!(out, "<table>")?;
+    writeln!(out, "<tr><th>File</th><th>Lines</th><th>Functions</th><th>Branches</th></tr>")?;
+    let mut totals = Totals::default();
+    for file in &coverage.files {
+        let file_totals = Totals::of(file);
+        write!(out, "<tr><td>{}</td>", escape(&file.path))?;
+        write_html_cells(&file_totals, out)?;
+        writeln!(out, "</tr>")?;
+        totals.add(&file_totals);
+    }
+    write!(out, "<tr><th>Total</th>")?;
+    write_html_cells(&totals, out)?;
+    writeln!(out, "</tr>")?;
+    writeln!(out, "</table>")?;
+
+    let mut unexecuted = coverage
+        .files
+        .iter()
+        .flat_map(|file| {
+            file.functions
+                .iter()
+                .filter(|function| function.execution_count == 0)
+                .map(move |function| (file, function))
+        })
+        .peekable();
+    if unexecuted.peek().is_some() {
+        writeln!(out, "<h2>Functions never executed</h2>")?;
+        writeln!(out, "<table>")?;
+        writeln!(out, "<tr><th>Function</th><th>Location</th></tr>")?;
+        for (file, function) in unexecuted {
+            writeln!(
+                out,
+                "<tr><td><code>{}</code></td><td>{}:{}</td></tr>",
+                escape(&function.name),
+                escape(&file.path),
+                function.line
+            )?;
+        }
+        writeln!(out, "</table>")?;
+    }
+    writeln!(out, "</body>")?;
+    writeln!(out, "</html>")?;
+    Ok(())
+}
+
+/// Writes a plain text table of the line, function and branch coverage of each file.
+pub fn write_summary(coverage: &Coverage, out: &mut impl Write) -> io::Result<()> {
+    let width = coverage.files.iter().map(|file| file.path.len()).max().unwrap_or(0).max(5);
+    writeln!(
+        out,
+        "{:<width$}  {:>16}  {:>16}  {:>16}",
+        "File",
+        "Lines",
+        "Functions",
+        "Branches",
+        width = width
+    )?;
+    let mut totals = Totals::default();
+    for file in &coverage.files {
+        let file_totals = Totals::of(file);
+        write_summary_row(&file.path, &file_totals, width, out)?;
+        totals.add(&file_totals);
+    }
+    write_summary_row("TOTAL", &totals, width, out)
+}
+
+#[derive(Default)]
+struct Totals {
+    /// Hit and total counts of lines, functions and branch outcomes.
+    lines: (usize, usize),
+    functions: (usize, usize),
+    branches: (usize, usize),
+}
+
+impl Totals {
+    fn of(file: &FileCoverage) -> Self {
+        Self {
+            lines: (file.lines_hit(), file.lines.len()),
+            functions: (file.functions_hit(), file.functions.len()),
+            branches: (file.branch_outcomes_hit(), file.branches.len() * 2),
+        }
+    }
+
+    fn add(&mut self, other: &Totals) {
+        for (total, other) in [
+            (&mut self.lines, other.lines),
+            (&mut self.functions, other.functions),
+            (&mut self.branches, other.branches),
+        ]
+        .iter_mut()
+        {
+            total.0 += other.0;
+            total.1 += other.1;
+        }
+    }
+}
+
+fn write_summary_row(
+    name: &str,
+    totals: &Totals,
+    width: usize,
+    out: &mut impl Write,
+) -> io::Result<()> {
+    let cell = |(hit, total): (usize, usize)| {
+        if total == 0 {
+            "-".to_string()
+        } else {
+            format!("{}/{} {:>6.2}%", hit, total, percent(hit, total))
+        }
+    };
+    writeln!(
+        out,
+        "{:<width$}  {:>16}  {:>16}  {:>16}",
+        name,
+        cell(totals.lines),
+        cell(totals.functions),
+        cell(totals.branches),
+        width = width
+    )
+}
+
+fn write_html_cells(totals: &Totals, out: &mut impl Write) -> io::Result<()> {
+    for &(hit, total) in &[totals.lines, totals.functions, totals.branches] {
+        if total == 0 {
+            write!(out, "<td>-</td>")?;
+        } else {
+            let percent = percent(hit, total);
+            let class = if percent >= 90.0 {
+                "high"
+            } else if percent >= 75.0 {
+                "medium"
+            } else {
+                "low"
+            };
+            write!(out, r#"<td class="{}">{:.2}% ({}/{})</td>"#, class, percent, hit, total)?;
+        }
+    }
+    Ok(())
+}
+
+fn percent(hit: usize, total: usize) -> f64 {
+    hit as f64 * 100.0 / total as f64
+}
+
+/// The fraction of `total` that is `hit`, as Cobertura reports it (1 if `total` is zero).
+fn rate(hit: usize, total: usize) -> f64 {
+    if total == 0 { 1.0 } else { hit as f64 / total as f64 }
+}
+
+/// Escapes text for use in XML and HTML attributes and content.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
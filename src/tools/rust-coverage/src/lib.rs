@@ -0,0 +1,125 @@
+//! Generates coverage reports for programs compiled with `-Z instrument-coverage`, without
+//! `llvm-profdata` or `llvm-cov`.
+//!
+//! An instrumented program writes its counters to a raw profile (a `.profraw` file) when it
+//! exits. The meaning of those counters (which source regions they count, and how they combine
+//! into expressions) is recorded separately, in the coverage map that `rustc_codegen_llvm` embeds
+//! in the program itself (the `__llvm_covmap` and `__llvm_covfun` sections). This crate reads
+//! both ([`profraw`] and [`covmap`]), combines them into per-file line, function and branch
+//! counts ([`report`]), and writes those in common report formats ([`export`]).
+//!
+//! Only the formats written by the LLVM versions that `rustc` supports for coverage are read:
+//! raw profile version 5 (64-bit, little-endian), and coverage mapping format versions 4 and 5
+//! (the latter adds the branch regions of `-Z instrument-coverage-branches`).
+
+pub mod covmap;
+pub mod export;
+pub mod profraw;
+pub mod report;
+
+mod reader;
+
+use flate2::read::ZlibDecoder;
+use md5::{Digest, Md5};
+use reader::Reader;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+
+#[derive(Debug)]
+pub struct Error {
+    message: String,
+}
+
+impl Error {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self { message: message.into() }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Self::new(error.to_string())
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Computes the 64-bit hash LLVM uses to refer to function names and filename tables
+/// (`IndexedInstrProf::ComputeHash`): the low 64 bits of the MD5 digest, read as little-endian.
+pub fn md5_hash(bytes: &[u8]) -> u64 {
+    let digest = Md5::digest(bytes);
+    let mut low = [0; 8];
+    low.copy_from_slice(&digest[..8]);
+    u64::from_le_bytes(low)
+}
+
+/// Maps the hashed function names (`NameRef`s) found in raw profiles and coverage maps back to
+/// the mangled function names.
+#[derive(Default)]
+pub struct FunctionNames {
+    names: HashMap<u64, String>,
+}
+
+impl FunctionNames {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: &str) {
+        self.names.entry(md5_hash(name.as_bytes())).or_insert_with(|| name.to_owned());
+    }
+
+    pub fn get(&self, name_ref: u64) -> Option<&str> {
+        self.names.get(&name_ref).map(|name| &name[..])
+    }
+
+    /// Adds the names from a names section (`__llvm_prf_names`), as found in both instrumented
+    /// binaries and raw profiles. The section is a sequence of chunks, each a LEB128
+    /// uncompressed size, a LEB128 compressed size (zero if the chunk is not compressed), and
+    /// the (zlib-compressed) names, separated by `\x01`.
+    pub fn add_names_section(&mut self, data: &[u8]) -> Result<()> {
+        let mut reader = Reader::new(data);
+        while !reader.is_empty() {
+            let uncompressed_size = reader.read_uleb128()?;
+            let compressed_size = reader.read_uleb128()?;
+            let names = if compressed_size > 0 {
+                decompress(reader.read_bytes(compressed_size)?, uncompressed_size)?
+            } else {
+                reader.read_bytes(uncompressed_size)?.to_vec()
+            };
+            for name in names.split(|&byte| byte == 1).filter(|name| !name.is_empty()) {
+                self.insert(&String::from_utf8_lossy(name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The largest factor by which zlib can compress data.
+const MAX_ZLIB_RATIO: u64 = 1032;
+
+/// Decompresses zlib-compressed data, whose uncompressed size is recorded next to it. That size
+/// comes from the file, so it is checked against the data, and not trusted to preallocate more
+/// than the compressed data can expand to.
+pub(crate) fn decompress(compressed: &[u8], uncompressed_size: u64) -> Result<Vec<u8>> {
+    let capacity = uncompressed_size.min((compressed.len() as u64).saturating_mul(MAX_ZLIB_RATIO));
+    let mut decoded = Vec::with_capacity(capacity as usize);
+    ZlibDecoder::new(compressed).take(uncompressed_size).read_to_end(&mut decoded)?;
+    if decoded.len() as u64 != uncompressed_size {
+        return Err(Error::new(format!(
+            "compressed data decompresses to {} bytes instead of the recorded {}",
+            decoded.len(),
+            uncompressed_size
+        )));
+    }
+    Ok(decoded)
+}
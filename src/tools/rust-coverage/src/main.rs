@@ -0,0 +1,123 @@
+//! Generates coverage reports from the raw profiles (`.profraw` files) of programs compiled with
+//! `-Z instrument-coverage`, without `llvm-profdata` or `llvm-cov`.
+//!
+//! The raw profiles are merged, and combined with the coverage maps of the given binaries. By
+//! default, a summary of the line, function and branch coverage of each source file is printed to
+//! standard output; `--lcov`, `--cobertura` and `--html` write reports in those formats instead.
+//!
+//! As with `rust-demangler`, crate disambiguators are removed from the demangled function names,
+//! unless `-d` (or `--disambiguators`) is given.
+
+use rust_coverage::profraw::ProfileData;
+use rust_coverage::{covmap, export, report, Error, Result};
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+fn usage(progname: &str) -> ! {
+    eprintln!();
+    eprintln!(
+        "Usage: {} [options] --object <BINARY> [--object <BINARY>...] <PROFRAW>...",
+        progname
+    );
+    eprintln!();
+    eprintln!(
+        "This tool merges the raw profiles written by programs compiled with\n\
+        `-Z instrument-coverage`, and reports the coverage they record, using the coverage maps\n\
+        embedded in the instrumented binaries."
+    );
+    eprintln!();
+    eprintln!("Options:");
+    eprintln!("    -o, --object <BINARY>   An instrumented binary, object file or library");
+    eprintln!("        --lcov <FILE>       Write an lcov tracefile");
+    eprintln!("        --cobertura <FILE>  Write a Cobertura XML report");
+    eprintln!("        --html <FILE>       Write an HTML summary");
+    eprintln!("    -d, --disambiguators    Keep crate disambiguators in function names");
+    eprintln!();
+    eprintln!("Without --lcov, --cobertura or --html, a summary is printed to standard output.");
+    eprintln!();
+    std::process::exit(1)
+}
+
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("error: {}", error);
+        std::process::exit(1);
+    }
+}
+
+fn run() -> Result<()> {
+    let mut args = std::env::args();
+    let progname = args.next().unwrap();
+
+    let mut objects = Vec::new();
+    let mut profiles = Vec::new();
+    let mut lcov = None;
+    let mut cobertura = None;
+    let mut html = None;
+    let mut strip_disambiguators = true;
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().map(PathBuf::from).unwrap_or_else(|| usage(&progname));
+        match &arg[..] {
+            "-o" | "--object" => objects.push(value()),
+            "--lcov" => lcov = Some(value()),
+            "--cobertura" => cobertura = Some(value()),
+            "--html" => html = Some(value()),
+            "-d" | "--disambiguators" => strip_disambiguators = false,
+            _ if arg.starts_with('-') => usage(&progname),
+            _ => profiles.push(PathBuf::from(arg)),
+        }
+    }
+    if objects.is_empty() || profiles.is_empty() {
+        usage(&progname);
+    }
+
+    let mut profile = ProfileData::new();
+    for path in &profiles {
+        let data = read(path)?;
+        profile
+            .merge_raw_profile(&data)
+            .map_err(|e| Error::new(format!("{}: {}", path.display(), e)))?;
+    }
+
+    let mut records = Vec::new();
+    for path in &objects {
+        let data = read(path)?;
+        records.extend(
+            covmap::read_object(&data, &mut profile.names)
+                .map_err(|e| Error::new(format!("{}: {}", path.display(), e)))?,
+        );
+    }
+
+    let coverage = report::build(&records, &profile, &profile.names, strip_disambiguators)?;
+
+    let mut wrote_report = false;
+    for (path, write) in [
+        (lcov, export::write_lcov as fn(&_, &mut BufWriter<File>) -> io::Result<()>),
+        (cobertura, export::write_cobertura),
+        (html, export::write_html),
+    ]
+    .iter()
+    {
+        if let Some(path) = path {
+            let mut out = BufWriter::new(create(path)?);
+            write(&coverage, &mut out)
+                .and_then(|()| out.flush())
+                .map_err(|e| Error::new(format!("{}: {}", path.display(), e)))?;
+            wrote_report = true;
+        }
+    }
+    if !wrote_report {
+        let stdout = io::stdout();
+        export::write_summary(&coverage, &mut stdout.lock())?;
+    }
+    Ok(())
+}
+
+fn read(path: &PathBuf) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|e| Error::new(format!("{}: {}", path.display(), e)))
+}
+
+fn create(path: &PathBuf) -> Result<File> {
+    File::create(path).map_err(|e| Error::new(format!("{}: {}", path.display(), e)))
+}
@@ -0,0 +1,127 @@
+//! Reads and merges raw profiles (`.profraw` files).
+//!
+//! A raw profile is a header, followed by one data record per instrumented function, the array
+//! of counters the records point into, and the names of the functions. Only the counters are
+//! needed here; the rest of each record identifies the function (by the MD5 hash of its name and
+//! the hash of its source, which must match the coverage map) and locates its counters.
+
+use crate::reader::Reader;
+use crate::{Error, FunctionNames, Result};
+use std::collections::HashMap;
+
+/// `\xfflprofr\x81`, as written by a 64-bit, little-endian program.
+const RAW_MAGIC_64: u64 = 0xff6c_7072_6f66_7281;
+
+/// `\xfflprofR\x81`, as written by a 32-bit program.
+const RAW_MAGIC_32: u64 = 0xff6c_7072_6f66_5281;
+
+/// The high byte of the version holds variant flags (such as IR-level instrumentation).
+const VERSION_MASK: u64 = 0x00ff_ffff_ffff_ffff;
+
+/// The raw profile version written by LLVM 11 and 12.
+const SUPPORTED_VERSION: u64 = 5;
+
+/// The merged counters of any number of raw profiles.
+#[derive(Default)]
+pub struct ProfileData {
+    /// Counters, keyed by function name hash and function source hash.
+    counters: HashMap<(u64, u64), Vec<u64>>,
+    /// The names of the functions in the merged profiles. The names sections of the objects
+    /// are added to these, so that a function is named even if only one side records it.
+    pub names: FunctionNames,
+}
+
+impl ProfileData {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the counters of a raw profile to those already merged.
+    pub fn merge_raw_profile(&mut self, data: &[u8]) -> Result<()> {
+        let mut reader = Reader::new(data);
+        let magic = reader.read_u64()?;
+        if magic != RAW_MAGIC_64 {
+            return Err(Error::new(if magic == RAW_MAGIC_32 {
+                "raw profiles written by 32-bit programs are not supported"
+            } else if magic.swap_bytes() == RAW_MAGIC_64 {
+                "raw profiles written by big-endian programs are not supported"
+            } else {
+                "not a raw profile (bad magic number)"
+            }));
+        }
+        let version = reader.read_u64()? & VERSION_MASK;
+        if version != SUPPORTED_VERSION {
+            return Err(Error::new(format!(
+                "raw profile version {} is not supported (only version {} is)",
+                version, SUPPORTED_VERSION
+            )));
+        }
+        let data_size = reader.read_u64()?;
+        let padding_bytes_before_counters = reader.read_u64()?;
+        let counters_size = reader.read_u64()?;
+        let padding_bytes_after_counters = reader.read_u64()?;
+        let names_size = reader.read_u64()?;
+        let counters_delta = reader.read_u64()?;
+        let _names_delta = reader.read_u64()?;
+        let _value_kind_last = reader.read_u64()?;
+
+        let mut records = Vec::new();
+        for _ in 0..data_size {
+            let name_ref = reader.read_u64()?;
+            let func_hash = reader.read_u64()?;
+            let counter_ptr = reader.read_u64()?;
+            let _function_pointer = reader.read_u64()?;
+            let _values = reader.read_u64()?;
+            let num_counters = reader.read_u32()?;
+            let _num_value_sites = [reader.read_u16()?, reader.read_u16()?];
+            records.push((name_ref, func_hash, counter_ptr, num_counters));
+        }
+
+        reader.skip(padding_bytes_before_counters)?;
+        let mut counters = Vec::new();
+        for _ in 0..counters_size {
+            counters.push(reader.read_u64()?);
+        }
+        reader.skip(padding_bytes_after_counters)?;
+        self.names.add_names_section(reader.read_bytes(names_size)?)?;
+
+        for (name_ref, func_hash, counter_ptr, num_counters) in records {
+            let start = counter_ptr.wrapping_sub(counters_delta) / 8;
+            let end = start.wrapping_add(u64::from(num_counters));
+            if start > end || end > counters.len() as u64 {
+                return Err(Error::new(format!(
+                    "counters of function {:#x} are outside of the counters section",
+                    name_ref
+                )));
+            }
+            let function_counters = &counters[start as usize..end as usize];
+            let merged = self
+                .counters
+                .entry((name_ref, func_hash))
+                .or_insert_with(|| vec![0; function_counters.len()]);
+            if merged.len() != function_counters.len() {
+                return Err(Error::new(format!(
+                    "function {:#x} has {} counters, but {} in a previous profile",
+                    name_ref,
+                    function_counters.len(),
+                    merged.len()
+                )));
+            }
+            for (merged, &count) in merged.iter_mut().zip(function_counters) {
+                *merged = merged.saturating_add(count);
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the counters of the function with the given name and source hashes, if it was
+    /// ever called.
+    pub fn counters(&self, name_ref: u64, func_hash: u64) -> Option<&[u64]> {
+        self.counters.get(&(name_ref, func_hash)).map(|counters| &counters[..])
+    }
+
+    /// Returns true if the profile has counters for the function, with any source hash.
+    pub fn has_function(&self, name_ref: u64) -> bool {
+        self.counters.keys().any(|&(profile_name_ref, _)| profile_name_ref == name_ref)
+    }
+}
@@ -0,0 +1,88 @@
+use crate::{Error, Result};
+
+/// A cursor over the little-endian, LEB128-heavy encodings used by raw profiles and coverage maps.
+pub(crate) struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    pub(crate) fn remaining(&self) -> usize {
+        self.data.len() - self.pos
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    pub(crate) fn read_bytes(&mut self, len: u64) -> Result<&'a [u8]> {
+        if len > self.remaining() as u64 {
+            return Err(Error::new(format!(
+                "unexpected end of data: {} bytes needed at offset {}, {} available",
+                len,
+                self.pos,
+                self.remaining()
+            )));
+        }
+        let bytes = &self.data[self.pos..self.pos + len as usize];
+        self.pos += len as usize;
+        Ok(bytes)
+    }
+
+    pub(crate) fn skip(&mut self, len: u64) -> Result<()> {
+        self.read_bytes(len).map(|_| ())
+    }
+
+    /// Skips to the next multiple of `align`, relative to the start of the data.
+    pub(crate) fn align(&mut self, align: usize) {
+        let aligned = (self.pos + align - 1) / align * align;
+        self.pos = aligned.min(self.data.len());
+    }
+
+    pub(crate) fn read_u16(&mut self) -> Result<u16> {
+        let mut bytes = [0; 2];
+        bytes.copy_from_slice(self.read_bytes(2)?);
+        Ok(u16::from_le_bytes(bytes))
+    }
+
+    pub(crate) fn read_u32(&mut self) -> Result<u32> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.read_bytes(4)?);
+        Ok(u32::from_le_bytes(bytes))
+    }
+
+    pub(crate) fn read_u64(&mut self) -> Result<u64> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.read_bytes(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+
+    pub(crate) fn read_uleb128(&mut self) -> Result<u64> {
+        let mut result = 0;
+        let mut shift = 0;
+        loop {
+            let byte = self.read_bytes(1)?[0];
+            if shift >= 64 {
+                return Err(Error::new(format!("LEB128 value too large at offset {}", self.pos)));
+            }
+            result |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(result);
+            }
+            shift += 7;
+        }
+    }
+
+    /// Reads a LEB128 value that must fit in a `u32`, like LLVM's `readIntMax()`.
+    pub(crate) fn read_uleb128_u32(&mut self) -> Result<u32> {
+        let value = self.read_uleb128()?;
+        if value > u64::from(u32::MAX) {
+            return Err(Error::new(format!("value {} is out of range at offset {}", value, self.pos)));
+        }
+        Ok(value as u32)
+    }
+}
@@ -0,0 +1,297 @@
+//! Combines coverage maps and profile counters into per-file line, function and branch counts.
+//!
+//! Line counts follow the rules `llvm-cov` uses: the count of a line is the largest count of the
+//! code regions that start on it and of the innermost region that was entered before the line.
+//! Counts of functions with several instantiations (such as generic functions) are summed.
+
+use crate::covmap::{Counter, ExprKind, FunctionRecord, RegionKind};
+use crate::profraw::ProfileData;
+use crate::{Error, FunctionNames, Result};
+use rust_demangler::{create_disambiguator_re, demangle_lines};
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Default)]
+pub struct Coverage {
+    /// The covered files, sorted by path.
+    pub files: Vec<FileCoverage>,
+}
+
+#[derive(Debug, Default)]
+pub struct FileCoverage {
+    pub path: String,
+    /// The execution count of each instrumented line.
+    pub lines: BTreeMap<u32, u64>,
+    /// The functions defined in the file, sorted by line.
+    pub functions: Vec<FunctionCoverage>,
+    /// The branches in the file (only with `-Z instrument-coverage-branches`), sorted by position.
+    pub branches: Vec<BranchCoverage>,
+}
+
+#[derive(Debug)]
+pub struct FunctionCoverage {
+    /// The demangled name of the function.
+    pub name: String,
+    /// The first line of the function.
+    pub line: u32,
+    pub execution_count: u64,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BranchCoverage {
+    pub line: u32,
+    pub column: u32,
+    pub true_count: u64,
+    pub false_count: u64,
+}
+
+impl FileCoverage {
+    pub fn lines_hit(&self) -> usize {
+        self.lines.values().filter(|&&count| count > 0).count()
+    }
+
+    pub fn functions_hit(&self) -> usize {
+        self.functions.iter().filter(|function| function.execution_count > 0).count()
+    }
+
+    /// The number of branch outcomes (each branch has a `true` and a `false` outcome) taken at
+    /// least once.
+    pub fn branch_outcomes_hit(&self) -> usize {
+        self.branches
+            .iter()
+            .map(|branch| (branch.true_count > 0) as usize + (branch.false_count > 0) as usize)
+            .sum()
+    }
+}
+
+/// Builds the coverage report of the functions in `records` (read from one or more binaries),
+/// with the counters in `profile`. Functions that are absent from the profile were never called,
+/// and are reported with zero counts; functions whose source hash does not match the profile
+/// were compiled from different sources than the profiled program, and are ignored.
+///
+/// If `strip_disambiguators` is true, crate disambiguators are removed from the demangled
+/// function names, as `rust-demangler` does by default.
+///
+/// Returns an error if the expressions of a function refer to themselves.
+pub fn build(
+    records: &[FunctionRecord],
+    profile: &ProfileData,
+    names: &FunctionNames,
+    strip_disambiguators: bool,
+) -> Result<Coverage> {
+    // A function can be mapped in several objects; prefer the record that was profiled.
+    let mut unique_records: HashMap<u64, (&FunctionRecord, Option<&[u64]>)> = HashMap::new();
+    for record in records {
+        let counters = profile.counters(record.name_ref, record.func_hash);
+        if counters.is_none() && profile.has_function(record.name_ref) {
+            continue;
+        }
+        let entry = unique_records.entry(record.name_ref).or_insert((record, counters));
+        if entry.1.is_none() && counters.is_some() {
+            *entry = (record, counters);
+        }
+    }
+    let mut unique_records: Vec<_> = unique_records.into_iter().map(|(_, entry)| entry).collect();
+    unique_records.sort_by_key(|(record, _)| record.name_ref);
+
+    let mangled_names: Vec<String> = unique_records
+        .iter()
+        .map(|(record, _)| match names.get(record.name_ref) {
+            Some(name) => name.to_owned(),
+            None => format!("{:#018x}", record.name_ref),
+        })
+        .collect();
+    let demangled_names = demangle_lines(
+        mangled_names.join("\n").lines(),
+        if strip_disambiguators { Some(create_disambiguator_re()) } else { None },
+    );
+
+    let mut files: BTreeMap<&str, FileCoverage> = BTreeMap::new();
+    // The true and false counts of each branch, keyed by file, line and column.
+    let mut branches: HashMap<&str, BranchCounts> = HashMap::new();
+    for ((record, counters), name) in unique_records.into_iter().zip(demangled_names) {
+        let counters = counters.unwrap_or(&[]);
+        let expression_values = evaluate_expressions(record, counters)?;
+        let count = |counter| value(counter, &expression_values, counters);
+
+        let mut function_lines: HashMap<u32, BTreeMap<u32, LineCount>> = HashMap::new();
+        for region in &record.regions {
+            let file_lines = function_lines.entry(region.file_id).or_default();
+            match region.kind {
+                RegionKind::Code => {
+                    let region_count = count(region.counter);
+                    for line in region.start_line..=region.end_line {
+                        file_lines.entry(line).or_default().add_region(
+                            line == region.start_line,
+                            region.start_line,
+                            region_count,
+                        );
+                    }
+                }
+                RegionKind::Skipped => {
+                    for line in region.start_line..=region.end_line {
+                        file_lines.remove(&line);
+                    }
+                }
+                RegionKind::Branch => {
+                    let filename = &record.filenames[region.file_id as usize];
+                    let branch = branches
+                        .entry(filename)
+                        .or_default()
+                        .entry((region.start_line, region.start_col))
+                        .or_default();
+                    branch.0 = branch.0.saturating_add(count(region.counter));
+                    branch.1 = branch.1.saturating_add(count(region.false_counter));
+                }
+                RegionKind::Expansion | RegionKind::Gap => {}
+            }
+        }
+
+        for (file_id, lines) in function_lines {
+            let filename = &record.filenames[file_id as usize];
+            let file = files.entry(filename).or_insert_with(|| FileCoverage {
+                path: filename.clone(),
+                ..FileCoverage::default()
+            });
+            for (line, line_count) in lines {
+                let total = file.lines.entry(line).or_default();
+                *total = total.saturating_add(line_count.count());
+            }
+        }
+
+        // The first region of a function starts at its entry, and counts its executions.
+        if let Some(entry) = record.regions.first() {
+            let filename = &record.filenames[entry.file_id as usize];
+            if let Some(file) = files.get_mut(&filename[..]) {
+                file.functions.push(FunctionCoverage {
+                    name,
+                    line: entry.start_line,
+                    execution_count: count(entry.counter),
+                });
+            }
+        }
+    }
+
+    for (filename, file_branches) in branches {
+        if let Some(file) = files.get_mut(filename) {
+            file.branches = file_branches
+                .into_iter()
+                .map(|((line, column), (true_count, false_count))| BranchCoverage {
+                    line,
+                    column,
+                    true_count,
+                    false_count,
+                })
+                .collect();
+        }
+    }
+
+    let mut files: Vec<_> = files.into_iter().map(|(_, file)| file).collect();
+    for file in &mut files {
+        file.functions.sort_by(|a, b| (a.line, &a.name).cmp(&(b.line, &b.name)));
+    }
+    Ok(Coverage { files })
+}
+
+type BranchCounts = BTreeMap<(u32, u32), (u64, u64)>;
+
+/// The regions of one function covering a line.
+#[derive(Default)]
+struct LineCount {
+    /// The largest count of the regions starting on the line.
+    max_starting: Option<u64>,
+    /// The start line and count of the innermost (latest starting) region that started on an
+    /// earlier line, and so is still active at the start of the line.
+    wrapped: Option<(u32, u64)>,
+}
+
+impl LineCount {
+    fn add_region(&mut self, starts_on_line: bool, start_line: u32, count: u64) {
+        if starts_on_line {
+            self.max_starting = Some(self.max_starting.map_or(count, |max| max.max(count)));
+        } else if self.wrapped.map_or(true, |(wrapped_start, _)| start_line >= wrapped_start) {
+            self.wrapped = Some((start_line, count));
+        }
+    }
+
+    fn count(&self) -> u64 {
+        let wrapped = self.wrapped.map(|(_, count)| count);
+        self.max_starting.max(wrapped).unwrap_or(0)
+    }
+}
+
+/// Evaluates each expression of `record` once. Expressions only refer to earlier expressions in
+/// well-formed coverage maps, but malformed ones may refer to themselves, directly or through
+/// other expressions, which is reported as an error.
+fn evaluate_expressions(record: &FunctionRecord, counters: &[u64]) -> Result<Vec<u64>> {
+    #[derive(Clone, Copy)]
+    enum State {
+        Unvisited,
+        /// The operands of the expression are being evaluated.
+        InProgress,
+        Done(u64),
+    }
+
+    let expressions = &record.expressions;
+    let mut states = vec![State::Unvisited; expressions.len()];
+    // An explicit stack, since long chains of expressions would overflow the call stack.
+    let mut stack = Vec::new();
+    for root in 0..expressions.len() {
+        stack.push(root);
+        while let Some(&index) = stack.last() {
+            let expression = &expressions[index];
+            match states[index] {
+                State::Unvisited => {
+                    states[index] = State::InProgress;
+                    for &operand in &[expression.lhs, expression.rhs] {
+                        if let Counter::Expression(operand) = operand {
+                            match states.get(operand as usize) {
+                                Some(State::Unvisited) => stack.push(operand as usize),
+                                Some(State::InProgress) => {
+                                    return Err(Error::new(format!(
+                                        "expression {} of function {:#x} refers to itself",
+                                        operand, record.name_ref
+                                    )));
+                                }
+                                Some(State::Done(_)) | None => {}
+                            }
+                        }
+                    }
+                }
+                State::InProgress => {
+                    let done = |operand| match operand {
+                        Counter::Expression(operand) => match states.get(operand as usize) {
+                            Some(&State::Done(value)) => value,
+                            _ => 0,
+                        },
+                        counter => value(counter, &[], counters),
+                    };
+                    let (lhs, rhs) = (done(expression.lhs), done(expression.rhs));
+                    states[index] = State::Done(match expression.kind {
+                        ExprKind::Subtract => lhs.saturating_sub(rhs),
+                        ExprKind::Add => lhs.saturating_add(rhs),
+                    });
+                    stack.pop();
+                }
+                State::Done(_) => {
+                    stack.pop();
+                }
+            }
+        }
+    }
+    Ok(states
+        .into_iter()
+        .map(|state| match state {
+            State::Done(value) => value,
+            State::Unvisited | State::InProgress => unreachable!(),
+        })
+        .collect())
+}
+
+/// The value of `counter`, given the values of the expressions of its function.
+fn value(counter: Counter, expression_values: &[u64], counters: &[u64]) -> u64 {
+    match counter {
+        Counter::Zero => 0,
+        Counter::CounterValueReference(index) => counters.get(index as usize).copied().unwrap_or(0),
+        Counter::Expression(index) => expression_values.get(index as usize).copied().unwrap_or(0),
+    }
+}
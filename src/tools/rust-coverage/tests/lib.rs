@@ -0,0 +1,287 @@
+use rust_coverage::covmap::{self, Counter, ExprKind, RegionKind};
+use rust_coverage::profraw::ProfileData;
+use rust_coverage::*;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+const FUNCTION_NAME: &str = "_RNvCs4fqI2P2rA04_4main4main";
+const FUNCTION_HASH: u64 = 0x1234_5678_9abc_def0;
+
+fn uleb128(out: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// Encodes a reference to the counter with the given index.
+fn counter(index: u64) -> u64 {
+    (index << 2) | 1
+}
+
+/// Encodes a reference to the subtract expression with the given index.
+fn subtract(index: u64) -> u64 {
+    (index << 2) | 2
+}
+
+fn align(out: &mut Vec<u8>, align: usize) {
+    while out.len() % align != 0 {
+        out.push(0);
+    }
+}
+
+fn names_section(names: &[&str]) -> Vec<u8> {
+    let joined = names.join("\x01");
+    let mut section = Vec::new();
+    uleb128(&mut section, joined.len() as u64);
+    uleb128(&mut section, 0);
+    section.extend_from_slice(joined.as_bytes());
+    section
+}
+
+/// A raw profile with a single function, with the given counters.
+fn raw_profile(counters: &[u64]) -> Vec<u8> {
+    const COUNTERS_DELTA: u64 = 0x1000;
+    let names = names_section(&[FUNCTION_NAME]);
+    let mut profile = Vec::new();
+    let mut u64s = |values: &[u64]| {
+        for value in values {
+            profile.extend_from_slice(&value.to_le_bytes());
+        }
+    };
+    u64s(&[0xff6c_7072_6f66_7281, 5]);
+    // DataSize, PaddingBytesBeforeCounters, CountersSize, PaddingBytesAfterCounters, NamesSize,
+    // CountersDelta, NamesDelta, ValueKindLast.
+    u64s(&[1, 0, counters.len() as u64, 0, names.len() as u64, COUNTERS_DELTA, 0, 1]);
+    // NameRef, FuncHash, CounterPtr, FunctionPointer, Values.
+    u64s(&[md5_hash(FUNCTION_NAME.as_bytes()), FUNCTION_HASH, COUNTERS_DELTA, 0, 0]);
+    profile.extend_from_slice(&(counters.len() as u32).to_le_bytes());
+    profile.extend_from_slice(&[0; 4]);
+    for counter in counters {
+        profile.extend_from_slice(&counter.to_le_bytes());
+    }
+    profile.extend_from_slice(&names);
+    profile
+}
+
+/// The coverage map of `main`, written the way `rustc_codegen_llvm::coverageinfo::mapgen` does:
+///
+/// ```text
+/// 1 | fn main() {              // #0
+/// 2 |     if cond() {          // branch: #1, #0 - #1
+/// 3 |         hit();           // #1
+/// 4 |     }                    // #0 - #1 (gap), then #0
+/// 5 | }
+/// ```
+fn coverage_sections() -> (Vec<u8>, Vec<u8>) {
+    let mut filenames = Vec::new();
+    uleb128(&mut filenames, 1);
+    let mut uncompressed = Vec::new();
+    uleb128(&mut uncompressed, "src/main.rs".len() as u64);
+    uncompressed.extend_from_slice(b"src/main.rs");
+    uleb128(&mut filenames, uncompressed.len() as u64);
+    uleb128(&mut filenames, 0);
+    filenames.extend_from_slice(&uncompressed);
+
+    let mut covmap = Vec::new();
+    for value in &[0u32, filenames.len() as u32, 0, 4] {
+        covmap.extend_from_slice(&value.to_le_bytes());
+    }
+    covmap.extend_from_slice(&filenames);
+    align(&mut covmap, 8);
+
+    let mut mapping = Vec::new();
+    // One file, with index 0 in the filename table.
+    uleb128(&mut mapping, 1);
+    uleb128(&mut mapping, 0);
+    // One subtract expression: #0 - #1.
+    uleb128(&mut mapping, 1);
+    uleb128(&mut mapping, counter(0));
+    uleb128(&mut mapping, counter(1));
+    // Regions: counter, line delta, start column, number of lines, end column.
+    let regions: &[(&[u64], u64, u64, u64, u64)] = &[
+        (&[counter(0)], 1, 1, 1, 17),
+        (&[4 << 3, counter(1), subtract(0)], 1, 8, 0, 14),
+        (&[counter(1)], 0, 15, 2, 6),
+        (&[subtract(0)], 2, 6, 0, 7 | (1 << 31)),
+        (&[counter(0)], 0, 6, 1, 2),
+    ];
+    uleb128(&mut mapping, regions.len() as u64);
+    for (counters, line_delta, start_col, num_lines, end_col) in regions {
+        for &counter in counters.iter() {
+            uleb128(&mut mapping, counter);
+        }
+        for &value in &[*line_delta, *start_col, *num_lines, *end_col] {
+            uleb128(&mut mapping, value);
+        }
+    }
+
+    let mut covfun = Vec::new();
+    covfun.extend_from_slice(&md5_hash(FUNCTION_NAME.as_bytes()).to_le_bytes());
+    covfun.extend_from_slice(&(mapping.len() as u32).to_le_bytes());
+    covfun.extend_from_slice(&FUNCTION_HASH.to_le_bytes());
+    covfun.extend_from_slice(&md5_hash(&filenames).to_le_bytes());
+    covfun.extend_from_slice(&mapping);
+    align(&mut covfun, 8);
+    (covmap, covfun)
+}
+
+#[test]
+fn test_md5_hash() {
+    assert_eq!(md5_hash(b""), 0x04b2_008f_d98c_1dd4);
+}
+
+#[test]
+fn test_merge_raw_profiles() {
+    let mut profile = ProfileData::new();
+    profile.merge_raw_profile(&raw_profile(&[3, 2])).unwrap();
+    profile.merge_raw_profile(&raw_profile(&[1, 0])).unwrap();
+    let name_ref = md5_hash(FUNCTION_NAME.as_bytes());
+    assert_eq!(profile.counters(name_ref, FUNCTION_HASH), Some(&[4, 2][..]));
+    assert_eq!(profile.counters(name_ref, FUNCTION_HASH + 1), None);
+    assert_eq!(profile.names.get(name_ref), Some(FUNCTION_NAME));
+}
+
+#[test]
+fn test_bad_raw_profile() {
+    let mut profile = ProfileData::new();
+    let error = profile.merge_raw_profile(b"not a profile").unwrap_err();
+    assert_eq!(error.to_string(), "not a raw profile (bad magic number)");
+}
+
+/// A compressed names section, which records `uncompressed_size` as the size of the names.
+fn compressed_names_section(names: &[&str], uncompressed_size: u64) -> Vec<u8> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(names.join("\x01").as_bytes()).unwrap();
+    let compressed = encoder.finish().unwrap();
+    let mut section = Vec::new();
+    uleb128(&mut section, uncompressed_size);
+    uleb128(&mut section, compressed.len() as u64);
+    section.extend_from_slice(&compressed);
+    section
+}
+
+#[test]
+fn test_compressed_names_section() {
+    let size = FUNCTION_NAME.len() as u64 + "\x01main".len() as u64;
+    let mut names = FunctionNames::new();
+    names.add_names_section(&compressed_names_section(&[FUNCTION_NAME, "main"], size)).unwrap();
+    assert_eq!(names.get(md5_hash(FUNCTION_NAME.as_bytes())), Some(FUNCTION_NAME));
+    assert_eq!(names.get(md5_hash(b"main")), Some("main"));
+
+    // The recorded size must not be trusted to allocate memory up front.
+    let error =
+        names.add_names_section(&compressed_names_section(&["main"], u64::MAX / 2)).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!("compressed data decompresses to 4 bytes instead of the recorded {}", u64::MAX / 2)
+    );
+}
+
+#[test]
+fn test_read_coverage_sections() {
+    let (covmap, covfun) = coverage_sections();
+    let records = covmap::read_coverage_sections(&covmap, &covfun).unwrap();
+    assert_eq!(records.len(), 1);
+    let record = &records[0];
+    assert_eq!(record.name_ref, md5_hash(FUNCTION_NAME.as_bytes()));
+    assert_eq!(record.func_hash, FUNCTION_HASH);
+    assert_eq!(record.filenames, ["src/main.rs"]);
+    assert_eq!(record.expressions.len(), 1);
+    assert_eq!(record.expressions[0].kind, ExprKind::Subtract);
+    let kinds: Vec<_> = record.regions.iter().map(|region| region.kind).collect();
+    assert_eq!(
+        kinds,
+        [RegionKind::Code, RegionKind::Branch, RegionKind::Code, RegionKind::Gap, RegionKind::Code]
+    );
+    let branch = &record.regions[1];
+    assert_eq!((branch.start_line, branch.start_col), (2, 8));
+    assert_eq!(branch.counter, Counter::CounterValueReference(1));
+    assert_eq!(branch.false_counter, Counter::Expression(0));
+    let last = &record.regions[4];
+    assert_eq!((last.start_line, last.end_line), (4, 5));
+}
+
+#[test]
+fn test_report() {
+    let mut profile = ProfileData::new();
+    profile.merge_raw_profile(&raw_profile(&[3, 2])).unwrap();
+    let (covmap, covfun) = coverage_sections();
+    let records = covmap::read_coverage_sections(&covmap, &covfun).unwrap();
+    let coverage = report::build(&records, &profile, &profile.names, true).unwrap();
+
+    assert_eq!(coverage.files.len(), 1);
+    let file = &coverage.files[0];
+    assert_eq!(file.path, "src/main.rs");
+    assert_eq!(file.lines.iter().map(|(&line, &count)| (line, count)).collect::<Vec<_>>(), [
+        (1, 3),
+        (2, 3),
+        (3, 2),
+        (4, 3),
+        (5, 3)
+    ]);
+    assert_eq!(file.functions.len(), 1);
+    assert_eq!(file.functions[0].name, "main::main");
+    assert_eq!(file.functions[0].execution_count, 3);
+    assert_eq!(file.branches.len(), 1);
+    assert_eq!((file.branches[0].true_count, file.branches[0].false_count), (2, 1));
+
+    let mut lcov = Vec::new();
+    export::write_lcov(&coverage, &mut lcov).unwrap();
+    assert_eq!(
+        String::from_utf8(lcov).unwrap(),
+        "SF:src/main.rs\n\
+        FN:1,main::main\n\
+        FNDA:3,main::main\n\
+        FNF:1\n\
+        FNH:1\n\
+        BRDA:2,0,0,2\n\
+        BRDA:2,0,1,1\n\
+        BRF:2\n\
+        BRH:2\n\
+        DA:1,3\n\
+        DA:2,3\n\
+        DA:3,2\n\
+        DA:4,3\n\
+        DA:5,3\n\
+        LF:5\n\
+        LH:5\n\
+        end_of_record\n"
+    );
+}
+
+#[test]
+fn test_unexecuted_function() {
+    let (covmap, covfun) = coverage_sections();
+    let records = covmap::read_coverage_sections(&covmap, &covfun).unwrap();
+    let coverage =
+        report::build(&records, &ProfileData::new(), &FunctionNames::new(), true).unwrap();
+    let file = &coverage.files[0];
+    assert_eq!(file.lines_hit(), 0);
+    assert_eq!(file.functions_hit(), 0);
+    assert_eq!(file.functions[0].execution_count, 0);
+}
+
+#[test]
+fn test_self_referencing_expression() {
+    let (covmap, covfun) = coverage_sections();
+    let mut records = covmap::read_coverage_sections(&covmap, &covfun).unwrap();
+    records[0].expressions[0].lhs = Counter::Expression(0);
+    records[0].expressions[0].rhs = Counter::Expression(0);
+    let error =
+        report::build(&records, &ProfileData::new(), &FunctionNames::new(), true).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        format!(
+            "expression 0 of function {:#x} refers to itself",
+            md5_hash(FUNCTION_NAME.as_bytes())
+        )
+    );
+}